@@ -0,0 +1,54 @@
+use crate::activations::DenseActivation;
+use crate::maths::Matrix;
+use crate::registry::{Constructor, Registry};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub trait Activation: Send + Sync {
+    // name written in saved networks, must be registered to be loaded back
+    // and must not contain any whitespace
+    fn name(&self) -> String;
+
+    fn apply(&self, mat: &mut Matrix);
+    fn derivative(&self, mat: &mut Matrix);
+
+    // propagates the gradient with respect to the activated values back to
+    // the raw values, activations coupling their outputs must override it
+    fn backward(&self, raw: &Matrix, gradient: &Matrix) -> Matrix {
+        let mut d_z = raw.clone();
+        self.derivative(&mut d_z);
+        gradient.hadamard_dot(&d_z)
+    }
+}
+
+impl From<DenseActivation> for Arc<dyn Activation> {
+    fn from(activation: DenseActivation) -> Self {
+        Arc::new(activation)
+    }
+}
+
+static REGISTRY: OnceLock<RwLock<Registry<dyn Activation>>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<Registry<dyn Activation>> {
+    REGISTRY.get_or_init(|| {
+        let mut registry: Registry<dyn Activation> = Registry::new();
+        for name in ["Sigmoid", "Relu", "LeakyRelu", "Softmax", "Tanh", "Linear"] {
+            registry.register(name, build_dense_activation);
+        }
+        RwLock::new(registry)
+    })
+}
+
+fn build_dense_activation(descriptor: &str) -> Option<Box<dyn Activation>> {
+    DenseActivation::from_str(descriptor)
+        .ok()
+        .map(|activation| Box::new(activation) as Box<dyn Activation>)
+}
+
+pub fn register_activation(name: &str, constructor: Constructor<dyn Activation>) {
+    registry().write().unwrap().register(name, constructor);
+}
+
+pub fn build_activation(descriptor: &str) -> Option<Arc<dyn Activation>> {
+    registry().read().unwrap().build(descriptor).map(Arc::from)
+}
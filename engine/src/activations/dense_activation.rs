@@ -1,15 +1,20 @@
 use crate::maths::activation::{
-    dleaky_relu, drelu, dsigmoid, dtanh, leaky_relu, relu, sigmoid, tanh,
+    dleaky_relu, dlinear, drelu, dsigmoid, dtanh, leaky_relu, linear, relu, sigmoid, tanh,
 };
+use crate::activations::Activation;
 use crate::maths::Matrix;
+use std::fmt;
 use std::str::FromStr;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DenseActivation {
     Sigmoid,
     Relu,
     LeakyRelu,
     Softmax,
     Tanh,
+    // identity, for outputs that are raw scores such as logits or margins
+    Linear,
 }
 
 impl FromStr for DenseActivation {
@@ -22,56 +27,93 @@ impl FromStr for DenseActivation {
             "LeakyRelu" => Ok(DenseActivation::LeakyRelu),
             "Softmax" => Ok(DenseActivation::Softmax),
             "Tanh" => Ok(DenseActivation::Tanh),
+            "Linear" => Ok(DenseActivation::Linear),
             _ => Err(()),
         }
     }
 }
 
-impl ToString for DenseActivation {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for DenseActivation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
             DenseActivation::Sigmoid => "Sigmoid",
             DenseActivation::Relu => "Relu",
             DenseActivation::LeakyRelu => "LeakyRelu",
             DenseActivation::Softmax => "Softmax",
             DenseActivation::Tanh => "Tanh",
-        }
-        .to_string()
+            DenseActivation::Linear => "Linear",
+        };
+        write!(f, "{}", name)
     }
 }
 
-impl DenseActivation {
-    pub fn apply(&self, mat: &mut Matrix) {
+impl Activation for DenseActivation {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn apply(&self, mat: &mut Matrix) {
         match self {
             DenseActivation::Sigmoid => mat.map(sigmoid),
             DenseActivation::Relu => mat.map(relu),
             DenseActivation::LeakyRelu => mat.map(leaky_relu),
             DenseActivation::Softmax => softmax_matrix(mat),
             DenseActivation::Tanh => mat.map(tanh),
+            DenseActivation::Linear => mat.map(linear),
         };
     }
 
-    pub fn derivative(&self, mat: &mut Matrix) {
+    fn derivative(&self, mat: &mut Matrix) {
         match self {
             DenseActivation::Sigmoid => mat.map(dsigmoid),
             DenseActivation::Relu => mat.map(drelu),
             DenseActivation::LeakyRelu => mat.map(dleaky_relu),
             DenseActivation::Softmax => dsoftmax_matrix(mat),
             DenseActivation::Tanh => mat.map(dtanh),
+            DenseActivation::Linear => mat.map(dlinear),
         };
     }
+
+    fn backward(&self, raw: &Matrix, gradient: &Matrix) -> Matrix {
+        match self {
+            DenseActivation::Softmax => softmax_backward(raw, gradient),
+            _ => {
+                let mut d_z = raw.clone();
+                self.derivative(&mut d_z);
+                gradient.hadamard_dot(&d_z)
+            }
+        }
+    }
 }
 
 fn softmax_matrix(mat: &mut Matrix) -> &Matrix {
-    mat.map(|x| x.exp());
-    mat.map2::<f64>(|x, y| (x / y), mat.sum());
+    // shifting by the maximum keeps exp from overflowing
+    let mut max = f64::NEG_INFINITY;
+    for i in 0..mat.len() {
+        max = max.max(mat.get(i));
+    }
+    mat.map2::<f64>(|x, m| (x - m).exp(), max);
+    mat.map2::<f64>(|x, y| x / y, mat.sum());
     mat
 }
 
+// diagonal of the softmax jacobian
 fn dsoftmax_matrix(mat: &mut Matrix) -> &Matrix {
-
-    mat.map(|x| x.exp());
-    let sum = mat.sum();
-    mat.map3::<f64, f64>(|x,y,z| (y * x - x.powi(2)) / z, sum, sum.powi(2));
+    softmax_matrix(mat);
+    mat.map(|p| p * (1.0 - p));
     mat
 }
+
+// product of the full softmax jacobian with the gradient:
+// d_i = p_i * (g_i - sum_j(g_j * p_j))
+fn softmax_backward(raw: &Matrix, gradient: &Matrix) -> Matrix {
+    let mut p = raw.clone();
+    softmax_matrix(&mut p);
+    let weighted_sum = p.hadamard_dot(gradient).sum();
+
+    let mut delta = Matrix::new(p.w, p.h);
+    for i in 0..p.len() {
+        delta.set(i, p.get(i) * (gradient.get(i) - weighted_sum));
+    }
+    delta
+}
@@ -1,2 +1,4 @@
+mod activation;
 mod dense_activation;
+pub use activation::{build_activation, register_activation, Activation};
 pub use dense_activation::DenseActivation;
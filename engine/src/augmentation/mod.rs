@@ -0,0 +1,94 @@
+mod transforms;
+
+use crate::maths::Matrix;
+use crate::shapes::DenseShape;
+pub use transforms::{
+    Cutout, ElasticDistortion, GaussianNoise, HorizontalFlip, RandomRotation, RandomScale,
+    RandomShift,
+};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::Arc;
+
+// a random change of an image, drawn from rng. Images are column matrices
+// of shape.y rows of shape.x pixels, one after the other, each pixel
+// holding its shape.z channels.
+pub trait Transform: Send + Sync {
+    fn apply(&self, image: &Matrix, shape: &DenseShape, rng: &mut StdRng) -> Matrix;
+}
+
+// transforms applied one after the other to the inputs of the samples. The
+// draws of a sample only depend on the seed, the epoch and the index of the
+// sample, whatever the order or the thread it is loaded in. Without a seed
+// of its own, an augmentation takes the seed of the session it is given to.
+#[derive(Clone)]
+pub struct Augmentation {
+    shape: DenseShape,
+    transforms: Vec<Arc<dyn Transform>>,
+    seed: Option<u64>,
+}
+
+impl Augmentation {
+    pub fn new(shape: DenseShape) -> Augmentation {
+        Augmentation {
+            shape,
+            transforms: vec![],
+            seed: None,
+        }
+    }
+
+    pub fn with_transform<T: Transform + 'static>(mut self, transform: T) -> Augmentation {
+        self.transforms.push(Arc::new(transform));
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Augmentation {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn shape(&self) -> &DenseShape {
+        &self.shape
+    }
+
+    pub fn apply(&self, image: &Matrix, rng: &mut StdRng) -> Matrix {
+        assert_eq!(
+            image.len(),
+            self.shape.range,
+            "The image does not match the shape of the augmentation"
+        );
+        let mut image = image.clone();
+        for transform in &self.transforms {
+            image = transform.apply(&image, &self.shape, rng);
+        }
+        image
+    }
+
+    // the sample with its input augmented for the given epoch
+    pub fn augment(
+        &self,
+        sample: &(Matrix, Matrix),
+        epoch: usize,
+        index: usize,
+    ) -> (Matrix, Matrix) {
+        let seed = self.seed.unwrap_or_default();
+        let mut rng = StdRng::seed_from_u64(sample_seed(seed, epoch, index));
+        (self.apply(&sample.0, &mut rng), sample.1.clone())
+    }
+}
+
+// mixes the seed, epoch and index so that neighbouring samples and epochs
+// get unrelated draws
+fn sample_seed(seed: u64, epoch: usize, index: usize) -> u64 {
+    let mut z = seed
+        ^ (epoch as u64).wrapping_mul(0x9E3779B97F4A7C15)
+        ^ (index as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
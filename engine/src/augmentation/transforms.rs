@@ -0,0 +1,273 @@
+use crate::augmentation::Transform;
+use crate::maths::Matrix;
+use crate::shapes::DenseShape;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::f64::consts::PI;
+
+// moves the image by up to max_x pixels horizontally and max_y vertically,
+// the uncovered pixels being 0
+#[derive(Clone, Debug)]
+pub struct RandomShift {
+    max_x: usize,
+    max_y: usize,
+}
+
+impl RandomShift {
+    pub fn new(max_x: usize, max_y: usize) -> RandomShift {
+        RandomShift { max_x, max_y }
+    }
+}
+
+impl Transform for RandomShift {
+    fn apply(&self, image: &Matrix, shape: &DenseShape, rng: &mut StdRng) -> Matrix {
+        let dx = rng.gen_range(-(self.max_x as i64)..=self.max_x as i64) as f64;
+        let dy = rng.gen_range(-(self.max_y as i64)..=self.max_y as i64) as f64;
+        remap(image, shape, |row, column| (row - dy, column - dx))
+    }
+}
+
+// turns the image around its center by up to max_degrees either way
+#[derive(Clone, Debug)]
+pub struct RandomRotation {
+    max_degrees: f64,
+}
+
+impl RandomRotation {
+    pub fn new(max_degrees: f64) -> RandomRotation {
+        RandomRotation { max_degrees }
+    }
+}
+
+impl Transform for RandomRotation {
+    fn apply(&self, image: &Matrix, shape: &DenseShape, rng: &mut StdRng) -> Matrix {
+        let angle = rng.gen_range(-self.max_degrees..=self.max_degrees) * PI / 180.0;
+        let (sin, cos) = angle.sin_cos();
+        let (center_row, center_column) = center(shape);
+        // every pixel takes the value found by turning it back
+        remap(image, shape, |row, column| {
+            let (y, x) = (row - center_row, column - center_column);
+            (
+                center_row + cos * y - sin * x,
+                center_column + sin * y + cos * x,
+            )
+        })
+    }
+}
+
+// zooms in or out of the center of the image by a factor between min and max
+#[derive(Clone, Debug)]
+pub struct RandomScale {
+    min: f64,
+    max: f64,
+}
+
+impl RandomScale {
+    pub fn new(min: f64, max: f64) -> RandomScale {
+        assert!(
+            0.0 < min && min <= max,
+            "The scales must be positive and ordered"
+        );
+        RandomScale { min, max }
+    }
+}
+
+impl Transform for RandomScale {
+    fn apply(&self, image: &Matrix, shape: &DenseShape, rng: &mut StdRng) -> Matrix {
+        let scale = rng.gen_range(self.min..=self.max);
+        let (center_row, center_column) = center(shape);
+        remap(image, shape, |row, column| {
+            (
+                center_row + (row - center_row) / scale,
+                center_column + (column - center_column) / scale,
+            )
+        })
+    }
+}
+
+// moves every pixel along a random field smoothed by a gaussian of
+// deviation sigma and scaled by alpha, as in Simard et al. 2003
+#[derive(Clone, Debug)]
+pub struct ElasticDistortion {
+    alpha: f64,
+    sigma: f64,
+}
+
+impl ElasticDistortion {
+    pub fn new(alpha: f64, sigma: f64) -> ElasticDistortion {
+        assert!(
+            sigma > 0.0,
+            "The deviation of the smoothing must be positive"
+        );
+        ElasticDistortion { alpha, sigma }
+    }
+}
+
+impl Transform for ElasticDistortion {
+    fn apply(&self, image: &Matrix, shape: &DenseShape, rng: &mut StdRng) -> Matrix {
+        let size = shape.x * shape.y;
+        let mut field = || {
+            let noise = (0..size)
+                .map(|_| rng.gen_range(-1.0..=1.0))
+                .collect::<Vec<f64>>();
+            smooth(&noise, shape, self.sigma)
+        };
+        let (dy, dx) = (field(), field());
+        remap(image, shape, |row, column| {
+            let pixel = row as usize * shape.x + column as usize;
+            (
+                row + self.alpha * dy[pixel],
+                column + self.alpha * dx[pixel],
+            )
+        })
+    }
+}
+
+// adds a normal noise of deviation std_dev to every value
+#[derive(Clone, Debug)]
+pub struct GaussianNoise {
+    std_dev: f64,
+}
+
+impl GaussianNoise {
+    pub fn new(std_dev: f64) -> GaussianNoise {
+        GaussianNoise { std_dev }
+    }
+}
+
+impl Transform for GaussianNoise {
+    fn apply(&self, image: &Matrix, _: &DenseShape, rng: &mut StdRng) -> Matrix {
+        let mut result = image.clone();
+        for i in 0..image.len() {
+            // Box-Muller transform of two uniform draws
+            let (u, v): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+            let normal = (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos();
+            result.set(i, image.get(i) + self.std_dev * normal);
+        }
+        result
+    }
+}
+
+// sets a square of size pixels around a random pixel to 0, the square
+// being cut by the borders of the image
+#[derive(Clone, Debug)]
+pub struct Cutout {
+    size: usize,
+}
+
+impl Cutout {
+    pub fn new(size: usize) -> Cutout {
+        Cutout { size }
+    }
+}
+
+impl Transform for Cutout {
+    fn apply(&self, image: &Matrix, shape: &DenseShape, rng: &mut StdRng) -> Matrix {
+        let mut result = image.clone();
+        if shape.x == 0 || shape.y == 0 {
+            return result;
+        }
+        let (row, column) = (rng.gen_range(0..shape.y), rng.gen_range(0..shape.x));
+        let (top, left) = (
+            row.saturating_sub(self.size / 2),
+            column.saturating_sub(self.size / 2),
+        );
+        let bottom = (row + self.size.div_ceil(2)).min(shape.y);
+        let right = (column + self.size.div_ceil(2)).min(shape.x);
+        for y in top..bottom {
+            for x in left..right {
+                for channel in 0..shape.z {
+                    result.set((y * shape.x + x) * shape.z + channel, 0.0);
+                }
+            }
+        }
+        result
+    }
+}
+
+// mirrors the image left to right with the given probability. Digits are
+// not symmetric, this is meant for images whose class does not depend on
+// their orientation.
+#[derive(Clone, Debug)]
+pub struct HorizontalFlip {
+    probability: f64,
+}
+
+impl HorizontalFlip {
+    pub fn new(probability: f64) -> HorizontalFlip {
+        HorizontalFlip { probability }
+    }
+}
+
+impl Transform for HorizontalFlip {
+    fn apply(&self, image: &Matrix, shape: &DenseShape, rng: &mut StdRng) -> Matrix {
+        if !rng.gen_bool(self.probability) {
+            return image.clone();
+        }
+        remap(image, shape, |row, column| {
+            (row, (shape.x - 1) as f64 - column)
+        })
+    }
+}
+
+fn center(shape: &DenseShape) -> (f64, f64) {
+    ((shape.y as f64 - 1.0) / 2.0, (shape.x as f64 - 1.0) / 2.0)
+}
+
+// every pixel of the result takes the value of the image at the position
+// given by source, interpolated between the 4 closest pixels, outside of
+// the image being 0
+fn remap<F: Fn(f64, f64) -> (f64, f64)>(image: &Matrix, shape: &DenseShape, source: F) -> Matrix {
+    let mut result = Matrix::new(1, image.len());
+    let value = |row: i64, column: i64, channel: usize| {
+        if row < 0 || column < 0 || row >= shape.y as i64 || column >= shape.x as i64 {
+            0.0
+        } else {
+            image.get((row as usize * shape.x + column as usize) * shape.z + channel)
+        }
+    };
+    for row in 0..shape.y {
+        for column in 0..shape.x {
+            let (y, x) = source(row as f64, column as f64);
+            let (top, left) = (y.floor(), x.floor());
+            let (dy, dx) = (y - top, x - left);
+            let (top, left) = (top as i64, left as i64);
+            for channel in 0..shape.z {
+                let interpolated = value(top, left, channel) * (1.0 - dy) * (1.0 - dx)
+                    + value(top, left + 1, channel) * (1.0 - dy) * dx
+                    + value(top + 1, left, channel) * dy * (1.0 - dx)
+                    + value(top + 1, left + 1, channel) * dy * dx;
+                result.set((row * shape.x + column) * shape.z + channel, interpolated);
+            }
+        }
+    }
+    result
+}
+
+// gaussian blur of one value per pixel, rows then columns
+fn smooth(values: &[f64], shape: &DenseShape, sigma: f64) -> Vec<f64> {
+    let radius = (3.0 * sigma).ceil() as i64;
+    let kernel = (-radius..=radius)
+        .map(|d| (-(d * d) as f64 / (2.0 * sigma * sigma)).exp())
+        .collect::<Vec<_>>();
+    let total = kernel.iter().sum::<f64>();
+
+    let blur = |values: &[f64], step: (i64, i64)| {
+        let mut result = vec![0.0; values.len()];
+        for row in 0..shape.y as i64 {
+            for column in 0..shape.x as i64 {
+                let mut sum = 0.0;
+                for (k, weight) in (-radius..=radius).zip(&kernel) {
+                    let (y, x) = (row + k * step.0, column + k * step.1);
+                    if y >= 0 && x >= 0 && y < shape.y as i64 && x < shape.x as i64 {
+                        sum += weight * values[(y * shape.x as i64 + x) as usize];
+                    }
+                }
+                result[(row * shape.x as i64 + column) as usize] = sum / total;
+            }
+        }
+        result
+    };
+    blur(&blur(values, (0, 1)), (1, 0))
+}
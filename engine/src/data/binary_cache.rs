@@ -0,0 +1,306 @@
+use crate::data::Dataset;
+use crate::maths::Matrix;
+use crate::shapes::DenseShape;
+
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// a cache file is a header of HEADER_SIZE bytes followed by the inputs then
+// outputs of every sample, little endian. The header holds the magic string,
+// the version, the size of the elements, the number of samples and the
+// x, y, z of the input and output shapes.
+const CACHE_MAGIC: &[u8] = b"BRICKSDS";
+const CACHE_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CacheType {
+    F32,
+    F64,
+}
+
+impl CacheType {
+    fn from_size(size: u8) -> Option<CacheType> {
+        match size {
+            4 => Some(CacheType::F32),
+            8 => Some(CacheType::F64),
+            _ => None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            CacheType::F32 => 4,
+            CacheType::F64 => 8,
+        }
+    }
+
+    fn read(&self, bytes: &[u8]) -> f64 {
+        match self {
+            CacheType::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            CacheType::F64 => f64::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    fn write(&self, value: f64, bytes: &mut Vec<u8>) {
+        match self {
+            CacheType::F32 => bytes.extend_from_slice(&(value as f32).to_le_bytes()),
+            CacheType::F64 => bytes.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CacheError {
+    Io(String),
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnknownType(u8),
+    // bytes expected from the header against the bytes of the file
+    Truncated(usize, usize),
+    TrailingBytes(usize),
+    // index of a sample whose size differs from the shapes of the cache
+    BadSample(usize),
+    // the shapes of the header give samples too large to be addressed
+    ShapeOverflow,
+}
+
+impl Display for CacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::Io(error) => write!(f, "Could not access the cache file: {}", error),
+            CacheError::BadMagic => write!(f, "Not a dataset cache file"),
+            CacheError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported cache version {}", version)
+            }
+            CacheError::UnknownType(size) => write!(f, "Unknown element size {}", size),
+            CacheError::Truncated(expected, found) => write!(
+                f,
+                "Truncated cache file: {} bytes expected, {} found",
+                expected, found
+            ),
+            CacheError::TrailingBytes(count) => {
+                write!(f, "{} unexpected bytes at the end of the cache file", count)
+            }
+            CacheError::BadSample(index) => {
+                write!(f, "Sample {} does not match the shapes of the cache", index)
+            }
+            CacheError::ShapeOverflow => write!(f, "The shapes of the cache are too large"),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<std::io::Error> for CacheError {
+    fn from(error: std::io::Error) -> CacheError {
+        CacheError::Io(error.to_string())
+    }
+}
+
+pub struct CacheHeader {
+    pub nb_samples: usize,
+    pub input_shape: DenseShape,
+    pub output_shape: DenseShape,
+    pub element_type: CacheType,
+}
+
+impl CacheHeader {
+    fn parse(bytes: &[u8]) -> Result<CacheHeader, CacheError> {
+        if !bytes.starts_with(CACHE_MAGIC) {
+            return Err(CacheError::BadMagic);
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(CacheError::Truncated(HEADER_SIZE, bytes.len()));
+        }
+        let read_u32 = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let version = read_u32(8);
+        if version != CACHE_VERSION {
+            return Err(CacheError::UnsupportedVersion(version));
+        }
+        let element_type =
+            CacheType::from_size(bytes[12]).ok_or(CacheError::UnknownType(bytes[12]))?;
+        let nb_samples = u64::from_le_bytes(bytes[16..24].try_into().unwrap()) as usize;
+        // a corrupt header must not overflow the sizes of the samples
+        let shape = |at: usize| {
+            let (x, y, z) = (
+                read_u32(at) as usize,
+                read_u32(at + 4) as usize,
+                read_u32(at + 8) as usize,
+            );
+            x.checked_mul(y)
+                .and_then(|range| range.checked_mul(z))
+                .map(|_| DenseShape::new(x, y, z))
+                .ok_or(CacheError::ShapeOverflow)
+        };
+        let header = CacheHeader {
+            nb_samples,
+            input_shape: shape(24)?,
+            output_shape: shape(36)?,
+            element_type,
+        };
+        header
+            .input_shape
+            .range
+            .checked_add(header.output_shape.range)
+            .and_then(|range| range.checked_mul(element_type.size()))
+            .ok_or(CacheError::ShapeOverflow)?;
+        Ok(header)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = CACHE_MAGIC.to_vec();
+        bytes.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&[self.element_type.size() as u8, 0, 0, 0]);
+        bytes.extend_from_slice(&(self.nb_samples as u64).to_le_bytes());
+        for shape in [&self.input_shape, &self.output_shape] {
+            for size in [shape.x, shape.y, shape.z] {
+                bytes.extend_from_slice(&(size as u32).to_le_bytes());
+            }
+        }
+        bytes.resize(HEADER_SIZE, 0);
+        bytes
+    }
+
+    // bytes of the inputs and outputs of one sample
+    fn sample_size(&self) -> usize {
+        (self.input_shape.range + self.output_shape.range) * self.element_type.size()
+    }
+}
+
+// converts a dataset, such as a FileDataset of a .dat file, into a cache
+// file. The shapes are those of the first sample unless given.
+pub struct CacheWriter {
+    element_type: CacheType,
+    shapes: Option<(DenseShape, DenseShape)>,
+}
+
+impl CacheWriter {
+    pub fn new(element_type: CacheType) -> CacheWriter {
+        CacheWriter {
+            element_type,
+            shapes: None,
+        }
+    }
+
+    // shapes of the samples, such as 28x28 images, kept in the header
+    pub fn with_shapes(mut self, input_shape: DenseShape, output_shape: DenseShape) -> CacheWriter {
+        self.shapes = Some((input_shape, output_shape));
+        self
+    }
+
+    // the samples are written one at a time, the dataset is never held in
+    // memory as a whole
+    pub fn write<D: Dataset + ?Sized>(
+        &self,
+        dataset: &D,
+        path: &str,
+    ) -> Result<CacheHeader, CacheError> {
+        let (input_shape, output_shape) = match &self.shapes {
+            Some((input, output)) => (
+                DenseShape::new(input.x, input.y, input.z),
+                DenseShape::new(output.x, output.y, output.z),
+            ),
+            None if dataset.is_empty() => (DenseShape::one_d(0), DenseShape::one_d(0)),
+            None => {
+                let sample = dataset.get(0);
+                (
+                    DenseShape::one_d(sample.0.len()),
+                    DenseShape::one_d(sample.1.len()),
+                )
+            }
+        };
+        let header = CacheHeader {
+            nb_samples: dataset.len(),
+            input_shape,
+            output_shape,
+            element_type: self.element_type,
+        };
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&header.to_bytes())?;
+        let mut bytes = Vec::with_capacity(header.sample_size());
+        for index in 0..dataset.len() {
+            let sample = dataset.get(index);
+            let (input, output) = (&sample.0, &sample.1);
+            if input.len() != header.input_shape.range || output.len() != header.output_shape.range
+            {
+                return Err(CacheError::BadSample(index));
+            }
+            bytes.clear();
+            for matrix in [input, output] {
+                for i in 0..matrix.len() {
+                    self.element_type.write(matrix.get(i), &mut bytes);
+                }
+            }
+            file.write_all(&bytes)?;
+        }
+        file.flush()?;
+        Ok(header)
+    }
+}
+
+// a cache file mapped in memory, each sample being read from the pages of
+// the file when it is accessed
+pub struct MappedDataset {
+    map: Mmap,
+    header: CacheHeader,
+}
+
+impl MappedDataset {
+    pub fn open(path: &str) -> Result<MappedDataset, CacheError> {
+        let file = File::open(path)?;
+        // the cache files are only written once, by CacheWriter, and must not
+        // be modified while they are mapped
+        let map = unsafe { Mmap::map(&file)? };
+        let header = CacheHeader::parse(&map)?;
+
+        let size = header
+            .sample_size()
+            .checked_mul(header.nb_samples)
+            .and_then(|size| size.checked_add(HEADER_SIZE))
+            .unwrap_or(usize::MAX);
+        if map.len() < size {
+            return Err(CacheError::Truncated(size, map.len()));
+        }
+        if map.len() > size {
+            return Err(CacheError::TrailingBytes(map.len() - size));
+        }
+        Ok(MappedDataset { map, header })
+    }
+
+    pub fn header(&self) -> &CacheHeader {
+        &self.header
+    }
+}
+
+impl Dataset for MappedDataset {
+    fn len(&self) -> usize {
+        self.header.nb_samples
+    }
+
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)> {
+        assert!(index < self.len(), "Sample index out of the dataset");
+        let start = HEADER_SIZE + index * self.header.sample_size();
+        let size = self.header.element_type.size();
+        let values = |offset: usize, length: usize| {
+            let values = self.map[offset..offset + length * size]
+                .chunks(size)
+                .map(|value| self.header.element_type.read(value))
+                .collect();
+            Matrix::reshape(values, 1, length)
+        };
+
+        let (i_length, o_length) = (
+            self.header.input_shape.range,
+            self.header.output_shape.range,
+        );
+        Cow::Owned((
+            values(start, i_length),
+            values(start + i_length * size, o_length),
+        ))
+    }
+}
@@ -0,0 +1,31 @@
+use crate::maths::Matrix;
+use crate::metrics::class_of;
+
+// weights each class by n_samples / (n_classes * n_samples_of_class), so
+// every class contributes as much to the loss. Absent classes weigh 0.
+pub fn balanced_class_weights(data: &[(Matrix, Matrix)]) -> Matrix {
+    assert!(
+        !data.is_empty(),
+        "Cannot compute class weights without data"
+    );
+
+    let nb_outputs = data[0].1.len();
+    let nb_classes = if nb_outputs == 1 { 2 } else { nb_outputs };
+    let mut counts = vec![0usize; nb_classes];
+    for (_, expected) in data {
+        counts[class_of(expected)] += 1;
+    }
+
+    let weights = counts
+        .iter()
+        .map(|&count| {
+            if count == 0 {
+                0.0
+            } else {
+                data.len() as f64 / (nb_classes * count) as f64
+            }
+        })
+        .collect::<Vec<f64>>();
+
+    Matrix::from(weights)
+}
@@ -0,0 +1,353 @@
+use crate::data::Dataset;
+use crate::maths::Matrix;
+
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::fs;
+
+// a column of a CSV file, by its name in the header or its index from 0
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Column {
+        Column::Name(name.to_string())
+    }
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Column {
+        Column::Index(index)
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Column::Name(name) => write!(f, "{}", name),
+            Column::Index(index) => write!(f, "#{}", index),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CsvError {
+    Io(String),
+    UnknownColumn(Column),
+    // line number, from 1 and counting the header, and why each rejected
+    // row was rejected
+    BadRows(Vec<(usize, String)>),
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsvError::Io(error) => write!(f, "Could not read the CSV file: {}", error),
+            CsvError::UnknownColumn(column) => write!(f, "Unknown column {}", column),
+            CsvError::BadRows(rows) => {
+                write!(f, "{} bad rows", rows.len())?;
+                for (line, reason) in rows {
+                    write!(f, "\nline {}: {}", line, reason)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+// samples read from a CSV file, in the format of load_data: the features
+// and targets of a row as column matrices
+#[derive(Clone, Default)]
+pub struct CsvData {
+    pub samples: Vec<(Matrix, Matrix)>,
+    pub feature_names: Vec<String>,
+    pub target_names: Vec<String>,
+    // labels of each one-hot encoded target, in the order of its outputs
+    pub classes: Vec<Option<Vec<String>>>,
+    // rows left out by a loader skipping bad rows
+    pub skipped: Vec<(usize, String)>,
+}
+
+impl Dataset for CsvData {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)> {
+        Cow::Borrowed(&self.samples[index])
+    }
+}
+
+// reads the feature and target columns of a CSV file. Every other column
+// is a feature unless features are given. A target holding values that are
+// not numbers is one-hot encoded, its classes being sorted. Empty features
+// are read as NaN, to be imputed afterwards.
+#[derive(Clone, Debug)]
+pub struct CsvLoader {
+    targets: Vec<Column>,
+    features: Option<Vec<Column>>,
+    delimiter: char,
+    has_header: bool,
+    one_hot: bool,
+    skip_bad_rows: bool,
+}
+
+impl CsvLoader {
+    pub fn new<C: Into<Column>>(targets: Vec<C>) -> CsvLoader {
+        CsvLoader {
+            targets: targets.into_iter().map(Into::into).collect(),
+            features: None,
+            delimiter: ',',
+            has_header: true,
+            one_hot: false,
+            skip_bad_rows: false,
+        }
+    }
+
+    pub fn with_features<C: Into<Column>>(mut self, features: Vec<C>) -> CsvLoader {
+        self.features = Some(features.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> CsvLoader {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_header(mut self, has_header: bool) -> CsvLoader {
+        self.has_header = has_header;
+        self
+    }
+
+    // one-hot encodes the targets even when they are numbers, such as the
+    // indices of classes
+    pub fn with_one_hot(mut self, one_hot: bool) -> CsvLoader {
+        self.one_hot = one_hot;
+        self
+    }
+
+    // leaves the bad rows out, listing them in the data, instead of failing
+    pub fn with_skip_bad_rows(mut self, skip_bad_rows: bool) -> CsvLoader {
+        self.skip_bad_rows = skip_bad_rows;
+        self
+    }
+
+    pub fn load(&self, path: &str) -> Result<CsvData, CsvError> {
+        let contents = fs::read_to_string(path).map_err(|e| CsvError::Io(e.to_string()))?;
+        self.parse(&contents)
+    }
+
+    pub fn parse(&self, contents: &str) -> Result<CsvData, CsvError> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.trim_end_matches('\r')))
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let mut header = None;
+        if self.has_header {
+            if let Some((number, line)) = lines.next() {
+                let fields = split_fields(line, self.delimiter)
+                    .map_err(|reason| CsvError::BadRows(vec![(number, reason)]))?;
+                header = Some(fields);
+            }
+        }
+
+        let mut bad_rows = vec![];
+        let mut rows = vec![];
+        for (number, line) in lines {
+            match split_fields(line, self.delimiter) {
+                Ok(fields) => rows.push((number, fields)),
+                Err(reason) => bad_rows.push((number, reason)),
+            }
+        }
+        let width = match (&header, rows.first()) {
+            (Some(header), _) => header.len(),
+            (None, Some((_, fields))) => fields.len(),
+            (None, None) => 0,
+        };
+
+        let targets = self
+            .targets
+            .iter()
+            .map(|column| resolve(column, header.as_deref(), width))
+            .collect::<Result<Vec<_>, _>>()?;
+        let features = match &self.features {
+            Some(features) => features
+                .iter()
+                .map(|column| resolve(column, header.as_deref(), width))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => (0..width).filter(|i| !targets.contains(i)).collect(),
+        };
+        let names = match header {
+            Some(header) => header,
+            None => (0..width).map(|index| index.to_string()).collect(),
+        };
+
+        // rows whose features are numbers and targets are not empty
+        let mut valid = vec![];
+        for (line, fields) in rows {
+            match check_row(&fields, &names, &features, &targets) {
+                Ok(values) => valid.push((values, fields)),
+                Err(reason) => bad_rows.push((line, reason)),
+            }
+        }
+        if !bad_rows.is_empty() && !self.skip_bad_rows {
+            bad_rows.sort_unstable();
+            return Err(CsvError::BadRows(bad_rows));
+        }
+
+        let classes = targets
+            .iter()
+            .map(|&target| {
+                let values = valid.iter().map(|(_, fields)| fields[target].as_str());
+                self.classes(values)
+            })
+            .collect::<Vec<_>>();
+
+        let samples = valid
+            .into_iter()
+            .map(|(inputs, fields)| {
+                let mut outputs = vec![];
+                for (&target, classes) in targets.iter().zip(&classes) {
+                    let value = &fields[target];
+                    match classes {
+                        Some(classes) => outputs
+                            .extend(classes.iter().map(|class| (class == value) as usize as f64)),
+                        None => outputs.push(value.parse::<f64>().unwrap()),
+                    }
+                }
+                let (i_length, o_length) = (inputs.len(), outputs.len());
+                (
+                    Matrix::reshape(inputs, 1, i_length),
+                    Matrix::reshape(outputs, 1, o_length),
+                )
+            })
+            .collect();
+
+        bad_rows.sort_unstable();
+        Ok(CsvData {
+            samples,
+            feature_names: features.iter().map(|&i| names[i].clone()).collect(),
+            target_names: targets.iter().map(|&i| names[i].clone()).collect(),
+            classes,
+            skipped: bad_rows,
+        })
+    }
+
+    // sorted labels of a target to one-hot encode, None for a number
+    fn classes<'a, I: Iterator<Item = &'a str> + Clone>(&self, values: I) -> Option<Vec<String>> {
+        let numeric = values.clone().all(|value| value.parse::<f64>().is_ok());
+        if numeric && !self.one_hot {
+            return None;
+        }
+
+        let mut classes = values.map(str::to_string).collect::<Vec<_>>();
+        if numeric {
+            classes.sort_by(|a, b| a.parse::<f64>().unwrap().total_cmp(&b.parse().unwrap()));
+        } else {
+            classes.sort_unstable();
+        }
+        classes.dedup();
+        Some(classes)
+    }
+}
+
+// the features of the row, once it is known to be valid
+fn check_row(
+    fields: &[String],
+    names: &[String],
+    features: &[usize],
+    targets: &[usize],
+) -> Result<Vec<f64>, String> {
+    if fields.len() != names.len() {
+        return Err(format!(
+            "expected {} fields, found {}",
+            names.len(),
+            fields.len()
+        ));
+    }
+    if let Some(&target) = targets.iter().find(|&&target| fields[target].is_empty()) {
+        return Err(format!("missing target {}", names[target]));
+    }
+    features
+        .iter()
+        .map(|&feature| match fields[feature].as_str() {
+            "" => Ok(f64::NAN),
+            value => value
+                .parse::<f64>()
+                .map_err(|_| format!("{:?} in column {} is not a number", value, names[feature])),
+        })
+        .collect()
+}
+
+fn resolve(column: &Column, header: Option<&[String]>, width: usize) -> Result<usize, CsvError> {
+    let index = match (column, header) {
+        (Column::Index(index), _) => Some(*index),
+        (Column::Name(name), Some(header)) => header.iter().position(|field| field == name),
+        (Column::Name(_), None) => None,
+    };
+    match index {
+        Some(index) if index < width => Ok(index),
+        _ => Err(CsvError::UnknownColumn(column.clone())),
+    }
+}
+
+// fields of a line, trimmed unless quoted, a quote inside quoted fields
+// being written twice
+fn split_fields(line: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut fields = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars
+            .peek()
+            .is_some_and(|&c| c != delimiter && c.is_whitespace())
+        {
+            chars.next();
+        }
+
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted field".to_string()),
+                }
+            }
+            while chars
+                .peek()
+                .is_some_and(|&c| c != delimiter && c.is_whitespace())
+            {
+                chars.next();
+            }
+            if chars.peek().is_some_and(|&c| c != delimiter) {
+                return Err("unexpected character after a quoted field".to_string());
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == delimiter {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+            field = field.trim_end().to_string();
+        }
+        fields.push(field);
+
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
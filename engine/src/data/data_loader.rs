@@ -1,5 +1,19 @@
+use crate::augmentation::Augmentation;
+use crate::data::Dataset;
 use crate::maths::Matrix;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use std::fs;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+// batches each prefetching thread may load ahead
+const PREFETCH_DEPTH: usize = 2;
+
+pub type SplitData = (Vec<(Matrix, Matrix)>, Vec<(Matrix, Matrix)>);
 
 pub fn load_data(path: &str) -> Vec<(Matrix, Matrix)> {
     let contents = fs::read_to_string(path).expect("Loading path is invalid");
@@ -21,10 +35,10 @@ pub fn load_data(path: &str) -> Vec<(Matrix, Matrix)> {
     res
 }
 
-pub fn split_data(
-    mut data: Vec<(Matrix, Matrix)>,
-    ratio: usize,
-) -> (Vec<(Matrix, Matrix)>, Vec<(Matrix, Matrix)>) {
+// the first ratio % of the data is returned second, i.e. the result reads
+// (training, testing) when ratio is the testing percentage. ordered_split
+// returns (training, testing) whatever the percentage.
+pub fn split_data(mut data: Vec<(Matrix, Matrix)>, ratio: usize) -> SplitData {
     let nb_elements = ratio * data.len() / 100;
 
     let testing_data = data.split_off(nb_elements);
@@ -32,9 +46,204 @@ pub fn split_data(
     (testing_data, data)
 }
 
-fn create_vec(string: &str) -> Vec<f64> {
+pub(super) fn create_vec(string: &str) -> Vec<f64> {
     string
         .split(" ")
         .map(|value| value.parse::<f64>().unwrap())
         .collect::<Vec<f64>>()
 }
+
+// the samples of one batch along with their indices in the dataset
+pub struct Batch {
+    pub indices: Vec<usize>,
+    pub samples: Vec<(Matrix, Matrix)>,
+}
+
+// splits a dataset into batches, visiting it through a permutation of its
+// indices that is shuffled anew every epoch when a seed is given
+#[derive(Clone)]
+pub struct DataLoader {
+    dataset: Arc<dyn Dataset>,
+    batch_size: usize,
+    seed: Option<u64>,
+    drop_last: bool,
+    nb_prefetch_threads: usize,
+    augmentation: Option<Arc<Augmentation>>,
+}
+
+impl DataLoader {
+    pub fn new<D: Dataset + 'static>(dataset: D, batch_size: usize) -> DataLoader {
+        assert!(batch_size > 0, "A batch must hold at least one sample");
+        DataLoader {
+            dataset: Arc::new(dataset),
+            batch_size,
+            seed: None,
+            drop_last: false,
+            nb_prefetch_threads: 0,
+            augmentation: None,
+        }
+    }
+
+    // the permutation of an epoch is drawn from seed + epoch, so that an
+    // epoch can be replayed on its own
+    pub fn with_shuffle(mut self, seed: u64) -> DataLoader {
+        self.seed = Some(seed);
+        self
+    }
+
+    // leaves out the last batch when it is smaller than the others
+    pub fn with_drop_last(mut self, drop_last: bool) -> DataLoader {
+        self.drop_last = drop_last;
+        self
+    }
+
+    // loads the next batches from nb_threads background threads while the
+    // current one is used, batches still coming in order
+    pub fn with_prefetch(mut self, nb_threads: usize) -> DataLoader {
+        self.nb_prefetch_threads = nb_threads;
+        self
+    }
+
+    // augments the inputs of the samples as they are loaded, differently
+    // every epoch
+    pub fn with_augmentation(mut self, augmentation: Arc<Augmentation>) -> DataLoader {
+        self.augmentation = Some(augmentation);
+        self
+    }
+
+    pub fn dataset(&self) -> &Arc<dyn Dataset> {
+        &self.dataset
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    // number of batches of an epoch
+    pub fn len(&self) -> usize {
+        if self.drop_last {
+            self.dataset.len() / self.batch_size
+        } else {
+            self.dataset.len().div_ceil(self.batch_size)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // indices of the samples in the order the epoch visits them
+    pub fn order(&self, epoch: usize) -> Vec<usize> {
+        let mut order = (0..self.dataset.len()).collect::<Vec<_>>();
+        if let Some(seed) = self.seed {
+            order.shuffle(&mut StdRng::seed_from_u64(seed.wrapping_add(epoch as u64)));
+        }
+        order
+    }
+
+    pub fn batches(&self, epoch: usize) -> Batches {
+        let mut batches = Batches {
+            dataset: self.dataset.clone(),
+            order: Arc::new(self.order(epoch)),
+            augmentation: self.augmentation.clone(),
+            epoch,
+            batch_size: self.batch_size,
+            nb_batches: self.len(),
+            next: 0,
+            receivers: vec![],
+        };
+
+        // thread t loads the batches t, t + nb_threads, ... so that reading
+        // the threads in turn gives the batches back in order
+        let nb_threads = self.nb_prefetch_threads.min(batches.nb_batches);
+        for first in 0..nb_threads {
+            let (sender, receiver) = sync_channel(PREFETCH_DEPTH);
+            let (dataset, order) = (batches.dataset.clone(), batches.order.clone());
+            let (batch_size, nb_batches) = (batches.batch_size, batches.nb_batches);
+            let augmentation = batches.augmentation.clone();
+            thread::spawn(move || {
+                for batch in (first..nb_batches).step_by(nb_threads) {
+                    let mut loaded = load_batch(&*dataset, &order, batch_size, batch);
+                    if let Some(augmentation) = &augmentation {
+                        augment_batch(augmentation, &mut loaded, epoch);
+                    }
+                    // the iterator was dropped before the end of the epoch
+                    if sender.send(loaded).is_err() {
+                        break;
+                    }
+                }
+            });
+            batches.receivers.push(receiver);
+        }
+        batches
+    }
+}
+
+impl IntoIterator for &DataLoader {
+    type Item = Batch;
+    type IntoIter = Batches;
+
+    // batches of the first epoch
+    fn into_iter(self) -> Batches {
+        self.batches(0)
+    }
+}
+
+// batches of one epoch of a DataLoader
+pub struct Batches {
+    dataset: Arc<dyn Dataset>,
+    order: Arc<Vec<usize>>,
+    augmentation: Option<Arc<Augmentation>>,
+    epoch: usize,
+    batch_size: usize,
+    nb_batches: usize,
+    next: usize,
+    receivers: Vec<Receiver<Batch>>,
+}
+
+impl Iterator for Batches {
+    type Item = Batch;
+
+    fn next(&mut self) -> Option<Batch> {
+        if self.next >= self.nb_batches {
+            return None;
+        }
+        let batch = if self.receivers.is_empty() {
+            let mut batch = load_batch(&*self.dataset, &self.order, self.batch_size, self.next);
+            if let Some(augmentation) = &self.augmentation {
+                augment_batch(augmentation, &mut batch, self.epoch);
+            }
+            batch
+        } else {
+            self.receivers[self.next % self.receivers.len()]
+                .recv()
+                .expect("A prefetching thread panicked")
+        };
+        self.next += 1;
+        Some(batch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.nb_batches - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Batches {}
+
+fn load_batch(dataset: &dyn Dataset, order: &[usize], batch_size: usize, batch: usize) -> Batch {
+    let start = batch * batch_size;
+    let end = (start + batch_size).min(order.len());
+    let indices = order[start..end].to_vec();
+    let samples = indices
+        .iter()
+        .map(|&index| dataset.get(index).into_owned())
+        .collect();
+    Batch { indices, samples }
+}
+
+fn augment_batch(augmentation: &Augmentation, batch: &mut Batch, epoch: usize) {
+    for (sample, &index) in batch.samples.iter_mut().zip(&batch.indices) {
+        *sample = augmentation.augment(sample, epoch, index);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::data::data_loader::create_vec;
+use crate::data::Dataset;
+use crate::maths::Matrix;
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::sync::Arc;
+
+impl Dataset for Vec<(Matrix, Matrix)> {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)> {
+        Cow::Borrowed(&self[index])
+    }
+}
+
+impl<D: Dataset + ?Sized> Dataset for Arc<D> {
+    fn len(&self) -> usize {
+        self.as_ref().len()
+    }
+
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)> {
+        self.as_ref().get(index)
+    }
+}
+
+// the samples of a dataset at the given indices, in their order
+#[derive(Clone)]
+pub struct Subset {
+    dataset: Arc<dyn Dataset>,
+    indices: Vec<usize>,
+}
+
+impl Subset {
+    pub fn new(dataset: Arc<dyn Dataset>, indices: Vec<usize>) -> Subset {
+        assert!(
+            indices.iter().all(|&index| index < dataset.len()),
+            "The indices must be below the length of the dataset"
+        );
+        Subset { dataset, indices }
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl Dataset for Subset {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)> {
+        self.dataset.get(self.indices[index])
+    }
+}
+
+// a file in the format of load_data, read sample by sample. Opening it
+// only goes through the file once to find where every sample starts. The
+// samples are read at their offset without moving the cursor of the file,
+// so that the prefetching threads do not wait for each other.
+pub struct FileDataset {
+    file: File,
+    // offset and length in bytes of the two lines of each sample
+    samples: Vec<(u64, usize)>,
+}
+
+impl FileDataset {
+    pub fn open(path: &str) -> FileDataset {
+        let file = File::open(path).expect("Loading path is invalid");
+        let mut reader = BufReader::new(file);
+
+        let mut samples = vec![];
+        let mut offset = 0;
+        let mut line = String::new();
+        loop {
+            let mut length = 0;
+            for _ in 0..2 {
+                line.clear();
+                length += reader
+                    .read_line(&mut line)
+                    .expect("Could not read the dataset");
+            }
+            // a trailing unpaired line is ignored, like load_data does
+            if line.is_empty() {
+                break;
+            }
+            samples.push((offset, length));
+            offset += length as u64;
+        }
+
+        FileDataset {
+            file: reader.into_inner(),
+            samples,
+        }
+    }
+}
+
+impl Dataset for FileDataset {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)> {
+        let (offset, length) = self.samples[index];
+        let mut bytes = vec![0; length];
+        read_at(&self.file, &mut bytes, offset).expect("Could not read the dataset");
+
+        let text = String::from_utf8(bytes).expect("The dataset is not valid UTF-8");
+        let mut lines = text.split('\n');
+        let input = create_vec(lines.next().unwrap_or_default());
+        let output = create_vec(lines.next().unwrap_or_default());
+        let (i_length, o_length) = (input.len(), output.len());
+        Cow::Owned((
+            Matrix::reshape(input, 1, i_length),
+            Matrix::reshape(output, 1, o_length),
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, bytes: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, bytes, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, mut bytes: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !bytes.is_empty() {
+        match file.seek_read(bytes, offset)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            read => {
+                bytes = &mut bytes[read..];
+                offset += read as u64;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use crate::maths::Matrix;
+use crate::shapes::DenseShape;
+
+use std::fmt::{Display, Formatter};
+use std::fs;
+
+// largest value of the pixels of MNIST images
+const MNIST_MAX_PIXEL: f64 = 255.0;
+const MNIST_CLASSES: usize = 10;
+
+// element type of an IDX file, given by the third byte of its magic number
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdxType {
+    U8,
+    I8,
+    I16,
+    I32,
+    F32,
+    F64,
+}
+
+impl IdxType {
+    fn from_code(code: u8) -> Option<IdxType> {
+        match code {
+            0x08 => Some(IdxType::U8),
+            0x09 => Some(IdxType::I8),
+            0x0B => Some(IdxType::I16),
+            0x0C => Some(IdxType::I32),
+            0x0D => Some(IdxType::F32),
+            0x0E => Some(IdxType::F64),
+            _ => None,
+        }
+    }
+
+    fn code(&self) -> u8 {
+        match self {
+            IdxType::U8 => 0x08,
+            IdxType::I8 => 0x09,
+            IdxType::I16 => 0x0B,
+            IdxType::I32 => 0x0C,
+            IdxType::F32 => 0x0D,
+            IdxType::F64 => 0x0E,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            IdxType::U8 | IdxType::I8 => 1,
+            IdxType::I16 => 2,
+            IdxType::I32 | IdxType::F32 => 4,
+            IdxType::F64 => 8,
+        }
+    }
+
+    // bytes is big endian and of the size of the type
+    fn read(&self, bytes: &[u8]) -> f64 {
+        match self {
+            IdxType::U8 => bytes[0] as f64,
+            IdxType::I8 => bytes[0] as i8 as f64,
+            IdxType::I16 => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+            IdxType::I32 => i32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            IdxType::F32 => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            IdxType::F64 => f64::from_be_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    // values out of the range of the type are saturated
+    fn write(&self, value: f64, bytes: &mut Vec<u8>) {
+        match self {
+            IdxType::U8 => bytes.push(value as u8),
+            IdxType::I8 => bytes.push(value as i8 as u8),
+            IdxType::I16 => bytes.extend_from_slice(&(value as i16).to_be_bytes()),
+            IdxType::I32 => bytes.extend_from_slice(&(value as i32).to_be_bytes()),
+            IdxType::F32 => bytes.extend_from_slice(&(value as f32).to_be_bytes()),
+            IdxType::F64 => bytes.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IdxError {
+    // the first two bytes of the magic number must be zero
+    BadMagic,
+    UnknownType(u8),
+    // bytes expected from the dimensions against the bytes of the file
+    Truncated(usize, usize),
+    TrailingBytes(usize),
+}
+
+impl Display for IdxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdxError::BadMagic => write!(f, "Not an IDX file"),
+            IdxError::UnknownType(code) => write!(f, "Unknown IDX element type 0x{:02X}", code),
+            IdxError::Truncated(expected, found) => write!(
+                f,
+                "Truncated IDX file: {} bytes expected, {} found",
+                expected, found
+            ),
+            IdxError::TrailingBytes(count) => {
+                write!(f, "{} unexpected bytes at the end of the IDX file", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IdxError {}
+
+// an array of any dimensionality read from an IDX file, its first
+// dimension counting the items, such as the images or the labels of MNIST
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdxArray {
+    pub element_type: IdxType,
+    pub dimensions: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl IdxArray {
+    pub fn parse(bytes: &[u8]) -> Result<IdxArray, IdxError> {
+        if bytes.len() < 4 {
+            return Err(IdxError::Truncated(4, bytes.len()));
+        }
+        if bytes[0] != 0 || bytes[1] != 0 {
+            return Err(IdxError::BadMagic);
+        }
+        let element_type = IdxType::from_code(bytes[2]).ok_or(IdxError::UnknownType(bytes[2]))?;
+        let nb_dimensions = bytes[3] as usize;
+
+        let header_size = 4 + 4 * nb_dimensions;
+        if bytes.len() < header_size {
+            return Err(IdxError::Truncated(header_size, bytes.len()));
+        }
+        let dimensions = bytes[4..header_size]
+            .chunks(4)
+            .map(|size| u32::from_be_bytes(size.try_into().unwrap()) as usize)
+            .collect::<Vec<_>>();
+
+        let size = dimensions
+            .iter()
+            .try_fold(element_type.size(), |size, &d| size.checked_mul(d))
+            .and_then(|size| size.checked_add(header_size))
+            .unwrap_or(usize::MAX);
+        if bytes.len() < size {
+            return Err(IdxError::Truncated(size, bytes.len()));
+        }
+        if bytes.len() > size {
+            return Err(IdxError::TrailingBytes(bytes.len() - size));
+        }
+
+        let values = bytes[header_size..]
+            .chunks(element_type.size())
+            .map(|value| element_type.read(value))
+            .collect();
+        Ok(IdxArray {
+            element_type,
+            dimensions,
+            values,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0, 0, self.element_type.code(), self.dimensions.len() as u8];
+        for &size in &self.dimensions {
+            bytes.extend_from_slice(&(size as u32).to_be_bytes());
+        }
+        for &value in &self.values {
+            self.element_type.write(value, &mut bytes);
+        }
+        bytes
+    }
+
+    pub fn save(&self, path: &str) {
+        fs::write(path, self.to_bytes()).expect("Could not save the IDX file at the given path.");
+    }
+
+    // number of items, 1 for a scalar
+    pub fn len(&self) -> usize {
+        self.dimensions.first().copied().unwrap_or(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // shape of one item, the dimensions following the first one, a 28x28
+    // image giving DenseShape::new(28, 28, 1). Further dimensions are
+    // folded into the last one.
+    pub fn item_shape(&self) -> DenseShape {
+        let item = self.dimensions.iter().skip(1).copied().collect::<Vec<_>>();
+        let x = item.first().copied().unwrap_or(1);
+        let y = item.get(1).copied().unwrap_or(1);
+        let z = item.iter().skip(2).product();
+        DenseShape::new(x, y, z)
+    }
+
+    // every item as a column matrix of its values divided by scale, as
+    // load_data reads its inputs
+    pub fn items(&self, scale: f64) -> Vec<Matrix> {
+        let item_size = self.item_shape().range;
+        if item_size == 0 {
+            return vec![Matrix::new(1, 0); self.len()];
+        }
+        self.values
+            .chunks(item_size)
+            .map(|item| {
+                let values = item.iter().map(|value| value / scale).collect();
+                Matrix::reshape(values, 1, item_size)
+            })
+            .collect()
+    }
+
+    // the items of an array of class indices as one-hot column matrices
+    pub fn one_hot(&self, nb_classes: usize) -> Vec<Matrix> {
+        assert_eq!(
+            self.dimensions.len(),
+            1,
+            "Labels must be a 1 dimensional array"
+        );
+        self.values
+            .iter()
+            .map(|&label| {
+                assert!(
+                    label >= 0.0 && label.fract() == 0.0 && (label as usize) < nb_classes,
+                    "Invalid label {} for {} classes",
+                    label,
+                    nb_classes
+                );
+                let mut one_hot = Matrix::new(1, nb_classes);
+                one_hot.set(label as usize, 1.0);
+                one_hot
+            })
+            .collect()
+    }
+}
+
+pub fn load_idx(path: &str) -> IdxArray {
+    let bytes = fs::read(path).expect("Loading path is invalid");
+    IdxArray::parse(&bytes).unwrap_or_else(|error| panic!("{}: {}", path, error))
+}
+
+// MNIST images and labels, such as train-images-idx3-ubyte and
+// train-labels-idx1-ubyte once decompressed, as samples of 28x28 inputs
+// and one-hot outputs. Pixels are scaled to [0, 1] when normalized.
+pub fn load_mnist(images_path: &str, labels_path: &str, normalize: bool) -> Vec<(Matrix, Matrix)> {
+    let images = load_idx(images_path);
+    let labels = load_idx(labels_path);
+    assert_eq!(
+        images.dimensions.len(),
+        3,
+        "MNIST images must be a 3 dimensional array"
+    );
+    assert_eq!(
+        images.len(),
+        labels.len(),
+        "There must be as many labels as images"
+    );
+
+    let scale = if normalize { MNIST_MAX_PIXEL } else { 1.0 };
+    images
+        .items(scale)
+        .into_iter()
+        .zip(labels.one_hot(MNIST_CLASSES))
+        .collect()
+}
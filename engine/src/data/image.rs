@@ -0,0 +1,454 @@
+use crate::maths::Matrix;
+
+use std::fmt::{Display, Formatter};
+use std::fs;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+// weights of the red, green and blue channels in the luminance
+const LUMA: [f64; 3] = [0.299, 0.587, 0.114];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageError {
+    Io(String),
+    UnknownFormat,
+    Unsupported(String),
+    Invalid(String),
+    Truncated,
+    // the error of an image of a folder, with the path of the image
+    InFile(String, Box<ImageError>),
+    NoClasses,
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Io(error) => write!(f, "Could not read the image: {}", error),
+            ImageError::UnknownFormat => write!(f, "Not a PGM, PPM, BMP or PNG image"),
+            ImageError::Unsupported(feature) => write!(f, "Unsupported image: {}", feature),
+            ImageError::Invalid(reason) => write!(f, "Invalid image: {}", reason),
+            ImageError::Truncated => write!(f, "Truncated image"),
+            ImageError::InFile(path, error) => write!(f, "{}: {}", path, error),
+            ImageError::NoClasses => write!(f, "No class directory holding images"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+// a decoded image, its values in [0, 1]. Pixels are stored row after row,
+// each one holding its channels: gray, gray and alpha, RGB or RGBA.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub channels: usize,
+    pub values: Vec<f64>,
+}
+
+impl Image {
+    pub fn open(path: &str) -> Result<Image, ImageError> {
+        let bytes = fs::read(path).map_err(|e| ImageError::Io(e.to_string()))?;
+        Image::decode(&bytes)
+    }
+
+    // the format is found from the first bytes of the file
+    pub fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+        if bytes.starts_with(PNG_SIGNATURE) {
+            decode_png(bytes)
+        } else if bytes.starts_with(b"BM") {
+            decode_bmp(bytes)
+        } else if bytes.len() >= 2 && bytes[0] == b'P' && (b'2'..=b'6').contains(&bytes[1]) {
+            decode_pnm(bytes)
+        } else {
+            Err(ImageError::UnknownFormat)
+        }
+    }
+
+    pub fn to_grayscale(&self) -> Image {
+        let values = match self.channels {
+            1 => return self.clone(),
+            2 => self.values.chunks(2).map(|pixel| pixel[0]).collect(),
+            _ => self
+                .values
+                .chunks(self.channels)
+                .map(|pixel| (0..3).map(|c| LUMA[c] * pixel[c]).sum())
+                .collect(),
+        };
+        Image {
+            channels: 1,
+            values,
+            ..*self
+        }
+    }
+
+    // alpha is dropped
+    pub fn to_rgb(&self) -> Image {
+        if self.channels == 3 {
+            return self.clone();
+        }
+        let values = self
+            .values
+            .chunks(self.channels)
+            .flat_map(|pixel| match self.channels {
+                1 | 2 => [pixel[0]; 3],
+                _ => [pixel[0], pixel[1], pixel[2]],
+            })
+            .collect();
+        Image {
+            channels: 3,
+            values,
+            ..*self
+        }
+    }
+
+    // bilinear interpolation between the pixels, their centers lining up
+    // with the ones of the original image
+    pub fn resize(&self, width: usize, height: usize) -> Image {
+        if (width, height) == (self.width, self.height) {
+            return self.clone();
+        }
+        assert!(
+            self.width > 0 && self.height > 0,
+            "An empty image cannot be resized"
+        );
+        let source = |position: usize, from: usize, to: usize| {
+            let position = (position as f64 + 0.5) * from as f64 / to as f64 - 0.5;
+            let position = position.clamp(0.0, (from - 1) as f64);
+            let low = position.floor() as usize;
+            (low, (low + 1).min(from - 1), position - low as f64)
+        };
+
+        let mut values = Vec::with_capacity(width * height * self.channels);
+        for row in 0..height {
+            let (top, bottom, dy) = source(row, self.height, height);
+            for column in 0..width {
+                let (left, right, dx) = source(column, self.width, width);
+                for channel in 0..self.channels {
+                    let value = |y: usize, x: usize| {
+                        self.values[(y * self.width + x) * self.channels + channel]
+                    };
+                    values.push(
+                        value(top, left) * (1.0 - dy) * (1.0 - dx)
+                            + value(top, right) * (1.0 - dy) * dx
+                            + value(bottom, left) * dy * (1.0 - dx)
+                            + value(bottom, right) * dy * dx,
+                    );
+                }
+            }
+        }
+        Image {
+            width,
+            height,
+            channels: self.channels,
+            values,
+        }
+    }
+
+    // the values as a column matrix, as fed to a network
+    pub fn to_matrix(&self) -> Matrix {
+        Matrix::reshape(self.values.clone(), 1, self.values.len())
+    }
+}
+
+// PGM and PPM images, in their text (P2, P3) or binary (P5, P6) variants
+fn decode_pnm(bytes: &[u8]) -> Result<Image, ImageError> {
+    let (binary, channels) = match bytes[1] {
+        b'2' => (false, 1),
+        b'3' => (false, 3),
+        b'5' => (true, 1),
+        b'6' => (true, 3),
+        _ => return Err(ImageError::Unsupported("PBM bitmaps".to_string())),
+    };
+
+    // the header is made of the magic number, the width, the height and the
+    // largest value, separated by whitespace or comments
+    let mut position = 2;
+    let next_number = |position: &mut usize| -> Result<usize, ImageError> {
+        loop {
+            match bytes.get(*position) {
+                Some(b'#') => {
+                    while bytes.get(*position).is_some_and(|&byte| byte != b'\n') {
+                        *position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => *position += 1,
+                Some(_) => break,
+                None => return Err(ImageError::Truncated),
+            }
+        }
+        let start = *position;
+        while bytes.get(*position).is_some_and(u8::is_ascii_digit) {
+            *position += 1;
+        }
+        std::str::from_utf8(&bytes[start..*position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| ImageError::Invalid("bad number".to_string()))
+    };
+    let width = next_number(&mut position)?;
+    let height = next_number(&mut position)?;
+    let max_value = next_number(&mut position)?;
+    if width == 0 || height == 0 {
+        return Err(ImageError::Invalid(format!("size {}x{}", width, height)));
+    }
+    if max_value == 0 || max_value > u16::MAX as usize {
+        return Err(ImageError::Invalid(format!("maximum value {}", max_value)));
+    }
+
+    let length = width * height * channels;
+    let values = if binary {
+        // a single whitespace separates the header from the values
+        let data = bytes.get(position + 1..).ok_or(ImageError::Truncated)?;
+        let size = if max_value > u8::MAX as usize { 2 } else { 1 };
+        if data.len() < length * size {
+            return Err(ImageError::Truncated);
+        }
+        data.chunks(size)
+            .take(length)
+            .map(|value| match size {
+                1 => value[0] as usize,
+                _ => u16::from_be_bytes([value[0], value[1]]) as usize,
+            })
+            .collect::<Vec<_>>()
+    } else {
+        (0..length)
+            .map(|_| next_number(&mut position))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    Ok(Image {
+        width,
+        height,
+        channels,
+        values: values
+            .into_iter()
+            .map(|value| value.min(max_value) as f64 / max_value as f64)
+            .collect(),
+    })
+}
+
+// uncompressed BMP images of 8 bits with a palette, 24 or 32 bits, the
+// 32 bits ones possibly holding bit fields with the usual BGRA masks
+fn decode_bmp(bytes: &[u8]) -> Result<Image, ImageError> {
+    if bytes.len() < 54 {
+        return Err(ImageError::Truncated);
+    }
+    let read_u16 = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize;
+    let read_u32 = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    let offset = read_u32(10) as usize;
+    let header_size = read_u32(14) as usize;
+    let width = read_u32(18) as i32;
+    let height = read_u32(22) as i32;
+    let bits = read_u16(28);
+    let compression = read_u32(30);
+    match compression {
+        0 => {}
+        // the red, green and blue masks follow the 40 bytes of the header
+        3 if bits == 32 => {
+            let masks = bytes.get(54..66).ok_or(ImageError::Truncated)?;
+            if masks != [0, 0, 0xFF, 0, 0, 0xFF, 0, 0, 0xFF, 0, 0, 0] {
+                return Err(ImageError::Unsupported("BMP bit fields".to_string()));
+            }
+        }
+        _ => return Err(ImageError::Unsupported("compressed BMP".to_string())),
+    }
+    if width <= 0 || height == 0 {
+        return Err(ImageError::Invalid(format!("size {}x{}", width, height)));
+    }
+    // rows go from the bottom up unless the height is negative
+    let (width, bottom_up) = (width as usize, height > 0);
+    let height = height.unsigned_abs() as usize;
+
+    let palette = match bits {
+        8 => {
+            let nb_colors = match read_u32(46) {
+                0 => 256,
+                nb_colors => nb_colors as usize,
+            };
+            let start = 14 + header_size;
+            let palette = bytes
+                .get(start..start + 4 * nb_colors)
+                .ok_or(ImageError::Truncated)?;
+            // colors are stored as blue, green, red and an unused byte
+            palette
+                .chunks(4)
+                .map(|color| [color[2], color[1], color[0]])
+                .collect::<Vec<_>>()
+        }
+        24 | 32 => vec![],
+        _ => return Err(ImageError::Unsupported(format!("{} bits BMP", bits))),
+    };
+
+    // every row is padded to a multiple of 4 bytes
+    let stride = (width * bits / 8).div_ceil(4) * 4;
+    if bytes.len() < offset + stride * height {
+        return Err(ImageError::Truncated);
+    }
+    let mut values = Vec::with_capacity(width * height * 3);
+    for row in 0..height {
+        let row = if bottom_up { height - 1 - row } else { row };
+        let start = offset + row * stride;
+        for column in 0..width {
+            let color = match bits {
+                8 => *palette
+                    .get(bytes[start + column] as usize)
+                    .ok_or_else(|| ImageError::Invalid("color out of the palette".to_string()))?,
+                _ => {
+                    let pixel = start + column * bits / 8;
+                    [bytes[pixel + 2], bytes[pixel + 1], bytes[pixel]]
+                }
+            };
+            values.extend(color.iter().map(|&value| value as f64 / 255.0));
+        }
+    }
+    Ok(Image {
+        width,
+        height,
+        channels: 3,
+        values,
+    })
+}
+
+// PNG images of any color type and bit depth, without interlacing
+fn decode_png(bytes: &[u8]) -> Result<Image, ImageError> {
+    let invalid = |reason: &str| ImageError::Invalid(reason.to_string());
+    let mut position = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut compressed = vec![];
+    loop {
+        // length, type, data and crc of every chunk
+        let length = bytes
+            .get(position..position + 4)
+            .map(|length| u32::from_be_bytes(length.try_into().unwrap()) as usize)
+            .ok_or(ImageError::Truncated)?;
+        let kind = bytes
+            .get(position + 4..position + 8)
+            .ok_or(ImageError::Truncated)?;
+        let data = bytes
+            .get(position + 8..position + 8 + length)
+            .ok_or(ImageError::Truncated)?;
+        position += 12 + length;
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data),
+            b"PLTE" => palette = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+    let header = header.ok_or_else(|| invalid("no IHDR chunk"))?;
+
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize;
+    if width == 0 || height == 0 {
+        return Err(ImageError::Invalid(format!("size {}x{}", width, height)));
+    }
+    let (depth, color_type) = (header[8] as usize, header[9]);
+    if header[12] != 0 {
+        return Err(ImageError::Unsupported("interlaced PNG".to_string()));
+    }
+    let channels = match (color_type, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (2, 8 | 16) => 3,
+        (4, 8 | 16) => 2,
+        (6, 8 | 16) => 4,
+        _ => {
+            return Err(ImageError::Unsupported(format!(
+                "PNG of color type {} and depth {}",
+                color_type, depth
+            )))
+        }
+    };
+
+    let data = miniz_oxide::inflate::decompress_to_vec_zlib(&compressed)
+        .map_err(|_| invalid("bad compressed data"))?;
+    let stride = (width * channels * depth).div_ceil(8);
+    // bytes between a byte and the one of the previous pixel it is
+    // predicted from
+    let distance = (channels * depth / 8).max(1);
+    if data.len() < (stride + 1) * height {
+        return Err(ImageError::Truncated);
+    }
+
+    let mut pixels = vec![0u8; stride * height];
+    for row in 0..height {
+        let filter = data[row * (stride + 1)];
+        let line = &data[row * (stride + 1) + 1..(row + 1) * (stride + 1)];
+        let (previous, current) = pixels.split_at_mut(row * stride);
+        let above = |i: usize| {
+            if row == 0 {
+                0
+            } else {
+                previous[(row - 1) * stride + i]
+            }
+        };
+        let current = &mut current[..stride];
+        for i in 0..stride {
+            let left = if i >= distance {
+                current[i - distance]
+            } else {
+                0
+            };
+            let up = above(i);
+            let upper_left = if i >= distance {
+                above(i - distance)
+            } else {
+                0
+            };
+            let prediction = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, upper_left),
+                _ => return Err(invalid("unknown filter")),
+            };
+            current[i] = line[i].wrapping_add(prediction);
+        }
+    }
+
+    let max_value = ((1u32 << depth) - 1) as f64;
+    let mut values = Vec::with_capacity(width * height * channels.max(3));
+    for row in 0..height {
+        let line = &pixels[row * stride..(row + 1) * stride];
+        for i in 0..width * channels {
+            let sample = match depth {
+                16 => u16::from_be_bytes([line[2 * i], line[2 * i + 1]]) as u32,
+                8 => line[i] as u32,
+                // samples of less than a byte start from its high bits
+                _ => {
+                    let bit = i * depth;
+                    (line[bit / 8] >> (8 - depth - bit % 8)) as u32 & ((1 << depth) - 1)
+                }
+            };
+            if color_type == 3 {
+                let color = palette
+                    .get(3 * sample as usize..3 * sample as usize + 3)
+                    .ok_or_else(|| invalid("color out of the palette"))?;
+                values.extend(color.iter().map(|&value| value as f64 / 255.0));
+            } else {
+                values.push(sample as f64 / max_value);
+            }
+        }
+    }
+    Ok(Image {
+        width,
+        height,
+        channels: if color_type == 3 { 3 } else { channels },
+        values,
+    })
+}
+
+fn paeth(left: u8, up: u8, upper_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - upper_left as i16;
+    let distance = |value: u8| (estimate - value as i16).abs();
+    if distance(left) <= distance(up) && distance(left) <= distance(upper_left) {
+        left
+    } else if distance(up) <= distance(upper_left) {
+        up
+    } else {
+        upper_left
+    }
+}
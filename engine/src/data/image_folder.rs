@@ -0,0 +1,180 @@
+use crate::data::image::{Image, ImageError};
+use crate::data::Dataset;
+use crate::maths::Matrix;
+use crate::metrics::class_of;
+use crate::shapes::DenseShape;
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["pgm", "ppm", "pnm", "bmp", "png"];
+
+// names of the classes in the order of the outputs of a network, to turn
+// its predictions back into names. Saved one name per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClassNames {
+    names: Vec<String>,
+}
+
+impl ClassNames {
+    pub fn new(names: Vec<String>) -> ClassNames {
+        ClassNames { names }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|class| class == name)
+    }
+
+    // name of the class predicted by an output of a network
+    pub fn name_of(&self, output: &Matrix) -> &str {
+        &self.names[class_of(output)]
+    }
+
+    pub fn save(&self, path: &str) {
+        let content = self
+            .names
+            .iter()
+            .map(|name| format!("{}\n", name))
+            .collect::<String>();
+        fs::write(path, content).expect("Could not save the class names at the given path.");
+    }
+
+    pub fn load(path: &str) -> ClassNames {
+        let contents = fs::read_to_string(path).expect("Loading path is invalid");
+        ClassNames::new(contents.lines().map(str::to_string).collect())
+    }
+}
+
+// images read from a folder along with their one-hot classes
+#[derive(Clone, Default)]
+pub struct ImageFolderData {
+    pub samples: Vec<(Matrix, Matrix)>,
+    pub classes: ClassNames,
+    // file of each sample
+    pub paths: Vec<PathBuf>,
+}
+
+impl Dataset for ImageFolderData {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)> {
+        Cow::Borrowed(&self.samples[index])
+    }
+}
+
+// reads the images of a folder laid out as root/<class>/<image>, the
+// classes being sorted by name. Every image is converted to grayscale or
+// RGB depending on the channels of the shape, resized to its width x and
+// height y and scaled to [0, 1] unless normalize is turned off, which
+// leaves values in [0, 255].
+#[derive(Clone, Debug)]
+pub struct ImageFolder {
+    shape: DenseShape,
+    normalize: bool,
+}
+
+impl ImageFolder {
+    pub fn new(shape: DenseShape) -> ImageFolder {
+        assert!(
+            shape.z == 1 || shape.z == 3,
+            "Images must have 1 (grayscale) or 3 (RGB) channels"
+        );
+        assert!(shape.range > 0, "Images must not be empty");
+        ImageFolder {
+            shape,
+            normalize: true,
+        }
+    }
+
+    pub fn with_normalize(mut self, normalize: bool) -> ImageFolder {
+        self.normalize = normalize;
+        self
+    }
+
+    pub fn load(&self, root: &str) -> Result<ImageFolderData, ImageError> {
+        let mut classes = vec![];
+        for entry in read_dir(Path::new(root))? {
+            let images = read_dir(&entry)?
+                .into_iter()
+                .filter(|path| is_image(path))
+                .collect::<Vec<_>>();
+            if entry.is_dir() && !images.is_empty() {
+                classes.push((entry, images));
+            }
+        }
+        if classes.is_empty() {
+            return Err(ImageError::NoClasses);
+        }
+
+        let mut data = ImageFolderData::default();
+        let nb_classes = classes.len();
+        for (class, (directory, images)) in classes.into_iter().enumerate() {
+            let name = directory.file_name().unwrap_or_default();
+            data.classes.names.push(name.to_string_lossy().to_string());
+            for path in images {
+                let input = self.load_image(&path.to_string_lossy())?;
+                let mut output = Matrix::new(1, nb_classes);
+                output.set(class, 1.0);
+                data.samples.push((input, output));
+                data.paths.push(path);
+            }
+        }
+        Ok(data)
+    }
+
+    // one image in the format of the samples, to feed it to a network
+    // trained on the folder
+    pub fn load_image(&self, path: &str) -> Result<Matrix, ImageError> {
+        let image = Image::open(path)
+            .map_err(|error| ImageError::InFile(path.to_string(), Box::new(error)))?;
+        let image = match self.shape.z {
+            1 => image.to_grayscale(),
+            _ => image.to_rgb(),
+        };
+        let mut input = image.resize(self.shape.x, self.shape.y).to_matrix();
+        if !self.normalize {
+            input = input.multiply(255.0);
+        }
+        Ok(input)
+    }
+}
+
+// entries of a directory, sorted so that the order does not depend on the
+// file system
+fn read_dir(directory: &Path) -> Result<Vec<PathBuf>, ImageError> {
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut entries = fs::read_dir(directory)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| ImageError::Io(e.to_string()))?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn is_image(path: &Path) -> bool {
+    path.is_file()
+        && path.extension().is_some_and(|extension| {
+            let extension = extension.to_string_lossy().to_lowercase();
+            IMAGE_EXTENSIONS.contains(&extension.as_str())
+        })
+}
@@ -1,3 +1,39 @@
+mod binary_cache;
+mod class_weights;
+mod csv_loader;
 mod data_loader;
+mod datasets;
+mod idx;
+mod image;
+mod image_folder;
+mod npy_loader;
+mod splits;
+
+use crate::maths::Matrix;
+pub use binary_cache::{CacheError, CacheHeader, CacheType, CacheWriter, MappedDataset};
+pub use class_weights::balanced_class_weights;
+pub use csv_loader::{Column, CsvData, CsvError, CsvLoader};
 pub use data_loader::load_data;
-pub use data_loader::split_data;
+pub use data_loader::{split_data, Batch, Batches, DataLoader, SplitData};
+pub use datasets::{FileDataset, Subset};
+pub use idx::{load_idx, load_mnist, IdxArray, IdxError, IdxType};
+pub use image::{Image, ImageError};
+pub use image_folder::{ClassNames, ImageFolder, ImageFolderData};
+pub use npy_loader::{load_npy_data, load_npz_data, npy_samples, npz_samples};
+pub use splits::{
+    k_fold_indices, ordered_split, shuffle_data, shuffled_split, stratified_k_fold_indices,
+    stratified_split,
+};
+use std::borrow::Cow;
+
+// samples that can be read one at a time by index, without holding the
+// whole data in memory. Samples kept in memory are lent, the others built
+// on each call.
+pub trait Dataset: Send + Sync {
+    fn len(&self) -> usize;
+    fn get(&self, index: usize) -> Cow<'_, (Matrix, Matrix)>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,47 @@
+use crate::maths::{load_npz, Matrix, NpyError};
+
+use std::collections::BTreeMap;
+
+// samples from the arrays X and y of numpy, one per row. Inputs and outputs
+// are column matrices like the ones of load_data, a 1-D y giving outputs
+// of one value.
+pub fn npy_samples(x: &Matrix, y: &Matrix) -> Vec<(Matrix, Matrix)> {
+    assert_eq!(x.h, y.h, "X and y must have as many rows");
+    (0..x.h)
+        .map(|row| (matrix_row(x, row), matrix_row(y, row)))
+        .collect()
+}
+
+// samples from the arrays of a .npz archive, by name such as "X" and "y"
+pub fn npz_samples(
+    arrays: &BTreeMap<String, Matrix>,
+    x_name: &str,
+    y_name: &str,
+) -> Result<Vec<(Matrix, Matrix)>, NpyError> {
+    let array = |name: &str| {
+        arrays
+            .get(name)
+            .ok_or_else(|| NpyError::MissingArray(name.to_string()))
+    };
+    let (x, y) = (array(x_name)?, array(y_name)?);
+    if x.h != y.h {
+        return Err(NpyError::RowMismatch(x.h, y.h));
+    }
+    Ok(npy_samples(x, y))
+}
+
+pub fn load_npy_data(x_path: &str, y_path: &str) -> Vec<(Matrix, Matrix)> {
+    npy_samples(&Matrix::load_npy(x_path), &Matrix::load_npy(y_path))
+}
+
+pub fn load_npz_data(path: &str, x_name: &str, y_name: &str) -> Vec<(Matrix, Matrix)> {
+    npz_samples(&load_npz(path), x_name, y_name)
+        .unwrap_or_else(|error| panic!("{}: {}", path, error))
+}
+
+fn matrix_row(matrix: &Matrix, row: usize) -> Matrix {
+    let values = (0..matrix.w)
+        .map(|column| matrix.get(row * matrix.w + column))
+        .collect();
+    Matrix::reshape(values, 1, matrix.w)
+}
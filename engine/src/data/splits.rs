@@ -0,0 +1,123 @@
+use crate::data::SplitData;
+use crate::maths::Matrix;
+use crate::metrics::class_of;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+// unlike split_data, every split here returns (training, testing), the
+// given percentage of the data going to testing
+
+// the last testing_percentage % of the data, in order, goes to testing
+pub fn ordered_split(mut data: Vec<(Matrix, Matrix)>, testing_percentage: usize) -> SplitData {
+    assert!(
+        testing_percentage <= 100,
+        "The percentage must be at most 100"
+    );
+    let nb_training = data.len() - testing_percentage * data.len() / 100;
+    let testing_data = data.split_off(nb_training);
+    (data, testing_data)
+}
+
+pub fn shuffle_data(mut data: Vec<(Matrix, Matrix)>, seed: u64) -> Vec<(Matrix, Matrix)> {
+    data.shuffle(&mut StdRng::seed_from_u64(seed));
+    data
+}
+
+pub fn shuffled_split(
+    data: Vec<(Matrix, Matrix)>,
+    testing_percentage: usize,
+    seed: u64,
+) -> SplitData {
+    assert!(
+        testing_percentage <= 100,
+        "The percentage must be at most 100"
+    );
+    let mut training_data = shuffle_data(data, seed);
+    let nb_training = training_data.len() - testing_percentage * training_data.len() / 100;
+    let testing_data = training_data.split_off(nb_training);
+    (training_data, testing_data)
+}
+
+// splits every class on its own so that both sides keep the class
+// proportions of the data
+pub fn stratified_split(
+    data: Vec<(Matrix, Matrix)>,
+    testing_percentage: usize,
+    seed: u64,
+) -> SplitData {
+    assert!(
+        testing_percentage <= 100,
+        "The percentage must be at most 100"
+    );
+    // one generator for all the classes, so that classes of the same size
+    // are not shuffled alike
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut testing = vec![false; data.len()];
+    for mut indices in class_indices(&data) {
+        indices.shuffle(&mut rng);
+        let nb_testing = testing_percentage * indices.len() / 100;
+        for index in indices.into_iter().take(nb_testing) {
+            testing[index] = true;
+        }
+    }
+
+    let (mut training_data, mut testing_data) = (vec![], vec![]);
+    for (index, sample) in data.into_iter().enumerate() {
+        if testing[index] {
+            testing_data.push(sample);
+        } else {
+            training_data.push(sample);
+        }
+    }
+    (training_data, testing_data)
+}
+
+// indices of the samples of each fold, the folds being consecutive runs of
+// the data whose sizes differ by one at most
+pub fn k_fold_indices(nb_samples: usize, k: usize) -> Vec<Vec<usize>> {
+    assert!(
+        k > 1 && k <= nb_samples,
+        "k must be between 2 and the number of samples"
+    );
+    let mut folds = vec![];
+    let mut start = 0;
+    for fold in 0..k {
+        let size = nb_samples / k + (fold < nb_samples % k) as usize;
+        folds.push((start..start + size).collect());
+        start += size;
+    }
+    folds
+}
+
+// deals the samples of each class in turn to the folds, so that every fold
+// keeps the class proportions of the data
+pub fn stratified_k_fold_indices(data: &[(Matrix, Matrix)], k: usize) -> Vec<Vec<usize>> {
+    assert!(
+        k > 1 && k <= data.len(),
+        "k must be between 2 and the number of samples"
+    );
+    let mut folds = vec![vec![]; k];
+    let mut fold = 0;
+    for indices in class_indices(data) {
+        for index in indices {
+            folds[fold].push(index);
+            fold = (fold + 1) % k;
+        }
+    }
+    folds.iter_mut().for_each(|fold| fold.sort_unstable());
+    folds
+}
+
+fn class_indices(data: &[(Matrix, Matrix)]) -> Vec<Vec<usize>> {
+    let mut classes: Vec<Vec<usize>> = vec![];
+    for (index, (_, expected)) in data.iter().enumerate() {
+        let class = class_of(expected);
+        if class >= classes.len() {
+            classes.resize(class + 1, vec![]);
+        }
+        classes[class].push(index);
+    }
+    classes
+}
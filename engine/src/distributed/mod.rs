@@ -0,0 +1,6 @@
+mod parameter_server;
+mod protocol;
+mod worker;
+pub use parameter_server::{ParameterServer, UpdateMode};
+pub use protocol::{read_message, write_message, Message};
+pub use worker::Worker;
@@ -0,0 +1,248 @@
+use crate::distributed::{read_message, write_message, Message};
+use crate::networks::{DenseNetwork, Gradients, SupervisedNetwork};
+
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+
+// how the server applies the gradients pushed by the workers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpdateMode {
+    // the pushes of every worker are averaged into a single update, each
+    // worker waiting for the others before getting the new parameters
+    Synchronous,
+    // every push is applied as soon as it arrives, a worker waiting only
+    // when it is more than the given number of pushes ahead of the slowest
+    BoundedStaleness(usize),
+}
+
+// holds the parameters of a network that remote workers train together.
+// Workers pull the parameters, push the gradients of their minibatches and
+// get the updated parameters in reply, until they are all done.
+pub struct ParameterServer {
+    network: DenseNetwork,
+    learning_rate: f64,
+    mode: UpdateMode,
+}
+
+struct ServerState {
+    network: DenseNetwork,
+    version: u64,
+    // synchronous mode: sum of the gradients of the current step
+    pending: Option<Gradients>,
+    pending_samples: u64,
+    nb_pending: usize,
+    // pushes of each worker, None once it is done
+    clocks: Vec<Option<u64>>,
+}
+
+impl ServerState {
+    fn nb_active(&self) -> usize {
+        self.clocks.iter().flatten().count()
+    }
+
+    fn slowest_clock(&self) -> u64 {
+        self.clocks
+            .iter()
+            .flatten()
+            .copied()
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
+    fn apply(&mut self, mut gradients: Gradients, nb_samples: u64, learning_rate: f64) {
+        if nb_samples > 1 {
+            gradients.scale(1.0 / nb_samples as f64);
+        }
+        self.network.apply_gradients(&gradients, learning_rate);
+        self.version += 1;
+    }
+
+    // applies the step once every active worker pushed its gradients
+    fn apply_pending(&mut self, learning_rate: f64) -> bool {
+        if self.nb_pending == 0 || self.nb_pending < self.nb_active() {
+            return false;
+        }
+        let gradients = self.pending.take().expect("No pending gradients");
+        self.apply(gradients, self.pending_samples, learning_rate);
+        self.pending_samples = 0;
+        self.nb_pending = 0;
+        true
+    }
+
+    fn parameters(&self) -> Message {
+        Message::Parameters {
+            version: self.version,
+            weights: self.network.weights().to_vec(),
+            biases: self.network.biases().to_vec(),
+        }
+    }
+}
+
+impl ParameterServer {
+    pub fn new(network: DenseNetwork, learning_rate: f64, mode: UpdateMode) -> ParameterServer {
+        ParameterServer {
+            network,
+            learning_rate,
+            mode,
+        }
+    }
+
+    // accepts nb_workers connections on the listener and serves them until
+    // they are all done, then returns the trained network
+    pub fn serve(self, listener: &TcpListener, nb_workers: usize) -> DenseNetwork {
+        let shapes = self
+            .network
+            .weights()
+            .iter()
+            .chain(self.network.biases())
+            .map(|matrix| (matrix.w, matrix.h))
+            .collect::<Vec<_>>();
+        let state = Mutex::new(ServerState {
+            network: self.network,
+            version: 0,
+            pending: None,
+            pending_samples: 0,
+            nb_pending: 0,
+            clocks: vec![Some(0); nb_workers],
+        });
+        let updated = Condvar::new();
+
+        thread::scope(|scope| {
+            for worker in 0..nb_workers {
+                let (stream, _) = listener
+                    .accept()
+                    .expect("Could not accept the connection of a worker");
+                let (state, updated, shapes) = (&state, &updated, &shapes);
+                let (learning_rate, mode) = (self.learning_rate, self.mode);
+                scope.spawn(move || {
+                    let connection = Connection {
+                        worker,
+                        learning_rate,
+                        mode,
+                        shapes,
+                        state,
+                        updated,
+                    };
+                    if let Err(error) = connection.run(stream) {
+                        eprintln!("Lost worker {}: {}", worker, error);
+                    }
+                    // a lost worker must not keep the others waiting
+                    connection.leave();
+                });
+            }
+        });
+
+        state
+            .into_inner()
+            .expect("A worker thread panicked")
+            .network
+    }
+}
+
+struct Connection<'a> {
+    worker: usize,
+    learning_rate: f64,
+    mode: UpdateMode,
+    // of the weights then the biases of every layer
+    shapes: &'a [(usize, usize)],
+    state: &'a Mutex<ServerState>,
+    updated: &'a Condvar,
+}
+
+impl Connection<'_> {
+    fn lock(&self) -> MutexGuard<'_, ServerState> {
+        self.state.lock().expect("A worker thread panicked")
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, ServerState>) -> MutexGuard<'a, ServerState> {
+        self.updated.wait(state).expect("A worker thread panicked")
+    }
+
+    fn run(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        loop {
+            let reply = match read_message(&mut stream)? {
+                Message::Pull => self.lock().parameters(),
+                Message::Push {
+                    nb_samples,
+                    gradients,
+                    ..
+                } => self.push(gradients, nb_samples)?,
+                Message::Done => return Ok(()),
+                Message::Parameters { .. } => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Workers cannot send parameters",
+                    ))
+                }
+            };
+            write_message(&mut stream, &reply)?;
+        }
+    }
+
+    // applies the gradients according to the mode and returns the
+    // parameters the worker should continue from. Malformed gradients are
+    // rejected before taking the lock, so that they only cost the
+    // connection of their worker.
+    fn push(&self, gradients: Gradients, nb_samples: u64) -> io::Result<Message> {
+        let shapes = gradients.weights.iter().chain(&gradients.biases);
+        if gradients.weights.len() != gradients.biases.len()
+            || !shapes
+                .map(|matrix| (matrix.w, matrix.h))
+                .eq(self.shapes.iter().copied())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Pushed gradients do not match the layers of the network",
+            ));
+        }
+
+        let mut state = self.lock();
+        let Some(clock) = state.clocks[self.worker] else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Push from a worker that is done",
+            ));
+        };
+        let clock = clock + 1;
+        state.clocks[self.worker] = Some(clock);
+
+        match self.mode {
+            UpdateMode::Synchronous => {
+                match state.pending.as_mut() {
+                    Some(pending) => pending.add(&gradients),
+                    None => state.pending = Some(gradients),
+                }
+                state.pending_samples += nb_samples;
+                state.nb_pending += 1;
+
+                let version = state.version;
+                if state.apply_pending(self.learning_rate) {
+                    self.updated.notify_all();
+                }
+                while state.version == version {
+                    state = self.wait(state);
+                }
+            }
+            UpdateMode::BoundedStaleness(staleness) => {
+                state.apply(gradients, nb_samples, self.learning_rate);
+                self.updated.notify_all();
+                while clock > state.slowest_clock().saturating_add(staleness as u64) {
+                    state = self.wait(state);
+                }
+            }
+        }
+        Ok(state.parameters())
+    }
+
+    fn leave(&self) {
+        let mut state = self.lock();
+        state.clocks[self.worker] = None;
+        if self.mode == UpdateMode::Synchronous {
+            state.apply_pending(self.learning_rate);
+        }
+        self.updated.notify_all();
+    }
+}
@@ -0,0 +1,178 @@
+use crate::maths::Matrix;
+use crate::networks::Gradients;
+
+use std::io::{self, Read, Write};
+
+// every message travels in a frame: the length of its body as a big endian
+// u32, then the body made of a tag byte followed by the fields of the
+// message, integers and floats being little endian
+const MAX_FRAME_LENGTH: usize = 1 << 30;
+
+const PULL: u8 = 0;
+const PARAMETERS: u8 = 1;
+const PUSH: u8 = 2;
+const DONE: u8 = 3;
+
+#[derive(Clone)]
+pub enum Message {
+    // asks the server for its current parameters
+    Pull,
+    // weights and biases of the server after version updates
+    Parameters {
+        version: u64,
+        weights: Vec<Matrix>,
+        biases: Vec<Matrix>,
+    },
+    // gradients summed over nb_samples samples, computed on the parameters
+    // of the given version
+    Push {
+        version: u64,
+        nb_samples: u64,
+        gradients: Gradients,
+    },
+    // the worker finished its training and leaves
+    Done,
+}
+
+pub fn write_message<W: Write>(stream: &mut W, message: &Message) -> io::Result<()> {
+    let body = encode(message);
+    stream.write_all(&(body.len() as u32).to_be_bytes())?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+pub fn read_message<R: Read>(stream: &mut R) -> io::Result<Message> {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_LENGTH {
+        return Err(invalid_data(format!(
+            "Frame of {} bytes is too long",
+            length
+        )));
+    }
+
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body)?;
+    decode(&body)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn encode(message: &Message) -> Vec<u8> {
+    let mut body = vec![];
+    match message {
+        Message::Pull => body.push(PULL),
+        Message::Parameters {
+            version,
+            weights,
+            biases,
+        } => {
+            body.push(PARAMETERS);
+            body.extend_from_slice(&version.to_le_bytes());
+            encode_matrices(&mut body, weights);
+            encode_matrices(&mut body, biases);
+        }
+        Message::Push {
+            version,
+            nb_samples,
+            gradients,
+        } => {
+            body.push(PUSH);
+            body.extend_from_slice(&version.to_le_bytes());
+            body.extend_from_slice(&nb_samples.to_le_bytes());
+            encode_matrices(&mut body, &gradients.weights);
+            encode_matrices(&mut body, &gradients.biases);
+        }
+        Message::Done => body.push(DONE),
+    }
+    body
+}
+
+// number of matrices, then the width, height and values of each
+fn encode_matrices(body: &mut Vec<u8>, matrices: &[Matrix]) {
+    body.extend_from_slice(&(matrices.len() as u32).to_le_bytes());
+    for matrix in matrices {
+        body.extend_from_slice(&(matrix.w as u32).to_le_bytes());
+        body.extend_from_slice(&(matrix.h as u32).to_le_bytes());
+        for i in 0..matrix.len() {
+            body.extend_from_slice(&matrix.get(i).to_le_bytes());
+        }
+    }
+}
+
+fn decode(body: &[u8]) -> io::Result<Message> {
+    let mut reader = BodyReader { body, position: 0 };
+    let message = match reader.u8()? {
+        PULL => Message::Pull,
+        PARAMETERS => Message::Parameters {
+            version: reader.u64()?,
+            weights: reader.matrices()?,
+            biases: reader.matrices()?,
+        },
+        PUSH => Message::Push {
+            version: reader.u64()?,
+            nb_samples: reader.u64()?,
+            gradients: Gradients {
+                weights: reader.matrices()?,
+                biases: reader.matrices()?,
+            },
+        },
+        DONE => Message::Done,
+        tag => return Err(invalid_data(format!("Unknown message tag {}", tag))),
+    };
+
+    if reader.position != body.len() {
+        return Err(invalid_data("Trailing bytes after the message".to_string()));
+    }
+    Ok(message)
+}
+
+struct BodyReader<'a> {
+    body: &'a [u8],
+    position: usize,
+}
+
+impl BodyReader<'_> {
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let end = self.position + N;
+        let bytes = self
+            .body
+            .get(self.position..end)
+            .ok_or_else(|| invalid_data("Truncated message".to_string()))?;
+        self.position = end;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<usize> {
+        Ok(u32::from_le_bytes(self.bytes()?) as usize)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    fn matrices(&mut self) -> io::Result<Vec<Matrix>> {
+        let nb_matrices = self.u32()?;
+        let mut matrices = vec![];
+        for _ in 0..nb_matrices {
+            let (w, h) = (self.u32()?, self.u32()?);
+            // checks the size before allocating anything
+            if w.saturating_mul(h).saturating_mul(8) > self.body.len() - self.position {
+                return Err(invalid_data("Truncated message".to_string()));
+            }
+            let mut matrix = Matrix::new(w, h);
+            for i in 0..matrix.len() {
+                matrix.set(i, f64::from_le_bytes(self.bytes()?));
+            }
+            matrices.push(matrix);
+        }
+        Ok(matrices)
+    }
+}
@@ -0,0 +1,154 @@
+use crate::distributed::{read_message, write_message, Message};
+use crate::maths::Matrix;
+use crate::networks::{DenseNetwork, Gradients};
+use crate::sessions::{sample_gradients, EpochRecord, Logs, TrainingHistory};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Instant;
+
+// trains a network whose parameters live on a parameter server. The local
+// network only needs the shape of the server's one: its parameters are
+// replaced by those of the server before the first minibatch.
+pub struct Worker {
+    stream: TcpStream,
+    network: DenseNetwork,
+    training_data: Vec<(Matrix, Matrix)>,
+    epoch: usize,
+    minibatch: usize,
+    seed: u64,
+    // version of the parameters of the local network
+    version: u64,
+}
+
+impl Worker {
+    pub fn connect<A: ToSocketAddrs>(
+        address: A,
+        network: DenseNetwork,
+        training_data: Vec<(Matrix, Matrix)>,
+        epoch: usize,
+        minibatch: usize,
+    ) -> Worker {
+        assert!(minibatch > 0, "The minibatch must hold at least one sample");
+        let stream =
+            TcpStream::connect(address).expect("Could not connect to the parameter server");
+        stream
+            .set_nodelay(true)
+            .expect("Could not configure the connection to the parameter server");
+        Worker {
+            stream,
+            network,
+            training_data,
+            epoch,
+            minibatch,
+            seed: thread_rng().gen(),
+            version: 0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Worker {
+        self.seed = seed;
+        self
+    }
+
+    pub fn network(&self) -> &DenseNetwork {
+        &self.network
+    }
+
+    // version of the server's parameters the local network holds
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    // pushes the gradients of every minibatch of every epoch, then tells
+    // the server it is done. The logs of an epoch hold the mean loss of its
+    // samples, each computed on the parameters the worker held at the time.
+    pub fn train(&mut self) -> TrainingHistory {
+        let mut history = TrainingHistory::new();
+        let reply = self.request(&Message::Pull);
+        self.load_parameters(reply);
+
+        for ep in 0..self.epoch {
+            let start = Instant::now();
+            let mut order = (0..self.training_data.len()).collect::<Vec<_>>();
+            order.shuffle(&mut StdRng::seed_from_u64(
+                self.seed.wrapping_add(ep as u64),
+            ));
+
+            let mut error_sum = 0.0;
+            for batch in order.chunks(self.minibatch) {
+                let (gradients, error) = self.batch_gradients(batch);
+                error_sum += error;
+                let reply = self.request(&Message::Push {
+                    version: self.version,
+                    nb_samples: batch.len() as u64,
+                    gradients,
+                });
+                self.load_parameters(reply);
+            }
+
+            let loss = error_sum / self.training_data.len() as f64;
+            history.epochs.push(EpochRecord {
+                epoch: ep,
+                wall_time: start.elapsed().as_secs_f64(),
+                logs: Logs::from([("loss".to_string(), loss)]),
+            });
+        }
+
+        write_message(&mut self.stream, &Message::Done)
+            .expect("Lost the connection to the parameter server");
+        history
+    }
+
+    pub fn release_network(self) -> DenseNetwork {
+        self.network
+    }
+
+    fn batch_gradients(&mut self, batch: &[usize]) -> (Gradients, f64) {
+        let sample = &self.training_data[batch[0]];
+        let (mut batch_gradients, mut error_sum, _) =
+            sample_gradients(&mut self.network, sample, None, None);
+        for &i in &batch[1..] {
+            let sample = &self.training_data[i];
+            let (gradients, error, _) = sample_gradients(&mut self.network, sample, None, None);
+            batch_gradients.add(&gradients);
+            error_sum += error;
+        }
+        (batch_gradients, error_sum)
+    }
+
+    fn request(&mut self, message: &Message) -> Message {
+        write_message(&mut self.stream, message)
+            .and_then(|_| read_message(&mut self.stream))
+            .expect("Lost the connection to the parameter server")
+    }
+
+    fn load_parameters(&mut self, message: Message) {
+        let Message::Parameters {
+            version,
+            weights,
+            biases,
+        } = message
+        else {
+            panic!("The parameter server did not reply with its parameters");
+        };
+
+        let (local_weights, local_biases) = self.network.parameters_mut();
+        let same_shape = |local: &[Matrix], remote: &[Matrix]| {
+            local.len() == remote.len()
+                && local
+                    .iter()
+                    .zip(remote)
+                    .all(|(l, r)| (l.w, l.h) == (r.w, r.h))
+        };
+        assert!(
+            same_shape(local_weights, &weights) && same_shape(local_biases, &biases),
+            "The network of the parameter server does not have the shape of the worker's one"
+        );
+        local_weights.clone_from_slice(&weights);
+        local_biases.clone_from_slice(&biases);
+        self.version = version;
+    }
+}
@@ -1,7 +1,14 @@
 pub mod activations;
+pub mod augmentation;
 pub mod data;
+pub mod distributed;
 pub mod losses;
 pub mod maths;
+pub mod metrics;
 pub mod networks;
+pub mod preprocessing;
+pub mod registry;
+pub mod schedulers;
 pub mod sessions;
 pub mod shapes;
+pub mod tuning;
@@ -0,0 +1,109 @@
+use crate::losses::Loss;
+use crate::maths::Matrix;
+use crate::registry::{Constructor, Registry};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub trait LossFunction: Send + Sync {
+    // descriptor written in saved networks, its first word is the name
+    // the loss must be registered under to be loaded back
+    fn name(&self) -> String;
+
+    fn compute_error(&self, values: &Matrix, expected: &Matrix) -> f64;
+    fn compute_differential_error(&self, values: &Matrix, expected: &Matrix) -> Matrix;
+
+    fn compute_weighted_error(
+        &self,
+        values: &Matrix,
+        expected: &Matrix,
+        class_weights: Option<&Matrix>,
+        sample_weight: Option<f64>,
+    ) -> f64 {
+        self.compute_error(values, expected)
+            * sample_loss_weight(expected, class_weights, sample_weight)
+    }
+
+    fn compute_weighted_differential_error(
+        &self,
+        values: &Matrix,
+        expected: &Matrix,
+        class_weights: Option<&Matrix>,
+        sample_weight: Option<f64>,
+    ) -> Matrix {
+        self.compute_differential_error(values, expected)
+            .multiply(sample_loss_weight(expected, class_weights, sample_weight))
+    }
+}
+
+// factor applied to the loss of one sample: its own weight times the weight
+// of its class. With one-hot targets the class weight is the weight of the
+// hot class, a single output being weighted by [weight of 0, weight of 1].
+pub fn sample_loss_weight(
+    expected: &Matrix,
+    class_weights: Option<&Matrix>,
+    sample_weight: Option<f64>,
+) -> f64 {
+    let class_weight = match class_weights {
+        None => 1.0,
+        Some(weights) if expected.len() == 1 && weights.len() == 2 => {
+            let y = expected.get(0);
+            weights.get(0) * (1.0 - y) + weights.get(1) * y
+        }
+        Some(weights) => {
+            assert_eq!(
+                weights.len(),
+                expected.len(),
+                "One class weight is expected per output"
+            );
+            weights.hadamard_dot(expected).sum()
+        }
+    };
+
+    class_weight * sample_weight.unwrap_or(1.0)
+}
+
+impl From<Loss> for Arc<dyn LossFunction> {
+    fn from(loss: Loss) -> Self {
+        Arc::new(loss)
+    }
+}
+
+static REGISTRY: OnceLock<RwLock<Registry<dyn LossFunction>>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<Registry<dyn LossFunction>> {
+    REGISTRY.get_or_init(|| {
+        let mut registry: Registry<dyn LossFunction> = Registry::new();
+        for name in [
+            "CategoricalCrossEntropy",
+            "CrossEntropy",
+            "MeanSquaredError",
+            "MeanAbsoluteError",
+            "Huber",
+            "LogCosh",
+            "Quantile",
+            "MeanAbsolutePercentageError",
+            "BinaryCrossEntropyWithLogits",
+            "Hinge",
+            "SquaredHinge",
+            "Focal",
+            "KullbackLeiblerDivergence",
+        ] {
+            registry.register(name, build_builtin_loss);
+        }
+        RwLock::new(registry)
+    })
+}
+
+fn build_builtin_loss(descriptor: &str) -> Option<Box<dyn LossFunction>> {
+    Loss::from_str(descriptor)
+        .ok()
+        .map(|loss| Box::new(loss) as Box<dyn LossFunction>)
+}
+
+pub fn register_loss(name: &str, constructor: Constructor<dyn LossFunction>) {
+    registry().write().unwrap().register(name, constructor);
+}
+
+pub fn build_loss(descriptor: &str) -> Option<Arc<dyn LossFunction>> {
+    registry().read().unwrap().build(descriptor).map(Arc::from)
+}
@@ -1,100 +1,374 @@
+use crate::losses::LossFunction;
+use crate::maths::activation::sigmoid;
 use crate::maths::Matrix;
+use std::fmt;
 use std::str::FromStr;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Loss {
-    CategoricalCrossEntropy,
+    // label_smoothing: share of the target mass spread evenly over the classes
+    CategoricalCrossEntropy(f64),
+    // binary cross-entropy, the values being probabilities
     CrossEntropy,
+    // binary cross-entropy, the values being logits
+    BinaryCrossEntropyWithLogits,
+    // the values are margins, the targets are either 0/1 or -1/1
+    Hinge,
+    SquaredHinge,
+    // gamma: focusing parameter, alpha: weight of the positive class
+    Focal(f64, f64),
+    KullbackLeiblerDivergence,
     MeanSquaredError,
+    MeanAbsoluteError,
+    // delta: threshold between the quadratic and linear regions
+    Huber(f64),
+    LogCosh,
+    // tau: targeted quantile, in ]0, 1[
+    Quantile(f64),
+    MeanAbsolutePercentageError,
 }
 
+// smallest magnitude used as denominator when an expected value is zero
+const MAPE_EPSILON: f64 = 1E-8;
+// probabilities are clamped to [PROBABILITY_EPSILON, 1 - PROBABILITY_EPSILON]
+// before taking their logarithm
+const PROBABILITY_EPSILON: f64 = 1E-12;
+
 impl FromStr for Loss {
     type Err = ();
 
     fn from_str(input: &str) -> Result<Loss, Self::Err> {
-        match input {
+        let mut words = input.split_whitespace();
+        let name = words.next().ok_or(())?;
+        let mut parameter =
+            || -> Result<f64, ()> { words.next().ok_or(())?.parse::<f64>().map_err(|_| ()) };
+
+        match name {
             "CrossEntropy" => Ok(Loss::CrossEntropy),
-            "CategoricalCrossEntropy" => Ok(Loss::CategoricalCrossEntropy),
+            // networks saved before label smoothing was supported have no parameter
+            "CategoricalCrossEntropy" => {
+                Ok(Loss::CategoricalCrossEntropy(parameter().unwrap_or(0.0)))
+            }
+            "BinaryCrossEntropyWithLogits" => Ok(Loss::BinaryCrossEntropyWithLogits),
+            "Hinge" => Ok(Loss::Hinge),
+            "SquaredHinge" => Ok(Loss::SquaredHinge),
+            "Focal" => Ok(Loss::Focal(parameter()?, parameter()?)),
+            "KullbackLeiblerDivergence" => Ok(Loss::KullbackLeiblerDivergence),
             "MeanSquaredError" => Ok(Loss::MeanSquaredError),
+            "MeanAbsoluteError" => Ok(Loss::MeanAbsoluteError),
+            "Huber" => Ok(Loss::Huber(parameter()?)),
+            "LogCosh" => Ok(Loss::LogCosh),
+            "Quantile" => Ok(Loss::Quantile(parameter()?)),
+            "MeanAbsolutePercentageError" => Ok(Loss::MeanAbsolutePercentageError),
             _ => Err(()),
         }
     }
 }
 
-impl ToString for Loss {
-    fn to_string(&self) -> String {
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Loss::CrossEntropy => "CrossEntropy",
-            Loss::CategoricalCrossEntropy => "CategoricalCrossEntropy",
-            Loss::MeanSquaredError => "MeanSquaredError",
+            Loss::CrossEntropy => write!(f, "CrossEntropy"),
+            Loss::CategoricalCrossEntropy(smoothing) => {
+                write!(f, "CategoricalCrossEntropy {}", smoothing)
+            }
+            Loss::BinaryCrossEntropyWithLogits => write!(f, "BinaryCrossEntropyWithLogits"),
+            Loss::Hinge => write!(f, "Hinge"),
+            Loss::SquaredHinge => write!(f, "SquaredHinge"),
+            Loss::Focal(gamma, alpha) => write!(f, "Focal {} {}", gamma, alpha),
+            Loss::KullbackLeiblerDivergence => write!(f, "KullbackLeiblerDivergence"),
+            Loss::MeanSquaredError => write!(f, "MeanSquaredError"),
+            Loss::MeanAbsoluteError => write!(f, "MeanAbsoluteError"),
+            Loss::Huber(delta) => write!(f, "Huber {}", delta),
+            Loss::LogCosh => write!(f, "LogCosh"),
+            Loss::Quantile(tau) => write!(f, "Quantile {}", tau),
+            Loss::MeanAbsolutePercentageError => write!(f, "MeanAbsolutePercentageError"),
         }
-        .to_string()
     }
 }
 
-impl Loss {
-    pub fn compute_error(&self, values: &Matrix, expected: &Matrix) -> f64 {
+impl LossFunction for Loss {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn compute_error(&self, values: &Matrix, expected: &Matrix) -> f64 {
         match self {
-            Loss::CategoricalCrossEntropy => categorical_cross_entropy(values, expected),
+            Loss::CategoricalCrossEntropy(smoothing) => {
+                categorical_cross_entropy(values, expected, *smoothing)
+            }
             Loss::CrossEntropy => cross_entropy(values, expected),
+            Loss::BinaryCrossEntropyWithLogits => cross_entropy_with_logits(values, expected),
+            Loss::Hinge => hinge(values, expected),
+            Loss::SquaredHinge => squared_hinge(values, expected),
+            Loss::Focal(gamma, alpha) => focal(values, expected, *gamma, *alpha),
+            Loss::KullbackLeiblerDivergence => kullback_leibler_divergence(values, expected),
             Loss::MeanSquaredError => mean_squared_error(values, expected),
+            Loss::MeanAbsoluteError => mean_absolute_error(values, expected),
+            Loss::Huber(delta) => huber(values, expected, *delta),
+            Loss::LogCosh => log_cosh(values, expected),
+            Loss::Quantile(tau) => quantile(values, expected, *tau),
+            Loss::MeanAbsolutePercentageError => mean_absolute_percentage_error(values, expected),
         }
     }
 
-    pub fn compute_differential_error(&self, values: &Matrix, expected: &Matrix) -> Matrix {
+    fn compute_differential_error(&self, values: &Matrix, expected: &Matrix) -> Matrix {
         match self {
-            Loss::CategoricalCrossEntropy => {
-                differential_categorical_cross_entropy(values, expected)
+            Loss::CategoricalCrossEntropy(smoothing) => {
+                differential_categorical_cross_entropy(values, expected, *smoothing)
             }
             Loss::CrossEntropy => differential_cross_entropy(values, expected),
+            Loss::BinaryCrossEntropyWithLogits => {
+                differential_cross_entropy_with_logits(values, expected)
+            }
+            Loss::Hinge => differential_hinge(values, expected),
+            Loss::SquaredHinge => differential_squared_hinge(values, expected),
+            Loss::Focal(gamma, alpha) => differential_focal(values, expected, *gamma, *alpha),
+            Loss::KullbackLeiblerDivergence => {
+                differential_kullback_leibler_divergence(values, expected)
+            }
             Loss::MeanSquaredError => differential_mean_squared_error(values, expected),
+            Loss::MeanAbsoluteError => differential_mean_absolute_error(values, expected),
+            Loss::Huber(delta) => differential_huber(values, expected, *delta),
+            Loss::LogCosh => differential_log_cosh(values, expected),
+            Loss::Quantile(tau) => differential_quantile(values, expected, *tau),
+            Loss::MeanAbsolutePercentageError => {
+                differential_mean_absolute_percentage_error(values, expected)
+            }
         }
     }
 }
 
-fn categorical_cross_entropy(values: &Matrix, expected: &Matrix) -> f64 {
+fn mean_squared_error(values: &Matrix, expected: &Matrix) -> f64 {
+    (expected - values).powi(2).sum() * 0.5
+}
+
+fn differential_mean_squared_error(values: &Matrix, expected: &Matrix) -> Matrix {
+    values - expected
+}
+
+// losses are summed over the output components, the session averaging
+// them over the samples
+
+fn sum_elementwise(values: &Matrix, expected: &Matrix, f: impl Fn(f64, f64) -> f64) -> f64 {
+    assert_eq!(values.len(), expected.len());
     let mut sum: f64 = 0.0;
 
     for i in 0..values.len() {
-        let x = values.get(i);
-        let y = expected.get(i);
-        let v = if y == 1.0 {(-x).ln()} else {(1.0 - x).ln()};
-        sum += if v.is_nan() {0.0} else {v};
+        sum += f(values.get(i), expected.get(i));
     }
 
     sum
 }
 
-fn cross_entropy(values: &Matrix, expected: &Matrix) -> f64 {
-    let mut sum: f64 = 0.0;
+fn map_elementwise(values: &Matrix, expected: &Matrix, f: impl Fn(f64, f64) -> f64) -> Matrix {
+    assert_eq!(values.len(), expected.len());
+    let mut mat = Matrix::new(values.w, values.h);
 
     for i in 0..values.len() {
-        let x = values.get(i);
-        let y = expected.get(i);
-        let v = if y == 1.0 {-(-x).ln()} else {-(1.0 - x).ln()};
-        sum += if v.is_nan() {0.0} else {v};
+        mat.set(i, f(values.get(i), expected.get(i)));
     }
 
-    sum
+    mat
 }
 
-fn mean_squared_error(values: &Matrix, expected: &Matrix) -> f64 {
-    (expected - values).powi(2).sum() * 0.5
+fn mean_absolute_error(values: &Matrix, expected: &Matrix) -> f64 {
+    sum_elementwise(values, expected, |x, y| (x - y).abs())
 }
 
-fn differential_categorical_cross_entropy(values: &Matrix, expected: &Matrix) -> Matrix {
-    values - expected
+fn differential_mean_absolute_error(values: &Matrix, expected: &Matrix) -> Matrix {
+    map_elementwise(values, expected, |x, y| sign(x - y))
+}
+
+fn huber(values: &Matrix, expected: &Matrix, delta: f64) -> f64 {
+    sum_elementwise(values, expected, |x, y| {
+        let r = (x - y).abs();
+        if r <= delta {
+            0.5 * r.powi(2)
+        } else {
+            delta * (r - 0.5 * delta)
+        }
+    })
+}
+
+fn differential_huber(values: &Matrix, expected: &Matrix, delta: f64) -> Matrix {
+    map_elementwise(values, expected, |x, y| (x - y).clamp(-delta, delta))
+}
+
+fn log_cosh(values: &Matrix, expected: &Matrix) -> f64 {
+    // ln(cosh(r)) = |r| + ln(1 + exp(-2|r|)) - ln(2), which does not overflow
+    sum_elementwise(values, expected, |x, y| {
+        let r = (x - y).abs();
+        r + (-2.0 * r).exp().ln_1p() - std::f64::consts::LN_2
+    })
+}
+
+fn differential_log_cosh(values: &Matrix, expected: &Matrix) -> Matrix {
+    map_elementwise(values, expected, |x, y| (x - y).tanh())
+}
+
+fn quantile(values: &Matrix, expected: &Matrix, tau: f64) -> f64 {
+    sum_elementwise(values, expected, |x, y| {
+        let r = y - x;
+        (tau * r).max((tau - 1.0) * r)
+    })
+}
+
+fn differential_quantile(values: &Matrix, expected: &Matrix, tau: f64) -> Matrix {
+    map_elementwise(values, expected, |x, y| {
+        if y > x {
+            -tau
+        } else if y < x {
+            1.0 - tau
+        } else {
+            0.0
+        }
+    })
+}
+
+fn mean_absolute_percentage_error(values: &Matrix, expected: &Matrix) -> f64 {
+    sum_elementwise(values, expected, |x, y| {
+        100.0 * (x - y).abs() / y.abs().max(MAPE_EPSILON)
+    })
+}
+
+fn differential_mean_absolute_percentage_error(values: &Matrix, expected: &Matrix) -> Matrix {
+    map_elementwise(values, expected, |x, y| {
+        100.0 * sign(x - y) / y.abs().max(MAPE_EPSILON)
+    })
+}
+
+// unlike f64::signum, the subgradient at 0 is 0
+fn sign(x: f64) -> f64 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+pub(crate) fn clamp_probability(p: f64) -> f64 {
+    p.clamp(PROBABILITY_EPSILON, 1.0 - PROBABILITY_EPSILON)
+}
+
+fn smooth_label(y: f64, smoothing: f64, nb_classes: usize) -> f64 {
+    y * (1.0 - smoothing) + smoothing / nb_classes as f64
+}
+
+fn categorical_cross_entropy(values: &Matrix, expected: &Matrix, smoothing: f64) -> f64 {
+    let nb_classes = values.len();
+    sum_elementwise(values, expected, |p, y| {
+        -smooth_label(y, smoothing, nb_classes) * clamp_probability(p).ln()
+    })
+}
+
+fn differential_categorical_cross_entropy(
+    values: &Matrix,
+    expected: &Matrix,
+    smoothing: f64,
+) -> Matrix {
+    let nb_classes = values.len();
+    map_elementwise(values, expected, |p, y| {
+        -smooth_label(y, smoothing, nb_classes) / clamp_probability(p)
+    })
+}
+
+fn cross_entropy(values: &Matrix, expected: &Matrix) -> f64 {
+    sum_elementwise(values, expected, |p, y| {
+        let p = clamp_probability(p);
+        -(y * p.ln() + (1.0 - y) * (1.0 - p).ln())
+    })
 }
 
 fn differential_cross_entropy(values: &Matrix, expected: &Matrix) -> Matrix {
-//    Matrix::double_mapping(|x,y| {
-//        if x == 0.0 || x == 1.0 {
-//            return 0.0;
-//        }
-//        (y - x) / (x * (x - 1.0))
-//    }, values, expected)
-    values - expected
+    map_elementwise(values, expected, |p, y| {
+        let p = clamp_probability(p);
+        (p - y) / (p * (1.0 - p))
+    })
 }
 
-fn differential_mean_squared_error(values: &Matrix, expected: &Matrix) -> Matrix {
-    values - expected
+fn cross_entropy_with_logits(values: &Matrix, expected: &Matrix) -> f64 {
+    // max(z, 0) - z * y + ln(1 + exp(-|z|)) never overflows
+    sum_elementwise(values, expected, |z, y| {
+        z.max(0.0) - z * y + (-z.abs()).exp().ln_1p()
+    })
+}
+
+fn differential_cross_entropy_with_logits(values: &Matrix, expected: &Matrix) -> Matrix {
+    map_elementwise(values, expected, |z, y| sigmoid(z) - y)
+}
+
+// maps 0/1 and -1/1 targets to -1/1
+fn hinge_target(y: f64) -> f64 {
+    if y > 0.0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+fn hinge(values: &Matrix, expected: &Matrix) -> f64 {
+    sum_elementwise(values, expected, |z, y| {
+        (1.0 - hinge_target(y) * z).max(0.0)
+    })
+}
+
+fn differential_hinge(values: &Matrix, expected: &Matrix) -> Matrix {
+    map_elementwise(values, expected, |z, y| {
+        let t = hinge_target(y);
+        if t * z < 1.0 {
+            -t
+        } else {
+            0.0
+        }
+    })
+}
+
+fn squared_hinge(values: &Matrix, expected: &Matrix) -> f64 {
+    sum_elementwise(values, expected, |z, y| {
+        (1.0 - hinge_target(y) * z).max(0.0).powi(2)
+    })
+}
+
+fn differential_squared_hinge(values: &Matrix, expected: &Matrix) -> Matrix {
+    map_elementwise(values, expected, |z, y| {
+        let t = hinge_target(y);
+        -2.0 * t * (1.0 - t * z).max(0.0)
+    })
+}
+
+fn focal(values: &Matrix, expected: &Matrix, gamma: f64, alpha: f64) -> f64 {
+    sum_elementwise(values, expected, |p, y| {
+        let p = clamp_probability(p);
+        -(alpha * y * (1.0 - p).powf(gamma) * p.ln()
+            + (1.0 - alpha) * (1.0 - y) * p.powf(gamma) * (1.0 - p).ln())
+    })
+}
+
+fn differential_focal(values: &Matrix, expected: &Matrix, gamma: f64, alpha: f64) -> Matrix {
+    map_elementwise(values, expected, |p, y| {
+        let p = clamp_probability(p);
+        let positive =
+            alpha * y * ((1.0 - p).powf(gamma) / p - gamma * (1.0 - p).powf(gamma - 1.0) * p.ln());
+        let negative = (1.0 - alpha)
+            * (1.0 - y)
+            * (gamma * p.powf(gamma - 1.0) * (1.0 - p).ln() - p.powf(gamma) / (1.0 - p));
+        -(positive + negative)
+    })
+}
+
+fn kullback_leibler_divergence(values: &Matrix, expected: &Matrix) -> f64 {
+    sum_elementwise(values, expected, |p, y| {
+        if y == 0.0 {
+            0.0
+        } else {
+            y * (y / clamp_probability(p)).ln()
+        }
+    })
+}
+
+fn differential_kullback_leibler_divergence(values: &Matrix, expected: &Matrix) -> Matrix {
+    map_elementwise(values, expected, |p, y| -y / clamp_probability(p))
 }
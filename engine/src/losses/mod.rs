@@ -1,2 +1,6 @@
+mod loss_function;
+#[allow(clippy::module_inception)]
 mod losses;
+pub use loss_function::{build_loss, register_loss, sample_loss_weight, LossFunction};
+pub(crate) use losses::clamp_probability;
 pub use losses::Loss;
@@ -1,6 +1,6 @@
 use rayon::prelude::*;
 
-fn compute_rows_of_sums(a_row: &[f64], b: &Vec<f64>, k: usize, p: usize) -> Vec<f64> {
+fn compute_rows_of_sums(a_row: &[f64], b: &[f64], k: usize, p: usize) -> Vec<f64> {
     let mut unordered_columns = (0..p)
         .into_par_iter()
         .map(|y| (y, (0..k).map(|x| a_row[x] * b[x * p + y]).sum()))
@@ -14,7 +14,7 @@ fn compute_rows_of_sums(a_row: &[f64], b: &Vec<f64>, k: usize, p: usize) -> Vec<
         .collect()
 }
 
-pub fn dot_multithreaded(a: &Vec<f64>, b: &Vec<f64>, n: usize, k: usize, p: usize) -> Vec<f64> {
+pub fn dot_multithreaded(a: &[f64], b: &[f64], n: usize, k: usize, p: usize) -> Vec<f64> {
     let mut unordered_rows = (0..n)
         .into_par_iter()
         .map(move |i| {
@@ -31,7 +31,7 @@ pub fn dot_multithreaded(a: &Vec<f64>, b: &Vec<f64>, n: usize, k: usize, p: usiz
     vec
 }
 
-pub fn dot_monothreaded(a: &Vec<f64>, b: &Vec<f64>, n: usize, k: usize, p: usize) -> Vec<f64> {
+pub fn dot_monothreaded(a: &[f64], b: &[f64], n: usize, k: usize, p: usize) -> Vec<f64> {
     let mut result: Vec<f64> = vec![0.0; n * p];
 
     for i in 0..n {
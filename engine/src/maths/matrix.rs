@@ -1,6 +1,7 @@
 use crate::maths::high_freq_computation::{dot_monothreaded, dot_multithreaded};
 use crate::maths::MULTITHREADED;
 use rand::Rng;
+use std::fmt;
 
 pub struct Matrix {
     pub w: usize,
@@ -12,16 +13,12 @@ pub struct Matrix {
 impl Matrix {
     pub fn new(w: usize, h: usize) -> Matrix {
         let size = w * h;
-        let mut vec = Vec::with_capacity(size);
-        for _ in 0..size {
-            vec.push(0.0);
-        }
 
         Matrix {
             w,
             h,
             length: size,
-            values: vec,
+            values: vec![0.0; size],
         }
     }
 
@@ -48,8 +45,12 @@ impl Matrix {
         self.length
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
     pub fn get(&self, i: usize) -> f64 {
-        assert_eq!(i < self.len(), true, "We want to access index {} in a matrix of length {}", i, self.len());
+        assert!(i < self.len(), "We want to access index {} in a matrix of length {}", i, self.len());
         self.values[i]
     }
 
@@ -111,6 +112,21 @@ impl Matrix {
         sum
     }
 
+    pub fn is_finite(&self) -> bool {
+        self.values.iter().all(|value| value.is_finite())
+    }
+
+    // index of the largest value, the first one on ties
+    pub fn argmax(&self) -> usize {
+        let mut index = 0;
+        for i in 1..self.len() {
+            if self.get(i) > self.get(index) {
+                index = i;
+            }
+        }
+        index
+    }
+
     pub fn hadamard_dot(&self, other: &Matrix) -> Matrix {
         assert_eq!(self.w, other.w);
         assert_eq!(self.h, other.h);
@@ -127,13 +143,11 @@ impl Matrix {
     pub fn dot(&self, other: &Matrix) -> Matrix {
         assert_eq!(self.w, other.h);
 
-        let result: Vec<f64>;
-
-        if MULTITHREADED {
-            result = dot_multithreaded(&self.values, &other.values, self.h, self.w, other.w);
+        let result: Vec<f64> = if MULTITHREADED {
+            dot_multithreaded(&self.values, &other.values, self.h, self.w, other.w)
         } else {
-            result = dot_monothreaded(&self.values, &other.values, self.h, self.w, other.w);
-        }
+            dot_monothreaded(&self.values, &other.values, self.h, self.w, other.w)
+        };
 
         Matrix::reshape(result, other.w, self.h)
     }
@@ -241,15 +255,14 @@ impl Clone for Matrix {
     }
 }
 
-impl ToString for Matrix {
-    fn to_string(&self) -> String {
-        let mut content = "".to_owned();
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for i in 0..self.len() {
             if i != 0 {
-                content.push_str(" ");
+                write!(f, " ")?;
             }
-            content.push_str(self.get(i).to_string().as_str());
+            write!(f, "{}", self.get(i))?;
         }
-        return content;
+        Ok(())
     }
 }
@@ -2,8 +2,10 @@ pub mod activation;
 mod high_freq_computation;
 mod matrix;
 pub mod matrix_ops;
+mod numpy;
 
 pub use matrix::Matrix;
+pub use numpy::{load_npz, npz_bytes, parse_npz, save_npz, NpyError, NpyType};
 
 const LEAKY_RELU_VALUE: f64 = 1E-2;
 const MULTITHREADED: bool = false;
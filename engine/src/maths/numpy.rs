@@ -0,0 +1,392 @@
+use crate::maths::Matrix;
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+
+// .npy files: a magic string, a version, the length of the header and the
+// header itself, a Python dict giving the type, order and shape of the
+// array, followed by its values
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+const NPY_ALIGNMENT: usize = 64;
+
+// .npz files are zip archives of .npy files named after their arrays
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const ZIP64_EXTRA: u16 = 0x0001;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+// element type an array is written with, values being converted to it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NpyType {
+    F32,
+    F64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NpyError {
+    BadMagic,
+    BadHeader(String),
+    UnsupportedType(String),
+    // arrays of more than 2 dimensions have no matrix
+    UnsupportedShape(Vec<usize>),
+    Truncated,
+    BadArchive(String),
+    MissingArray(String),
+    // rows of the inputs and of the outputs of samples
+    RowMismatch(usize, usize),
+}
+
+impl Display for NpyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NpyError::BadMagic => write!(f, "Not a .npy file"),
+            NpyError::BadHeader(header) => write!(f, "Invalid .npy header {}", header),
+            NpyError::UnsupportedType(descr) => write!(f, "Unsupported element type {}", descr),
+            NpyError::UnsupportedShape(shape) => {
+                write!(f, "Arrays of shape {:?} cannot be matrices", shape)
+            }
+            NpyError::Truncated => write!(f, "Truncated .npy file"),
+            NpyError::BadArchive(reason) => write!(f, "Invalid .npz archive: {}", reason),
+            NpyError::MissingArray(name) => write!(f, "No array {} in the archive", name),
+            NpyError::RowMismatch(x, y) => {
+                write!(f, "{} rows of inputs for {} rows of outputs", x, y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NpyError {}
+
+// a 1-D array of n values is read as a column matrix, like the samples of
+// load_data, and a 2-D array of shape (rows, columns) as a matrix of h rows
+// and w columns
+impl Matrix {
+    pub fn from_npy(bytes: &[u8]) -> Result<Matrix, NpyError> {
+        if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+            return Err(NpyError::BadMagic);
+        }
+        let (header_start, header_length) = match bytes[6] {
+            1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+            2 | 3 if bytes.len() >= 12 => (12, read_u32(bytes, 8) as usize),
+            _ => return Err(NpyError::BadMagic),
+        };
+        let data_start = header_start + header_length;
+        let header = bytes
+            .get(header_start..data_start)
+            .ok_or(NpyError::Truncated)?;
+        let header = String::from_utf8_lossy(header);
+
+        let descr = header_value(&header, "descr")
+            .map(|value| value.trim_matches(|c| c == '\'' || c == '"'))
+            .ok_or_else(|| NpyError::BadHeader(header.to_string()))?;
+        let fortran_order = match header_value(&header, "fortran_order") {
+            Some("True") => true,
+            Some("False") => false,
+            _ => return Err(NpyError::BadHeader(header.to_string())),
+        };
+        let shape = header_value(&header, "shape")
+            .and_then(parse_shape)
+            .ok_or_else(|| NpyError::BadHeader(header.to_string()))?;
+
+        let (h, w) = match shape[..] {
+            [] => (1, 1),
+            [n] => (n, 1),
+            [rows, columns] => (rows, columns),
+            _ => return Err(NpyError::UnsupportedShape(shape)),
+        };
+        let element = Element::parse(descr)?;
+        let length = w.checked_mul(h).ok_or(NpyError::Truncated)?;
+        let data = &bytes[data_start..];
+        if data.len() / element.size < length {
+            return Err(NpyError::Truncated);
+        }
+
+        let values = data
+            .chunks(element.size)
+            .take(length)
+            .map(|value| element.read(value))
+            .collect::<Vec<_>>();
+        if fortran_order {
+            // the columns follow each other
+            Ok(Matrix::reshape(values, h, w).t())
+        } else {
+            Ok(Matrix::reshape(values, w, h))
+        }
+    }
+
+    // always 2-D and in C order
+    pub fn to_npy(&self, dtype: NpyType) -> Vec<u8> {
+        let descr = match dtype {
+            NpyType::F32 => "<f4",
+            NpyType::F64 => "<f8",
+        };
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+            descr, self.h, self.w
+        );
+        // the values start on an aligned offset, the header ending with a
+        // newline
+        let unpadded = NPY_MAGIC.len() + 4 + header.len() + 1;
+        let padding = (NPY_ALIGNMENT - unpadded % NPY_ALIGNMENT) % NPY_ALIGNMENT;
+        header.push_str(&" ".repeat(padding));
+        header.push('\n');
+
+        let mut bytes = NPY_MAGIC.to_vec();
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for i in 0..self.len() {
+            match dtype {
+                NpyType::F32 => bytes.extend_from_slice(&(self.get(i) as f32).to_le_bytes()),
+                NpyType::F64 => bytes.extend_from_slice(&self.get(i).to_le_bytes()),
+            }
+        }
+        bytes
+    }
+
+    pub fn load_npy(path: &str) -> Matrix {
+        let bytes = fs::read(path).expect("Loading path is invalid");
+        Matrix::from_npy(&bytes).unwrap_or_else(|error| panic!("{}: {}", path, error))
+    }
+
+    pub fn save_npy(&self, path: &str) {
+        fs::write(path, self.to_npy(NpyType::F64))
+            .expect("Could not save the matrix at the given path.");
+    }
+}
+
+// type of the elements as given by the descr of the header, such as <f8
+struct Element {
+    kind: char,
+    size: usize,
+    little_endian: bool,
+}
+
+impl Element {
+    fn parse(descr: &str) -> Result<Element, NpyError> {
+        let unsupported = || NpyError::UnsupportedType(descr.to_string());
+        let mut chars = descr.chars();
+        let little_endian = match chars.next() {
+            Some('<') | Some('|') | Some('=') => true,
+            Some('>') => false,
+            _ => return Err(unsupported()),
+        };
+        let kind = chars.next().ok_or_else(unsupported)?;
+        let size = chars.as_str().parse::<usize>().map_err(|_| unsupported())?;
+        match (kind, size) {
+            ('f', 4 | 8) | ('i' | 'u', 1 | 2 | 4 | 8) | ('b', 1) => Ok(Element {
+                kind,
+                size,
+                little_endian,
+            }),
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn read(&self, bytes: &[u8]) -> f64 {
+        let mut buffer = [0; 8];
+        buffer[..self.size].copy_from_slice(bytes);
+        if !self.little_endian {
+            buffer[..self.size].reverse();
+        }
+        let bits = u64::from_le_bytes(buffer);
+        // sign extension of the smaller integers
+        let shift = 64 - 8 * self.size as u32;
+        match (self.kind, self.size) {
+            ('f', 4) => f32::from_bits(bits as u32) as f64,
+            ('f', _) => f64::from_bits(bits),
+            ('i', _) => ((bits << shift) as i64 >> shift) as f64,
+            _ => bits as f64,
+        }
+    }
+}
+
+// raw value of a key of the header dict, such as '<f8' or (3, 4)
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let value = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if value.starts_with('(') {
+        value.find(')')? + 1
+    } else {
+        value.find([',', '}'])?
+    };
+    Some(value[..end].trim())
+}
+
+fn parse_shape(shape: &str) -> Option<Vec<usize>> {
+    let dimensions = shape.strip_prefix('(')?.strip_suffix(')')?;
+    dimensions
+        .split(',')
+        .map(str::trim)
+        .filter(|dimension| !dimension.is_empty())
+        .map(|dimension| dimension.trim_end_matches('L').parse().ok())
+        .collect()
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+// arrays of a .npz archive by name, written by numpy.savez or
+// numpy.savez_compressed
+pub fn parse_npz(bytes: &[u8]) -> Result<BTreeMap<String, Matrix>, NpyError> {
+    let bad = |reason: &str| NpyError::BadArchive(reason.to_string());
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&at| read_u32(bytes, at) == END_OF_CENTRAL_DIRECTORY)
+        .ok_or_else(|| bad("no end of central directory"))?;
+    let nb_entries = read_u16(bytes, end + 10) as usize;
+    let mut at = read_u32(bytes, end + 16) as usize;
+
+    let mut arrays = BTreeMap::new();
+    for _ in 0..nb_entries {
+        if at + 46 > bytes.len() || read_u32(bytes, at) != CENTRAL_HEADER {
+            return Err(bad("invalid central directory"));
+        }
+        let method = read_u16(bytes, at + 10);
+        let crc = read_u32(bytes, at + 16);
+        let mut compressed_size = read_u32(bytes, at + 20) as u64;
+        let mut size = read_u32(bytes, at + 24) as u64;
+        let name_length = read_u16(bytes, at + 28) as usize;
+        let extra_length = read_u16(bytes, at + 30) as usize;
+        let comment_length = read_u16(bytes, at + 32) as usize;
+        let mut offset = read_u32(bytes, at + 42) as u64;
+        let name_start = at + 46;
+        let extra_start = name_start + name_length;
+        if extra_start + extra_length > bytes.len() {
+            return Err(bad("invalid central directory"));
+        }
+        let name = String::from_utf8_lossy(&bytes[name_start..extra_start]).to_string();
+
+        // sizes and offset too large for 32 bits are in the zip64 extra
+        // field, in this order and only when saturated
+        let extra_end = extra_start + extra_length;
+        let mut extra = extra_start;
+        while extra + 4 <= extra_end {
+            let (id, length) = (read_u16(bytes, extra), read_u16(bytes, extra + 2) as usize);
+            let field_end = extra + 4 + length;
+            if field_end > extra_end {
+                return Err(bad("invalid extra field"));
+            }
+            if id == ZIP64_EXTRA {
+                let mut field = extra + 4;
+                for value in [&mut size, &mut compressed_size, &mut offset] {
+                    if *value == u32::MAX as u64 && field + 8 <= field_end {
+                        *value = read_u64(bytes, field);
+                        field += 8;
+                    }
+                }
+            }
+            extra = field_end;
+        }
+        at = extra_end + comment_length;
+
+        let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+        if offset.saturating_add(30) > bytes.len() || read_u32(bytes, offset) != LOCAL_HEADER {
+            return Err(bad("invalid local header"));
+        }
+        let data_start = offset
+            + 30
+            + read_u16(bytes, offset + 26) as usize
+            + read_u16(bytes, offset + 28) as usize;
+        let data = data_start
+            .checked_add(compressed_size as usize)
+            .and_then(|data_end| bytes.get(data_start..data_end))
+            .ok_or(NpyError::Truncated)?;
+
+        let data = match method {
+            STORED => data.to_vec(),
+            DEFLATED => miniz_oxide::inflate::decompress_to_vec(data)
+                .map_err(|_| bad("invalid compressed data"))?,
+            _ => return Err(bad("unsupported compression")),
+        };
+        if data.len() as u64 != size || crc32(&data) != crc {
+            return Err(bad(&format!("corrupted entry {}", name)));
+        }
+
+        let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+        arrays.insert(name, Matrix::from_npy(&data)?);
+    }
+    Ok(arrays)
+}
+
+// the arrays stored uncompressed, as numpy.savez does
+pub fn npz_bytes(arrays: &BTreeMap<String, Matrix>, dtype: NpyType) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut central_directory = vec![];
+    for (name, matrix) in arrays {
+        let name = format!("{}.npy", name);
+        let data = matrix.to_npy(dtype);
+        let offset = bytes.len() as u32;
+
+        // fields shared by the local and central headers: version needed,
+        // flags, method, time, date of 1980-01-01, crc and sizes
+        let mut fields = vec![];
+        fields.extend_from_slice(&20u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&STORED.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0x21u16.to_le_bytes());
+        fields.extend_from_slice(&crc32(&data).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+
+        bytes.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+        bytes.extend_from_slice(&fields);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&data);
+
+        central_directory.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes());
+        central_directory.extend_from_slice(&fields);
+        // comment length, disk, internal and external attributes
+        central_directory.extend_from_slice(&[0; 10]);
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = bytes.len() as u32;
+    bytes.extend_from_slice(&central_directory);
+    bytes.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&directory_offset.to_le_bytes());
+    bytes.extend_from_slice(&[0; 2]);
+    bytes
+}
+
+pub fn load_npz(path: &str) -> BTreeMap<String, Matrix> {
+    let bytes = fs::read(path).expect("Loading path is invalid");
+    parse_npz(&bytes).unwrap_or_else(|error| panic!("{}: {}", path, error))
+}
+
+pub fn save_npz(path: &str, arrays: &BTreeMap<String, Matrix>) {
+    fs::write(path, npz_bytes(arrays, NpyType::F64))
+        .expect("Could not save the arrays at the given path.");
+}
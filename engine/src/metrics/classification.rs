@@ -0,0 +1,319 @@
+use crate::losses::clamp_probability;
+use crate::maths::Matrix;
+use crate::metrics::{class_of, Average, ConfusionMatrix, Metric};
+
+// share of the samples whose predicted class is the expected one
+#[derive(Default)]
+pub struct Accuracy {
+    correct: usize,
+    total: usize,
+}
+
+impl Accuracy {
+    pub fn new() -> Accuracy {
+        Accuracy::default()
+    }
+}
+
+impl Metric for Accuracy {
+    fn name(&self) -> String {
+        "accuracy".to_string()
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        self.correct += (class_of(output) == class_of(expected)) as usize;
+        self.total += 1;
+    }
+
+    fn result(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.correct as f64 / self.total as f64
+    }
+
+    fn reset(&mut self) {
+        *self = Accuracy::default();
+    }
+}
+
+// share of the samples whose expected class is among the k highest outputs
+pub struct TopKAccuracy {
+    pub k: usize,
+    correct: usize,
+    total: usize,
+}
+
+impl TopKAccuracy {
+    pub fn new(k: usize) -> TopKAccuracy {
+        assert!(k > 0, "k must be positive");
+        TopKAccuracy {
+            k,
+            correct: 0,
+            total: 0,
+        }
+    }
+}
+
+impl Metric for TopKAccuracy {
+    fn name(&self) -> String {
+        format!("top_{}_accuracy", self.k)
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        let class = class_of(expected);
+        let correct = if output.len() == 1 {
+            // a single output ranks the two classes of a binary problem
+            self.k > 1 || class_of(output) == class
+        } else {
+            // outputs strictly above the expected one, ties being in its favour
+            let above = (0..output.len())
+                .filter(|&i| output.get(i) > output.get(class))
+                .count();
+            above < self.k
+        };
+        self.correct += correct as usize;
+        self.total += 1;
+    }
+
+    fn result(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.correct as f64 / self.total as f64
+    }
+
+    fn reset(&mut self) {
+        self.correct = 0;
+        self.total = 0;
+    }
+}
+
+fn average_suffix(average: Average) -> String {
+    match average {
+        Average::Macro => "macro".to_string(),
+        Average::Micro => "micro".to_string(),
+        Average::Class(class) => format!("class_{}", class),
+    }
+}
+
+pub struct Precision {
+    pub average: Average,
+    confusion_matrix: ConfusionMatrix,
+}
+
+impl Precision {
+    pub fn new(nb_classes: usize, average: Average) -> Precision {
+        Precision {
+            average,
+            confusion_matrix: ConfusionMatrix::new(nb_classes),
+        }
+    }
+}
+
+impl Metric for Precision {
+    fn name(&self) -> String {
+        format!("precision_{}", average_suffix(self.average))
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        self.confusion_matrix.update(output, expected);
+    }
+
+    fn result(&self) -> f64 {
+        self.confusion_matrix.precision(self.average)
+    }
+
+    fn reset(&mut self) {
+        self.confusion_matrix.reset();
+    }
+}
+
+pub struct Recall {
+    pub average: Average,
+    confusion_matrix: ConfusionMatrix,
+}
+
+impl Recall {
+    pub fn new(nb_classes: usize, average: Average) -> Recall {
+        Recall {
+            average,
+            confusion_matrix: ConfusionMatrix::new(nb_classes),
+        }
+    }
+}
+
+impl Metric for Recall {
+    fn name(&self) -> String {
+        format!("recall_{}", average_suffix(self.average))
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        self.confusion_matrix.update(output, expected);
+    }
+
+    fn result(&self) -> f64 {
+        self.confusion_matrix.recall(self.average)
+    }
+
+    fn reset(&mut self) {
+        self.confusion_matrix.reset();
+    }
+}
+
+pub struct F1Score {
+    pub average: Average,
+    confusion_matrix: ConfusionMatrix,
+}
+
+impl F1Score {
+    pub fn new(nb_classes: usize, average: Average) -> F1Score {
+        F1Score {
+            average,
+            confusion_matrix: ConfusionMatrix::new(nb_classes),
+        }
+    }
+}
+
+impl Metric for F1Score {
+    fn name(&self) -> String {
+        format!("f1_{}", average_suffix(self.average))
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        self.confusion_matrix.update(output, expected);
+    }
+
+    fn result(&self) -> f64 {
+        self.confusion_matrix.f1(self.average)
+    }
+
+    fn reset(&mut self) {
+        self.confusion_matrix.reset();
+    }
+}
+
+// area under the ROC curve of one class against the others. The score of a
+// sample is the output of that class, or the single output of a binary
+// network. Unlike the other metrics it has to keep every score.
+pub struct RocAuc {
+    pub class: usize,
+    scores: Vec<(f64, bool)>,
+}
+
+impl RocAuc {
+    // binary problems, with one output or two
+    pub fn new() -> RocAuc {
+        RocAuc::one_vs_rest(1)
+    }
+
+    pub fn one_vs_rest(class: usize) -> RocAuc {
+        RocAuc {
+            class,
+            scores: vec![],
+        }
+    }
+}
+
+impl Default for RocAuc {
+    fn default() -> RocAuc {
+        RocAuc::new()
+    }
+}
+
+impl Metric for RocAuc {
+    fn name(&self) -> String {
+        "roc_auc".to_string()
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        let score = if output.len() == 1 {
+            output.get(0)
+        } else {
+            output.get(self.class)
+        };
+        self.scores.push((score, class_of(expected) == self.class));
+    }
+
+    // probability that a positive sample scores above a negative one, ties
+    // counting for half
+    fn result(&self) -> f64 {
+        let mut scores = self.scores.clone();
+        scores.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let nb_positives = scores.iter().filter(|(_, positive)| *positive).count();
+        let nb_negatives = scores.len() - nb_positives;
+        if nb_positives == 0 || nb_negatives == 0 {
+            return 0.0;
+        }
+
+        // sum of the ranks of the positive samples, tied scores sharing
+        // their average rank
+        let mut rank_sum = 0.0;
+        let mut i = 0;
+        while i < scores.len() {
+            let mut j = i;
+            while j < scores.len() && scores[j].0 == scores[i].0 {
+                j += 1;
+            }
+            let average_rank = (i + j + 1) as f64 / 2.0;
+            let tied_positives = scores[i..j]
+                .iter()
+                .filter(|(_, positive)| *positive)
+                .count();
+            rank_sum += average_rank * tied_positives as f64;
+            i = j;
+        }
+
+        let positives = nb_positives as f64;
+        (rank_sum - positives * (positives + 1.0) / 2.0) / (positives * nb_negatives as f64)
+    }
+
+    fn reset(&mut self) {
+        self.scores.clear();
+    }
+}
+
+// mean cross-entropy of the predicted probabilities, the binary one for a
+// single output
+#[derive(Default)]
+pub struct LogLoss {
+    sum: f64,
+    total: usize,
+}
+
+impl LogLoss {
+    pub fn new() -> LogLoss {
+        LogLoss::default()
+    }
+}
+
+impl Metric for LogLoss {
+    fn name(&self) -> String {
+        "log_loss".to_string()
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        // clamped like the cross-entropy losses, to match the training loss
+        if output.len() == 1 {
+            let (p, y) = (clamp_probability(output.get(0)), expected.get(0));
+            self.sum -= y * p.ln() + (1.0 - y) * (1.0 - p).ln();
+        } else {
+            for i in 0..output.len() {
+                self.sum -= expected.get(i) * clamp_probability(output.get(i)).ln();
+            }
+        }
+        self.total += 1;
+    }
+
+    fn result(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.sum / self.total as f64
+    }
+
+    fn reset(&mut self) {
+        *self = LogLoss::default();
+    }
+}
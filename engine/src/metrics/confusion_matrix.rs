@@ -0,0 +1,150 @@
+use crate::maths::Matrix;
+use crate::metrics::{class_of, Metric};
+
+// how per-class scores are reduced to a single value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Average {
+    // unweighted mean of the scores of each class
+    Macro,
+    // score of the counts summed over every class
+    Micro,
+    // score of a single class
+    Class(usize),
+}
+
+// counts[expected][predicted] over the samples seen so far. As a metric it
+// reports the accuracy, the session keeping the matrix of its last test.
+#[derive(Clone, Debug)]
+pub struct ConfusionMatrix {
+    counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(nb_classes: usize) -> ConfusionMatrix {
+        ConfusionMatrix {
+            counts: vec![vec![0; nb_classes]; nb_classes],
+        }
+    }
+
+    pub fn nb_classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn counts(&self) -> &[Vec<usize>] {
+        &self.counts
+    }
+
+    pub fn get(&self, expected: usize, predicted: usize) -> usize {
+        self.counts[expected][predicted]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts
+            .iter()
+            .map(|row| row.iter().sum::<usize>())
+            .sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        let correct = (0..self.nb_classes())
+            .map(|c| self.counts[c][c])
+            .sum::<usize>();
+        ratio(correct, self.total())
+    }
+
+    fn true_positives(&self, class: usize) -> usize {
+        self.counts[class][class]
+    }
+
+    // samples predicted as the class
+    fn predicted(&self, class: usize) -> usize {
+        self.counts.iter().map(|row| row[class]).sum()
+    }
+
+    // samples actually of the class
+    fn actual(&self, class: usize) -> usize {
+        self.counts[class].iter().sum()
+    }
+
+    pub fn precision(&self, average: Average) -> f64 {
+        self.average(average, |c| self.predicted(c))
+    }
+
+    pub fn recall(&self, average: Average) -> f64 {
+        self.average(average, |c| self.actual(c))
+    }
+
+    pub fn f1(&self, average: Average) -> f64 {
+        match average {
+            Average::Macro => {
+                let classes = 0..self.nb_classes();
+                classes.map(|c| self.f1(Average::Class(c))).sum::<f64>() / self.nb_classes() as f64
+            }
+            _ => {
+                let precision = self.precision(average);
+                let recall = self.recall(average);
+                if precision + recall == 0.0 {
+                    0.0
+                } else {
+                    2.0 * precision * recall / (precision + recall)
+                }
+            }
+        }
+    }
+
+    // true positives over the given denominator, a class without any
+    // sample scoring 0
+    fn average<F: Fn(usize) -> usize>(&self, average: Average, denominator: F) -> f64 {
+        match average {
+            Average::Class(c) => ratio(self.true_positives(c), denominator(c)),
+            Average::Micro => {
+                let classes = 0..self.nb_classes();
+                let true_positives = classes.clone().map(|c| self.true_positives(c)).sum();
+                ratio(true_positives, classes.map(denominator).sum())
+            }
+            Average::Macro => {
+                let classes = 0..self.nb_classes();
+                let scores = classes.map(|c| ratio(self.true_positives(c), denominator(c)));
+                scores.sum::<f64>() / self.nb_classes() as f64
+            }
+        }
+    }
+}
+
+impl Metric for ConfusionMatrix {
+    fn name(&self) -> String {
+        "confusion_matrix_accuracy".to_string()
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        let expected = class_of(expected);
+        let predicted = class_of(output);
+        assert!(
+            expected < self.nb_classes() && predicted < self.nb_classes(),
+            "The confusion matrix has fewer classes than the network outputs"
+        );
+        self.counts[expected][predicted] += 1;
+    }
+
+    fn result(&self) -> f64 {
+        self.accuracy()
+    }
+
+    fn reset(&mut self) {
+        for row in self.counts.iter_mut() {
+            row.iter_mut().for_each(|count| *count = 0);
+        }
+    }
+
+    fn confusion_matrix(&self) -> Option<&ConfusionMatrix> {
+        Some(self)
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
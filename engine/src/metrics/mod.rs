@@ -0,0 +1,33 @@
+mod classification;
+mod confusion_matrix;
+mod regression;
+
+use crate::maths::Matrix;
+pub use classification::{Accuracy, F1Score, LogLoss, Precision, Recall, RocAuc, TopKAccuracy};
+pub use confusion_matrix::{Average, ConfusionMatrix};
+pub use regression::{MeanAbsoluteError, RSquared, RootMeanSquaredError};
+
+// accumulates the predictions of a network one sample at a time, so that
+// the metric of a whole dataset never needs all of its outputs at once
+pub trait Metric: Send {
+    fn name(&self) -> String;
+    fn update(&mut self, output: &Matrix, expected: &Matrix);
+    // value over the samples seen since the last reset
+    fn result(&self) -> f64;
+    fn reset(&mut self);
+
+    // counts behind the metric, for the metrics keeping a confusion matrix
+    fn confusion_matrix(&self) -> Option<&ConfusionMatrix> {
+        None
+    }
+}
+
+// class of a one-hot or probability vector, a single output standing for
+// the positive class of a binary problem
+pub fn class_of(values: &Matrix) -> usize {
+    if values.len() == 1 {
+        (values.get(0) >= 0.5) as usize
+    } else {
+        values.argmax()
+    }
+}
@@ -0,0 +1,141 @@
+use crate::maths::Matrix;
+use crate::metrics::Metric;
+
+// the regression metrics treat every output component as one prediction
+
+#[derive(Default)]
+pub struct MeanAbsoluteError {
+    sum: f64,
+    total: usize,
+}
+
+impl MeanAbsoluteError {
+    pub fn new() -> MeanAbsoluteError {
+        MeanAbsoluteError::default()
+    }
+}
+
+impl Metric for MeanAbsoluteError {
+    fn name(&self) -> String {
+        "mae".to_string()
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        for i in 0..output.len() {
+            self.sum += (output.get(i) - expected.get(i)).abs();
+        }
+        self.total += output.len();
+    }
+
+    fn result(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.sum / self.total as f64
+    }
+
+    fn reset(&mut self) {
+        *self = MeanAbsoluteError::default();
+    }
+}
+
+#[derive(Default)]
+pub struct RootMeanSquaredError {
+    sum: f64,
+    total: usize,
+}
+
+impl RootMeanSquaredError {
+    pub fn new() -> RootMeanSquaredError {
+        RootMeanSquaredError::default()
+    }
+}
+
+impl Metric for RootMeanSquaredError {
+    fn name(&self) -> String {
+        "rmse".to_string()
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        for i in 0..output.len() {
+            self.sum += (output.get(i) - expected.get(i)).powi(2);
+        }
+        self.total += output.len();
+    }
+
+    fn result(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.sum / self.total as f64).sqrt()
+    }
+
+    fn reset(&mut self) {
+        *self = RootMeanSquaredError::default();
+    }
+}
+
+// coefficient of determination 1 - SSres / SStot of every output component,
+// averaged over the components so that their scales do not mix. SStot is
+// streamed with Welford's algorithm.
+#[derive(Default)]
+pub struct RSquared {
+    components: Vec<SquaredErrors>,
+    total: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+struct SquaredErrors {
+    residuals: f64,
+    mean: f64,
+    deviations: f64,
+}
+
+impl RSquared {
+    pub fn new() -> RSquared {
+        RSquared::default()
+    }
+}
+
+impl Metric for RSquared {
+    fn name(&self) -> String {
+        "r2".to_string()
+    }
+
+    fn update(&mut self, output: &Matrix, expected: &Matrix) {
+        if self.components.is_empty() {
+            self.components = vec![SquaredErrors::default(); output.len()];
+        }
+        assert_eq!(
+            self.components.len(),
+            output.len(),
+            "Every sample must have the same number of outputs"
+        );
+        self.total += 1;
+        for (i, component) in self.components.iter_mut().enumerate() {
+            let y = expected.get(i);
+            component.residuals += (output.get(i) - y).powi(2);
+            let delta = y - component.mean;
+            component.mean += delta / self.total as f64;
+            component.deviations += delta * (y - component.mean);
+        }
+    }
+
+    fn result(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let r2 = self.components.iter().map(|component| {
+            if component.deviations <= 0.0 {
+                0.0
+            } else {
+                1.0 - component.residuals / component.deviations
+            }
+        });
+        r2.sum::<f64>() / self.components.len() as f64
+    }
+
+    fn reset(&mut self) {
+        *self = RSquared::default();
+    }
+}
@@ -1,32 +1,36 @@
 use std::borrow::Borrow;
-use crate::activations::DenseActivation;
-use crate::losses::Loss;
-use crate::maths::Matrix;
-use crate::networks::network_operations::{feed_forward_generics, load_network_generics, back_propagation_generics, save_network_generics, update_weights_generics, compute_output_delta_generics};
-use crate::networks::{Network, SupervisedNetwork, DEFAULT_EPSILON_VALUE};
+use crate::activations::Activation;
+use crate::losses::{Loss, LossFunction};
+use crate::maths::{save_npz, Matrix};
+use crate::networks::network_operations::{feed_forward_generics, load_network_generics, back_propagation_generics, save_network_generics, compute_output_delta_generics, compute_gradients_generics, apply_gradients_generics, regularization_penalty_generics};
+use crate::networks::{Gradients, Network, Regularization, SupervisedNetwork};
 use crate::shapes::DenseShape;
+use std::collections::BTreeMap;
 use std::fs;
+use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct DenseNetwork {
     nb_layers: usize,
-    pub loss: Loss,
-    activations: Vec<DenseActivation>,
+    pub loss: Arc<dyn LossFunction>,
+    activations: Vec<Arc<dyn Activation>>,
     weights: Vec<Matrix>,
 
     // line matrix, represents the bias of the hidden layers and output layer
     biases: Vec<Matrix>,
     raw_values: Vec<Matrix>,
     values: Vec<Matrix>,
-    epsilon: f64,
+    // one per layer of weights
+    regularizations: Vec<Regularization>,
+    weight_decay: f64,
 }
 
 impl DenseNetwork {
-    pub fn new(
-        activations: Vec<DenseActivation>,
-        loss: Loss,
-        shape: Vec<DenseShape>,
-        epsilon: Option<f64>,
-    ) -> DenseNetwork {
+    pub fn new<A, L>(activations: Vec<A>, loss: L, shape: Vec<DenseShape>) -> DenseNetwork
+    where
+        A: Into<Arc<dyn Activation>>,
+        L: Into<Arc<dyn LossFunction>>,
+    {
         assert_eq!(activations.len(), shape.len() - 1);
 
         let mut weights = Vec::with_capacity(shape.len() - 1);
@@ -34,9 +38,9 @@ impl DenseNetwork {
         let mut values = Vec::with_capacity(shape.len());
         let mut raw_values = Vec::with_capacity(shape.len());
 
-        for i in 0..shape.len() {
-            values.push(Matrix::new(1, shape[i].range));
-            raw_values.push(Matrix::new(1, shape[i].range));
+        for layer in shape.iter() {
+            values.push(Matrix::new(1, layer.range));
+            raw_values.push(Matrix::new(1, layer.range));
         }
 
         for i in 0..(shape.len() - 1) {
@@ -46,24 +50,137 @@ impl DenseNetwork {
 
         DenseNetwork {
             nb_layers: shape.len(),
-            loss,
-            activations,
+            loss: loss.into(),
+            activations: activations.into_iter().map(Into::into).collect(),
             weights,
             biases,
             raw_values,
             values,
-            epsilon: epsilon.unwrap_or(DEFAULT_EPSILON_VALUE),
+            regularizations: vec![Regularization::default(); shape.len() - 1],
+            weight_decay: 0.0,
+        }
+    }
+
+    // applies the regularization to every layer of weights
+    pub fn with_regularization(mut self, regularization: Regularization) -> DenseNetwork {
+        self.regularizations = vec![regularization; self.nb_layers - 1];
+        self
+    }
+
+    // the layer is the index of the layer of weights, 0 connecting the inputs
+    // to the first hidden layer
+    pub fn with_layer_regularization(
+        mut self,
+        layer: usize,
+        regularization: Regularization,
+    ) -> DenseNetwork {
+        assert!(layer < self.regularizations.len(), "The network has no layer of weights {}", layer);
+        self.regularizations[layer] = regularization;
+        self
+    }
+
+    pub fn with_weight_decay(mut self, weight_decay: f64) -> DenseNetwork {
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    pub fn weights(&self) -> &[Matrix] {
+        &self.weights
+    }
+
+    pub fn biases(&self) -> &[Matrix] {
+        &self.biases
+    }
+
+    // weights and biases, to be modified in place without changing shapes
+    pub fn parameters_mut(&mut self) -> (&mut [Matrix], &mut [Matrix]) {
+        (&mut self.weights, &mut self.biases)
+    }
+
+    // weights and biases of every layer as weights_0, biases_0, weights_1...
+    // the weights of a layer have the shape (outputs, inputs) and its biases
+    // the shape (outputs, 1)
+    pub fn named_parameters(&self) -> BTreeMap<String, Matrix> {
+        let mut parameters = BTreeMap::new();
+        for (l, (weights, biases)) in self.weights.iter().zip(&self.biases).enumerate() {
+            parameters.insert(format!("weights_{}", l), weights.clone());
+            parameters.insert(format!("biases_{}", l), biases.clone());
+        }
+        parameters
+    }
+
+    // the named parameters as a .npz archive, to be read with numpy.load
+    pub fn save_npz(&self, path: &str) {
+        save_npz(path, &self.named_parameters());
+    }
+
+    // first layer of weights holding a NaN or an infinite parameter
+    pub fn first_non_finite_layer(&self) -> Option<usize> {
+        (0..self.weights.len())
+            .find(|&l| !self.weights[l].is_finite() || !self.biases[l].is_finite())
+    }
+
+    pub fn regularizations(&self) -> &[Regularization] {
+        &self.regularizations
+    }
+
+    pub fn weight_decay(&self) -> f64 {
+        self.weight_decay
+    }
+
+    fn options(&self) -> Vec<String> {
+        let mut options = vec![];
+        for (layer, regularization) in self.regularizations.iter().enumerate() {
+            if !regularization.is_none() {
+                options.push(format!("Regularization {} {}", layer, regularization));
+            }
+        }
+        if self.weight_decay != 0.0 {
+            options.push(format!("WeightDecay {}", self.weight_decay));
+        }
+        options
+    }
+
+    fn load_option(&mut self, line: &str) {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "Regularization" => {
+                let (layer, regularization) = value
+                    .split_once(' ')
+                    .expect("Invalid regularization in saved network");
+                let layer = layer.parse::<usize>().expect("Invalid regularization layer");
+                self.regularizations[layer] = regularization
+                    .parse::<Regularization>()
+                    .expect("Invalid regularization in saved network");
+            }
+            "WeightDecay" => {
+                self.weight_decay = value.parse::<f64>().expect("Invalid weight decay");
+            }
+            "" => {}
+            _ => panic!("Unknown option {} in saved network", key),
         }
     }
 }
 
 impl SupervisedNetwork for DenseNetwork {
     fn compute_output_delta(&self, output: &Matrix) -> Matrix {
+        self.compute_weighted_output_delta(output, None, None)
+    }
+
+    fn compute_weighted_output_delta(
+        &self,
+        output: &Matrix,
+        class_weights: Option<&Matrix>,
+        sample_weight: Option<f64>,
+    ) -> Matrix {
         compute_output_delta_generics(
             &self.activations[self.activations.len() - 1],
+            &self.raw_values[self.raw_values.len() - 1],
             &self.values[self.values.len() - 1],
             &self.loss,
             output,
+            class_weights,
+            sample_weight,
         )
     }
 
@@ -82,17 +199,24 @@ impl SupervisedNetwork for DenseNetwork {
         deltas
     }
 
-    fn update_weights(&mut self, deltas: Vec<Matrix>, learning_rate: f64) {
-        update_weights_generics(
-            deltas,
+    fn compute_gradients(&self, deltas: &[Matrix]) -> Gradients {
+        compute_gradients_generics(deltas, &self.values)
+    }
+
+    fn apply_gradients(&mut self, gradients: &Gradients, learning_rate: f64) {
+        apply_gradients_generics(
+            gradients,
             learning_rate,
-            &self.values,
             &mut self.weights,
             &mut self.biases,
-            self.nb_layers,
-            self.epsilon,
+            &self.regularizations,
+            self.weight_decay,
         );
     }
+
+    fn regularization_penalty(&self) -> f64 {
+        regularization_penalty_generics(&self.weights, &self.regularizations)
+    }
 }
 
 impl Network for DenseNetwork {
@@ -126,33 +250,33 @@ impl Network for DenseNetwork {
     fn load_network(path: &str) -> DenseNetwork {
         let mut weights: Vec<Matrix> = vec![];
         let mut biases: Vec<Matrix> = vec![];
-        let mut activations: Vec<DenseActivation> = vec![];
-        let mut loss: Loss = Loss::CategoricalCrossEntropy;
+        let mut activations: Vec<Arc<dyn Activation>> = vec![];
+        let mut loss: Arc<dyn LossFunction> = Arc::new(Loss::CategoricalCrossEntropy(0.0));
 
         let mut shape: Vec<DenseShape> = vec![];
 
         let contents = fs::read_to_string(path).expect("Loading path is invalid");
 
-        let lines = contents.split("\n").collect::<Vec<_>>();
+        let lines = contents.split('\n').collect::<Vec<_>>();
 
-        load_network_generics(
+        let options_line = load_network_generics(
             &mut weights,
             &mut biases,
             &mut activations,
             &mut loss,
             &mut shape,
-            lines,
+            &lines,
         );
 
         let mut values = Vec::with_capacity(shape.len());
         let mut raw_values = Vec::with_capacity(shape.len());
 
-        for i in 0..shape.len() {
-            values.push(Matrix::new(1, shape[i].range));
-            raw_values.push(Matrix::new(1, shape[i].range));
+        for layer in shape.iter() {
+            values.push(Matrix::new(1, layer.range));
+            raw_values.push(Matrix::new(1, layer.range));
         }
 
-        DenseNetwork {
+        let mut network = DenseNetwork {
             nb_layers: shape.len(),
             loss,
             activations,
@@ -160,8 +284,14 @@ impl Network for DenseNetwork {
             biases,
             raw_values,
             values,
-            epsilon: DEFAULT_EPSILON_VALUE,
+            regularizations: vec![Regularization::default(); shape.len() - 1],
+            weight_decay: 0.0,
+        };
+
+        for line in lines.iter().skip(options_line) {
+            network.load_option(line.trim());
         }
+        network
     }
 
     fn save_network(&self, path: &str) {
@@ -173,6 +303,7 @@ impl Network for DenseNetwork {
             &self.loss,
             &self.weights,
             &self.biases,
+            self.options(),
         );
     }
 }
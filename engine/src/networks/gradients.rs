@@ -0,0 +1,61 @@
+use crate::maths::Matrix;
+
+// gradients of the loss with respect to the parameters of each layer,
+// laid out like the weights and biases of the network
+#[derive(Clone)]
+pub struct Gradients {
+    pub weights: Vec<Matrix>,
+    pub biases: Vec<Matrix>,
+}
+
+impl Gradients {
+    pub fn zeros_like(weights: &[Matrix], biases: &[Matrix]) -> Gradients {
+        Gradients {
+            weights: weights.iter().map(|w| Matrix::new(w.w, w.h)).collect(),
+            biases: biases.iter().map(|b| Matrix::new(b.w, b.h)).collect(),
+        }
+    }
+
+    pub fn add(&mut self, other: &Gradients) {
+        for l in 0..self.weights.len() {
+            self.weights[l] = &self.weights[l] + &other.weights[l];
+            self.biases[l] = &self.biases[l] + &other.biases[l];
+        }
+    }
+
+    pub fn scale(&mut self, factor: f64) {
+        for l in 0..self.weights.len() {
+            self.weights[l] = self.weights[l].multiply(factor);
+            self.biases[l] = self.biases[l].multiply(factor);
+        }
+    }
+
+    pub fn clip_by_value(&mut self, max_value: f64) {
+        for matrix in self.weights.iter_mut().chain(self.biases.iter_mut()) {
+            matrix.map2::<f64>(|x, m| x.clamp(-m, m), max_value);
+        }
+    }
+
+    pub fn global_norm(&self) -> f64 {
+        self.weights
+            .iter()
+            .chain(self.biases.iter())
+            .map(|matrix| matrix.powi(2).sum())
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    // rescales every gradient by the same factor so their joint norm does
+    // not exceed max_norm, keeping the direction of the update
+    pub fn clip_by_global_norm(&mut self, max_norm: f64) {
+        let norm = self.global_norm();
+        if norm > max_norm {
+            self.scale(max_norm / norm);
+        }
+    }
+
+    pub fn first_non_finite_layer(&self) -> Option<usize> {
+        (0..self.weights.len())
+            .find(|&l| !self.weights[l].is_finite() || !self.biases[l].is_finite())
+    }
+}
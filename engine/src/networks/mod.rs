@@ -1,10 +1,12 @@
 mod dense_network;
+mod gradients;
 mod network_operations;
+mod regularization;
 
 use crate::maths::Matrix;
 pub use dense_network::DenseNetwork;
-
-pub const DEFAULT_EPSILON_VALUE: f64 = 1E-6;
+pub use gradients::Gradients;
+pub use regularization::Regularization;
 
 pub trait Network {
     fn feed_forward(&mut self, input: &Matrix);
@@ -18,6 +20,21 @@ pub trait Network {
 
 pub trait SupervisedNetwork {
     fn compute_output_delta(&self, output: &Matrix) -> Matrix;
+    fn compute_weighted_output_delta(
+        &self,
+        output: &Matrix,
+        class_weights: Option<&Matrix>,
+        sample_weight: Option<f64>,
+    ) -> Matrix;
     fn feed_backward(&self, output_delta: Matrix) -> Vec<Matrix>;
-    fn update_weights(&mut self, deltas: Vec<Matrix>, learning_rate: f64);
+
+    // gradients of the last sample fed forward, given its deltas
+    fn compute_gradients(&self, deltas: &[Matrix]) -> Gradients;
+    fn apply_gradients(&mut self, gradients: &Gradients, learning_rate: f64);
+    fn regularization_penalty(&self) -> f64;
+
+    fn update_weights(&mut self, deltas: Vec<Matrix>, learning_rate: f64) {
+        let gradients = self.compute_gradients(&deltas);
+        self.apply_gradients(&gradients, learning_rate);
+    }
 }
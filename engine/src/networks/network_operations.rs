@@ -1,16 +1,17 @@
-use crate::activations::DenseActivation;
-use crate::losses::Loss;
+use crate::activations::{build_activation, Activation};
+use crate::losses::{build_loss, LossFunction};
 use crate::maths::Matrix;
+use crate::networks::{Gradients, Regularization};
 use crate::shapes::DenseShape;
 use std::fs;
-use std::str::FromStr;
+use std::sync::Arc;
 
 pub fn feed_forward_generics(
-    values: &mut Vec<Matrix>,
-    raw_values: &mut Vec<Matrix>,
-    activations: &Vec<DenseActivation>,
-    weights: &Vec<Matrix>,
-    biases: &Vec<Matrix>,
+    values: &mut [Matrix],
+    raw_values: &mut [Matrix],
+    activations: &[Arc<dyn Activation>],
+    weights: &[Matrix],
+    biases: &[Matrix],
     nb_layers: usize,
 ) {
     for i in 0..(nb_layers - 1) {
@@ -25,17 +26,17 @@ pub fn feed_forward_generics(
 pub fn load_network_generics(
     weights: &mut Vec<Matrix>,
     biases: &mut Vec<Matrix>,
-    activations: &mut Vec<DenseActivation>,
-    loss: &mut Loss,
+    activations: &mut Vec<Arc<dyn Activation>>,
+    loss: &mut Arc<dyn LossFunction>,
     shape: &mut Vec<DenseShape>,
-    lines: Vec<&str>,
-) {
-    let mut phase: usize = 0;
+    lines: &[&str],
+) -> usize {
     let mut shape_selector: usize = 0;
-    let nb_lines = lines.len();
-    for line in lines {
-        if phase == nb_lines - 1 {
-            *loss = Loss::from_str(line).unwrap();
+    let mut loss_line = lines.len() - 1;
+    for (phase, line) in lines.iter().enumerate() {
+        if phase == loss_line {
+            *loss = build_loss(line)
+                .unwrap_or_else(|| panic!("Unknown loss {}, was it registered?", line));
             break;
         }
 
@@ -45,17 +46,23 @@ pub fn load_network_generics(
                     .split(" ")
                     .map(|value| DenseShape::one_d(value.parse::<usize>().unwrap()))
                     .collect::<Vec<_>>();
+                // the loss follows the weights and biases of every layer
+                loss_line = 2 + 2 * (shape.len() - 1);
                 *weights = Vec::with_capacity(shape.len() - 1);
                 *biases = Vec::with_capacity(shape.len() - 1);
             }
             1 => {
                 *activations = line
                     .split(" ")
-                    .map(|value| DenseActivation::from_str(value).unwrap())
-                    .collect::<Vec<DenseActivation>>()
+                    .map(|value| {
+                        build_activation(value).unwrap_or_else(|| {
+                            panic!("Unknown activation {}, was it registered?", value)
+                        })
+                    })
+                    .collect::<Vec<Arc<dyn Activation>>>()
             }
             _ => {
-                if phase % 2 == 0 {
+                if phase.is_multiple_of(2) {
                     weights.push(Matrix::reshape(
                         line.split(" ")
                             .map(|value| value.parse::<f64>().unwrap())
@@ -75,101 +82,130 @@ pub fn load_network_generics(
                 }
             }
         }
-        phase += 1;
     }
+    loss_line + 1
 }
 
 pub fn save_network_generics(
     path: &str,
     shape: Vec<DenseShape>,
-    activations: &Vec<DenseActivation>,
-    loss: &Loss,
-    weights: &Vec<Matrix>,
-    biases: &Vec<Matrix>,
+    activations: &[Arc<dyn Activation>],
+    loss: &Arc<dyn LossFunction>,
+    weights: &[Matrix],
+    biases: &[Matrix],
+    options: Vec<String>,
 ) {
     let mut content: String = "".to_owned();
 
-    for i in 0..shape.len() {
+    for (i, layer) in shape.iter().enumerate() {
         if i != 0 {
-            content.push_str(" ");
+            content.push(' ');
         }
-        content.push_str(shape[i].range.to_string().as_str());
+        content.push_str(layer.range.to_string().as_str());
     }
-    content.push_str("\n");
-    for i in 0..activations.len() {
+    content.push('\n');
+    for (i, activation) in activations.iter().enumerate() {
         if i != 0 {
-            content.push_str(" ");
+            content.push(' ');
         }
-        content.push_str(activations[i].to_string().as_str());
+        content.push_str(activation.name().as_str());
     }
-    content.push_str("\n");
+    content.push('\n');
 
-    concat_weights_and_bias(&mut content, &weights, &biases);
-    content.push_str("\n");
-    content.push_str(&loss.to_string());
+    concat_weights_and_bias(&mut content, weights, biases);
+    content.push('\n');
+    content.push_str(&loss.name());
+    // optional settings, one per line, following the loss
+    for option in options {
+        content.push('\n');
+        content.push_str(&option);
+    }
 
     fs::write(path, content).expect("Could not save the network at the given path.");
 }
 
-fn concat_weights_and_bias(c: &mut String, weights: &Vec<Matrix>, biases: &Vec<Matrix>) {
+fn concat_weights_and_bias(c: &mut String, weights: &[Matrix], biases: &[Matrix]) {
     for i in 0..weights.len() {
         if i != 0 {
-            c.push_str("\n");
+            c.push('\n');
         }
         c.push_str(&weights[i].to_string());
-        c.push_str("\n");
+        c.push('\n');
         c.push_str(&biases[i].to_string());
     }
 }
 
 pub fn compute_output_delta_generics(
-    activation: &DenseActivation,
+    activation: &Arc<dyn Activation>,
+    raw_value: &Matrix,
     value: &Matrix,
-    loss: &Loss,
+    loss: &Arc<dyn LossFunction>,
     output: &Matrix,
+    class_weights: Option<&Matrix>,
+    sample_weight: Option<f64>,
 ) -> Matrix {
-    let mut d_z = value.clone();
-    activation.derivative(&mut d_z);
-    let d= loss.compute_differential_error(value, output).hadamard_dot(&d_z);
-    d
+    let gradient =
+        loss.compute_weighted_differential_error(value, output, class_weights, sample_weight);
+    activation.backward(raw_value, &gradient)
 }
 
 pub fn back_propagation_generics(
     deltas: &mut Vec<Matrix>,
-    activations: &Vec<DenseActivation>,
-    raw_values: &Vec<Matrix>,
-    weights: &Vec<Matrix>,
+    activations: &[Arc<dyn Activation>],
+    raw_values: &[Matrix],
+    weights: &[Matrix],
     nb_layers: usize,
 ) {
     for l in (1..nb_layers - 1).rev() {
-        let delta: Matrix;
-        let mut d_z = raw_values[l].clone();
-        activations[l - 1].derivative(&mut d_z);
-        delta = (&weights[l].t() * &deltas[deltas.len() - 1]).hadamard_dot(&d_z);
+        let gradient = &weights[l].t() * &deltas[deltas.len() - 1];
+        let delta = activations[l - 1].backward(&raw_values[l], &gradient);
         deltas.push(delta);
     }
     deltas.reverse();
 }
 
-pub fn update_weights_generics(
-    deltas: Vec<Matrix>,
+pub fn compute_gradients_generics(deltas: &[Matrix], values: &[Matrix]) -> Gradients {
+    // the delta of a layer is a column of its outputs and the values a column
+    // of its inputs, the gradient of the weights being their outer product
+    Gradients {
+        weights: (0..deltas.len())
+            .map(|l| &deltas[l] * &values[l].t())
+            .collect(),
+        biases: deltas.to_vec(),
+    }
+}
+
+pub fn apply_gradients_generics(
+    gradients: &Gradients,
     learning_rate: f64,
-    values: &Vec<Matrix>,
-    weights: &mut Vec<Matrix>,
-    biases: &mut Vec<Matrix>,
-    nb_layers: usize,
-    epsilon: f64,
+    weights: &mut [Matrix],
+    biases: &mut [Matrix],
+    regularizations: &[Regularization],
+    weight_decay: f64,
 ) {
-    for l in (1..nb_layers).rev() {
-        biases[l - 1] = &biases[l - 1] - &(&deltas[l - 1] * learning_rate) - epsilon;
-
-        for j in 0..deltas[l - 1].len() {
-            for k in 0..values[l - 1].len() {
-                let a = values[l - 1].get(k);
-                let d = deltas[l - 1].get(j);
-                let w = weights[l - 1].get_at(j, k);
-                weights[l - 1].set_at(j, k, w - learning_rate * (a * d) + epsilon);
-            }
+    for l in 0..weights.len() {
+        let regularization = &regularizations[l];
+        let mut step = gradients.weights[l].clone();
+        if regularization.l1 != 0.0 || regularization.l2 != 0.0 {
+            step = &step + &regularization.gradient(&weights[l]);
         }
+
+        // decoupled weight decay shrinks the weights independently of the loss
+        let decayed = weights[l].multiply(1.0 - learning_rate * weight_decay);
+        weights[l] = &decayed - &(&step * learning_rate);
+        biases[l] = &biases[l] - &(&gradients.biases[l] * learning_rate);
+
+        regularization.constrain(&mut weights[l]);
     }
 }
+
+pub fn regularization_penalty_generics(
+    weights: &[Matrix],
+    regularizations: &[Regularization],
+) -> f64 {
+    weights
+        .iter()
+        .zip(regularizations.iter())
+        .map(|(w, regularization)| regularization.penalty(w))
+        .sum()
+}
@@ -0,0 +1,141 @@
+use crate::maths::Matrix;
+use std::fmt;
+use std::str::FromStr;
+
+// penalties on the weights of one layer, l1 and l2 together being an
+// elastic-net penalty. The biases are never regularized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Regularization {
+    pub l1: f64,
+    pub l2: f64,
+    // maximal euclidean norm of the incoming weights of each neuron
+    pub max_norm: Option<f64>,
+}
+
+impl Regularization {
+    pub fn l1(l1: f64) -> Regularization {
+        Regularization {
+            l1,
+            ..Default::default()
+        }
+    }
+
+    pub fn l2(l2: f64) -> Regularization {
+        Regularization {
+            l2,
+            ..Default::default()
+        }
+    }
+
+    pub fn elastic_net(l1: f64, l2: f64) -> Regularization {
+        Regularization {
+            l1,
+            l2,
+            ..Default::default()
+        }
+    }
+
+    pub fn max_norm(max_norm: f64) -> Regularization {
+        Regularization {
+            max_norm: Some(max_norm),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_norm(mut self, max_norm: f64) -> Regularization {
+        self.max_norm = Some(max_norm);
+        self
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Regularization::default()
+    }
+
+    // l1 * sum(|w|) + l2 / 2 * sum(w^2)
+    pub fn penalty(&self, weights: &Matrix) -> f64 {
+        if self.l1 == 0.0 && self.l2 == 0.0 {
+            return 0.0;
+        }
+
+        let mut penalty = 0.0;
+        for i in 0..weights.len() {
+            let w = weights.get(i);
+            penalty += self.l1 * w.abs() + 0.5 * self.l2 * w.powi(2);
+        }
+        penalty
+    }
+
+    pub fn gradient(&self, weights: &Matrix) -> Matrix {
+        let mut gradient = Matrix::new(weights.w, weights.h);
+        for i in 0..weights.len() {
+            gradient.set(i, self.derivative(weights.get(i)));
+        }
+        gradient
+    }
+
+    // derivative of the penalty with respect to a single weight
+    pub fn derivative(&self, w: f64) -> f64 {
+        let sign = if w > 0.0 {
+            1.0
+        } else if w < 0.0 {
+            -1.0
+        } else {
+            0.0
+        };
+        self.l1 * sign + self.l2 * w
+    }
+
+    // rescales the rows exceeding the maximal norm, a row holding the
+    // incoming weights of one neuron
+    pub fn constrain(&self, weights: &mut Matrix) {
+        let max_norm = match self.max_norm {
+            Some(max_norm) => max_norm,
+            None => return,
+        };
+
+        for j in 0..weights.h {
+            let mut norm = 0.0;
+            for k in 0..weights.w {
+                norm += weights.get_at(j, k).powi(2);
+            }
+            norm = norm.sqrt();
+
+            if norm > max_norm {
+                let scale = max_norm / norm;
+                for k in 0..weights.w {
+                    let w = weights.get_at(j, k);
+                    weights.set_at(j, k, w * scale);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Regularization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max_norm {
+            Some(max_norm) => write!(f, "{} {} {}", self.l1, self.l2, max_norm),
+            None => write!(f, "{} {} none", self.l1, self.l2),
+        }
+    }
+}
+
+impl FromStr for Regularization {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Regularization, Self::Err> {
+        let words = input.split_whitespace().collect::<Vec<_>>();
+        if words.len() != 3 {
+            return Err(());
+        }
+
+        let l1 = words[0].parse::<f64>().map_err(|_| ())?;
+        let l2 = words[1].parse::<f64>().map_err(|_| ())?;
+        let max_norm = match words[2] {
+            "none" => None,
+            value => Some(value.parse::<f64>().map_err(|_| ())?),
+        };
+
+        Ok(Regularization { l1, l2, max_norm })
+    }
+}
@@ -0,0 +1,176 @@
+use crate::maths::Matrix;
+use crate::preprocessing::{check_features, columns, format_values, parse_values, Preprocessor};
+use std::str::FromStr;
+
+// expands every categorical feature into one column per category, holding
+// 1 for the category of the sample and 0 for the others
+#[derive(Clone, Debug, Default)]
+pub struct OneHotEncoder {
+    // sorted categories of each feature
+    categories: Vec<Vec<f64>>,
+}
+
+impl OneHotEncoder {
+    pub fn new() -> OneHotEncoder {
+        OneHotEncoder::default()
+    }
+
+    pub fn categories(&self) -> &[Vec<f64>] {
+        &self.categories
+    }
+}
+
+impl Preprocessor for OneHotEncoder {
+    fn name(&self) -> String {
+        let mut name = "OneHotEncoder".to_string();
+        for categories in &self.categories {
+            name.push(' ');
+            name.push_str(&format_values(categories));
+        }
+        name
+    }
+
+    fn fit(&mut self, data: &Matrix) {
+        self.categories = columns(data).into_iter().map(sorted_classes).collect();
+    }
+
+    fn transform(&self, data: &Matrix) -> Matrix {
+        check_features("OneHotEncoder", self.categories.len(), data);
+        let width = self.categories.iter().map(Vec::len).sum();
+        let mut result = Matrix::new(width, data.h);
+        for y in 0..data.h {
+            let mut offset = 0;
+            for (x, categories) in self.categories.iter().enumerate() {
+                let value = data.get_at(y, x);
+                let category = categories
+                    .iter()
+                    .position(|&category| category == value)
+                    .unwrap_or_else(|| panic!("Unknown category {} of feature {}", value, x));
+                result.set_at(y, offset + category, 1.0);
+                offset += categories.len();
+            }
+        }
+        result
+    }
+
+    // the category of each feature is the one of its largest column
+    fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        let width = self.categories.iter().map(Vec::len).sum();
+        check_features("OneHotEncoder", width, data);
+        let mut result = Matrix::new(self.categories.len(), data.h);
+        for y in 0..data.h {
+            let mut offset = 0;
+            for (x, categories) in self.categories.iter().enumerate() {
+                let category = (0..categories.len())
+                    .max_by(|&a, &b| {
+                        data.get_at(y, offset + a)
+                            .total_cmp(&data.get_at(y, offset + b))
+                    })
+                    .expect("A feature has no category");
+                result.set_at(y, x, categories[category]);
+                offset += categories.len();
+            }
+        }
+        result
+    }
+}
+
+impl FromStr for OneHotEncoder {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<OneHotEncoder, Self::Err> {
+        let mut words = input.split_whitespace();
+        if words.next() != Some("OneHotEncoder") {
+            return Err(());
+        }
+        let categories = words
+            .map(parse_values)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(())?;
+        Ok(OneHotEncoder { categories })
+    }
+}
+
+// replaces labels, such as class numbers that do not start from 0, by the
+// index of their class among the sorted labels
+#[derive(Clone, Debug, Default)]
+pub struct LabelEncoder {
+    classes: Vec<f64>,
+}
+
+impl LabelEncoder {
+    pub fn new() -> LabelEncoder {
+        LabelEncoder::default()
+    }
+
+    pub fn classes(&self) -> &[f64] {
+        &self.classes
+    }
+}
+
+impl Preprocessor for LabelEncoder {
+    fn name(&self) -> String {
+        format!("LabelEncoder {}", format_values(&self.classes))
+    }
+
+    // every value of the data is a label, whatever its column
+    fn fit(&mut self, data: &Matrix) {
+        self.classes = sorted_classes(columns(data).concat());
+    }
+
+    fn transform(&self, data: &Matrix) -> Matrix {
+        assert!(
+            !self.classes.is_empty(),
+            "The LabelEncoder must be fitted first"
+        );
+        let mut result = data.clone();
+        for i in 0..data.len() {
+            let label = data.get(i);
+            let class = self
+                .classes
+                .iter()
+                .position(|&class| class == label)
+                .unwrap_or_else(|| panic!("Unknown label {}", label));
+            result.set(i, class as f64);
+        }
+        result
+    }
+
+    fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        assert!(
+            !self.classes.is_empty(),
+            "The LabelEncoder must be fitted first"
+        );
+        let mut result = data.clone();
+        for i in 0..data.len() {
+            let class = data.get(i);
+            assert!(
+                class >= 0.0 && (class.round() as usize) < self.classes.len(),
+                "Invalid class {} for {} classes",
+                class,
+                self.classes.len()
+            );
+            result.set(i, self.classes[class.round() as usize]);
+        }
+        result
+    }
+}
+
+impl FromStr for LabelEncoder {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<LabelEncoder, Self::Err> {
+        match input.split_whitespace().collect::<Vec<_>>()[..] {
+            ["LabelEncoder", classes] => Ok(LabelEncoder {
+                classes: parse_values(classes).ok_or(())?,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+fn sorted_classes(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    values
+}
@@ -0,0 +1,112 @@
+use crate::maths::Matrix;
+use crate::preprocessing::scalers::{mean, quantile};
+use crate::preprocessing::{
+    check_features, columns, format_values, map_columns, parse_values, Preprocessor,
+};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImputeStrategy {
+    Mean,
+    Median,
+}
+
+impl fmt::Display for ImputeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImputeStrategy::Mean => write!(f, "mean"),
+            ImputeStrategy::Median => write!(f, "median"),
+        }
+    }
+}
+
+impl FromStr for ImputeStrategy {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ImputeStrategy, Self::Err> {
+        match input {
+            "mean" => Ok(ImputeStrategy::Mean),
+            "median" => Ok(ImputeStrategy::Median),
+            _ => Err(()),
+        }
+    }
+}
+
+// replaces the missing values of every feature, such as the empty fields
+// read by a CsvLoader, by the mean or median of the feature. A feature
+// missing everywhere is filled with 0.
+#[derive(Clone, Debug)]
+pub struct Imputer {
+    strategy: ImputeStrategy,
+    values: Vec<f64>,
+}
+
+impl Imputer {
+    pub fn new(strategy: ImputeStrategy) -> Imputer {
+        Imputer {
+            strategy,
+            values: vec![],
+        }
+    }
+
+    pub fn strategy(&self) -> ImputeStrategy {
+        self.strategy
+    }
+
+    // value replacing the missing ones of each feature
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+}
+
+impl Preprocessor for Imputer {
+    fn name(&self) -> String {
+        format!("Imputer {} {}", self.strategy, format_values(&self.values))
+    }
+
+    fn fit(&mut self, data: &Matrix) {
+        self.values = columns(data)
+            .into_iter()
+            .map(|mut column| match self.strategy {
+                ImputeStrategy::Mean => mean(&column),
+                ImputeStrategy::Median => {
+                    column.sort_by(f64::total_cmp);
+                    quantile(&column, 0.5)
+                }
+            })
+            .collect();
+    }
+
+    fn transform(&self, data: &Matrix) -> Matrix {
+        check_features("Imputer", self.values.len(), data);
+        map_columns(data, |x, value| {
+            if value.is_nan() {
+                self.values[x]
+            } else {
+                value
+            }
+        })
+    }
+
+    // which values were missing is not known anymore, the data is returned
+    // as it is
+    fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        check_features("Imputer", self.values.len(), data);
+        data.clone()
+    }
+}
+
+impl FromStr for Imputer {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Imputer, Self::Err> {
+        match input.split_whitespace().collect::<Vec<_>>()[..] {
+            ["Imputer", strategy, values] => Ok(Imputer {
+                strategy: strategy.parse()?,
+                values: parse_values(values).ok_or(())?,
+            }),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+mod encoders;
+mod imputer;
+mod pipeline;
+mod scalers;
+
+use crate::maths::Matrix;
+use crate::registry::{Constructor, Registry};
+pub use encoders::{LabelEncoder, OneHotEncoder};
+pub use imputer::{ImputeStrategy, Imputer};
+pub use pipeline::{load_model, save_model, Pipeline};
+pub use scalers::{MinMaxScaler, RobustScaler, StandardScaler};
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+// transforms data whose rows are samples and columns features, such as the
+// inputs of a dataset stacked by inputs_matrix. Missing values are NaN and
+// are left out when fitting.
+pub trait Preprocessor: Send + Sync {
+    // descriptor saved along with the fitted parameters, its first word is
+    // the name the preprocessor must be registered under to be loaded back
+    fn name(&self) -> String;
+
+    fn fit(&mut self, data: &Matrix);
+    fn transform(&self, data: &Matrix) -> Matrix;
+    fn inverse_transform(&self, data: &Matrix) -> Matrix;
+
+    fn fit_transform(&mut self, data: &Matrix) -> Matrix {
+        self.fit(data);
+        self.transform(data)
+    }
+
+    // one sample as a column matrix, as fed to a network
+    fn transform_sample(&self, sample: &Matrix) -> Matrix {
+        self.transform(&sample.t()).t()
+    }
+
+    // such as the output of a network trained on scaled targets
+    fn inverse_transform_sample(&self, sample: &Matrix) -> Matrix {
+        self.inverse_transform(&sample.t()).t()
+    }
+}
+
+// the inputs of the samples as the rows of a matrix
+pub fn inputs_matrix(data: &[(Matrix, Matrix)]) -> Matrix {
+    stack_rows(data.iter().map(|(input, _)| input))
+}
+
+pub fn outputs_matrix(data: &[(Matrix, Matrix)]) -> Matrix {
+    stack_rows(data.iter().map(|(_, output)| output))
+}
+
+fn stack_rows<'a, I: Iterator<Item = &'a Matrix>>(rows: I) -> Matrix {
+    let mut values = vec![];
+    let mut nb_rows = 0;
+    let mut width = None;
+    for row in rows {
+        assert_eq!(
+            *width.get_or_insert(row.len()),
+            row.len(),
+            "Every sample must have the same size"
+        );
+        values.extend((0..row.len()).map(|i| row.get(i)));
+        nb_rows += 1;
+    }
+    Matrix::reshape(values, width.unwrap_or(0), nb_rows)
+}
+
+// values of each column, the missing ones left out
+fn columns(data: &Matrix) -> Vec<Vec<f64>> {
+    (0..data.w)
+        .map(|x| {
+            (0..data.h)
+                .map(|y| data.get_at(y, x))
+                .filter(|value| !value.is_nan())
+                .collect()
+        })
+        .collect()
+}
+
+// applies f to every value along with its column
+fn map_columns<F: Fn(usize, f64) -> f64>(data: &Matrix, f: F) -> Matrix {
+    let mut result = data.clone();
+    for y in 0..data.h {
+        for x in 0..data.w {
+            result.set_at(y, x, f(x, data.get_at(y, x)));
+        }
+    }
+    result
+}
+
+fn check_features(name: &str, expected: usize, data: &Matrix) {
+    assert!(expected != 0, "The {} must be fitted first", name);
+    assert_eq!(
+        data.w, expected,
+        "The {} was fitted on {} features",
+        name, expected
+    );
+}
+
+// values of a descriptor, written as [1,2.5,-3]
+fn format_values(values: &[f64]) -> String {
+    let values = values.iter().map(f64::to_string).collect::<Vec<_>>();
+    format!("[{}]", values.join(","))
+}
+
+fn parse_values(word: &str) -> Option<Vec<f64>> {
+    let values = word.strip_prefix('[')?.strip_suffix(']')?;
+    if values.is_empty() {
+        return Some(vec![]);
+    }
+    values.split(',').map(|value| value.parse().ok()).collect()
+}
+
+// two vectors of parameters, one value per feature each
+fn parse_pair(first: &str, second: &str) -> Option<(Vec<f64>, Vec<f64>)> {
+    let (first, second) = (parse_values(first)?, parse_values(second)?);
+    (first.len() == second.len()).then_some((first, second))
+}
+
+static REGISTRY: OnceLock<RwLock<Registry<dyn Preprocessor>>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<Registry<dyn Preprocessor>> {
+    REGISTRY.get_or_init(|| {
+        let mut registry: Registry<dyn Preprocessor> = Registry::new();
+        registry.register("StandardScaler", build::<StandardScaler>);
+        registry.register("MinMaxScaler", build::<MinMaxScaler>);
+        registry.register("RobustScaler", build::<RobustScaler>);
+        registry.register("OneHotEncoder", build::<OneHotEncoder>);
+        registry.register("LabelEncoder", build::<LabelEncoder>);
+        registry.register("Imputer", build::<Imputer>);
+        RwLock::new(registry)
+    })
+}
+
+fn build<P: Preprocessor + FromStr + 'static>(descriptor: &str) -> Option<Box<dyn Preprocessor>> {
+    P::from_str(descriptor)
+        .ok()
+        .map(|preprocessor| Box::new(preprocessor) as Box<dyn Preprocessor>)
+}
+
+pub fn register_preprocessor(name: &str, constructor: Constructor<dyn Preprocessor>) {
+    registry().write().unwrap().register(name, constructor);
+}
+
+pub fn build_preprocessor(descriptor: &str) -> Option<Box<dyn Preprocessor>> {
+    registry().read().unwrap().build(descriptor)
+}
@@ -0,0 +1,137 @@
+use crate::maths::Matrix;
+use crate::networks::{DenseNetwork, Network};
+use crate::preprocessing::{build_preprocessor, inputs_matrix, outputs_matrix, Preprocessor};
+use std::fs;
+use std::path::Path;
+
+// preprocessors applied one after the other, each one fitted on the output
+// of the previous ones. Saved next to a network, it gives inference the
+// exact transform the network was trained with.
+#[derive(Default)]
+pub struct Pipeline {
+    steps: Vec<Box<dyn Preprocessor>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    pub fn with_step<P: Preprocessor + 'static>(mut self, step: P) -> Pipeline {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    pub fn steps(&self) -> &[Box<dyn Preprocessor>] {
+        &self.steps
+    }
+
+    pub fn fit(&mut self, data: &Matrix) {
+        self.fit_transform(data);
+    }
+
+    pub fn fit_transform(&mut self, data: &Matrix) -> Matrix {
+        let mut data = data.clone();
+        for step in self.steps.iter_mut() {
+            data = step.fit_transform(&data);
+        }
+        data
+    }
+
+    pub fn transform(&self, data: &Matrix) -> Matrix {
+        let mut data = data.clone();
+        for step in &self.steps {
+            data = step.transform(&data);
+        }
+        data
+    }
+
+    pub fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        let mut data = data.clone();
+        for step in self.steps.iter().rev() {
+            data = step.inverse_transform(&data);
+        }
+        data
+    }
+
+    pub fn transform_sample(&self, sample: &Matrix) -> Matrix {
+        self.transform(&sample.t()).t()
+    }
+
+    pub fn inverse_transform_sample(&self, sample: &Matrix) -> Matrix {
+        self.inverse_transform(&sample.t()).t()
+    }
+
+    pub fn fit_inputs(&mut self, data: &[(Matrix, Matrix)]) {
+        self.fit(&inputs_matrix(data));
+    }
+
+    pub fn fit_outputs(&mut self, data: &[(Matrix, Matrix)]) {
+        self.fit(&outputs_matrix(data));
+    }
+
+    pub fn transform_inputs(&self, data: &[(Matrix, Matrix)]) -> Vec<(Matrix, Matrix)> {
+        data.iter()
+            .map(|(input, output)| (self.transform_sample(input), output.clone()))
+            .collect()
+    }
+
+    pub fn transform_outputs(&self, data: &[(Matrix, Matrix)]) -> Vec<(Matrix, Matrix)> {
+        data.iter()
+            .map(|(input, output)| (input.clone(), self.transform_sample(output)))
+            .collect()
+    }
+
+    // one step per line, as given by its name
+    pub fn save(&self, path: &str) {
+        let content = self
+            .steps
+            .iter()
+            .map(|step| format!("{}\n", step.name()))
+            .collect::<String>();
+        fs::write(path, content).expect("Could not save the pipeline at the given path.");
+    }
+
+    pub fn load(path: &str) -> Pipeline {
+        let contents = fs::read_to_string(path).expect("Loading path is invalid");
+        let steps = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                build_preprocessor(line).unwrap_or_else(|| panic!("Unknown preprocessor {}", line))
+            })
+            .collect();
+        Pipeline { steps }
+    }
+}
+
+// saves the network along with the pipeline of its inputs, as network.save
+// and pipeline.save in the directory, so that inference loads both at once
+pub fn save_model(directory: &str, network: &DenseNetwork, pipeline: &Pipeline) {
+    let directory = Path::new(directory);
+    fs::create_dir_all(directory).expect("Could not create the model directory.");
+    let path = |name: &str| {
+        directory
+            .join(name)
+            .to_str()
+            .expect("Invalid model path")
+            .to_string()
+    };
+    network.save_network(&path("network.save"));
+    pipeline.save(&path("pipeline.save"));
+}
+
+pub fn load_model(directory: &str) -> (DenseNetwork, Pipeline) {
+    let directory = Path::new(directory);
+    let path = |name: &str| {
+        directory
+            .join(name)
+            .to_str()
+            .expect("Invalid model path")
+            .to_string()
+    };
+    (
+        DenseNetwork::load_network(&path("network.save")),
+        Pipeline::load(&path("pipeline.save")),
+    )
+}
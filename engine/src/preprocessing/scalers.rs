@@ -0,0 +1,273 @@
+use crate::maths::Matrix;
+use crate::preprocessing::{
+    check_features, columns, format_values, map_columns, parse_pair, Preprocessor,
+};
+use std::str::FromStr;
+
+// centers every feature on 0 and scales it to a standard deviation of 1
+#[derive(Clone, Debug, Default)]
+pub struct StandardScaler {
+    means: Vec<f64>,
+    deviations: Vec<f64>,
+}
+
+impl StandardScaler {
+    pub fn new() -> StandardScaler {
+        StandardScaler::default()
+    }
+
+    pub fn means(&self) -> &[f64] {
+        &self.means
+    }
+
+    pub fn deviations(&self) -> &[f64] {
+        &self.deviations
+    }
+}
+
+impl Preprocessor for StandardScaler {
+    fn name(&self) -> String {
+        format!(
+            "StandardScaler {} {}",
+            format_values(&self.means),
+            format_values(&self.deviations)
+        )
+    }
+
+    fn fit(&mut self, data: &Matrix) {
+        let columns = columns(data);
+        self.means = columns.iter().map(|column| mean(column)).collect();
+        self.deviations = columns
+            .iter()
+            .zip(&self.means)
+            .map(|(column, mean)| {
+                let variance = column
+                    .iter()
+                    .map(|value| (value - mean).powi(2))
+                    .sum::<f64>()
+                    / column.len().max(1) as f64;
+                scale_or_one(variance.sqrt())
+            })
+            .collect();
+    }
+
+    fn transform(&self, data: &Matrix) -> Matrix {
+        check_features("StandardScaler", self.means.len(), data);
+        map_columns(data, |x, value| {
+            (value - self.means[x]) / self.deviations[x]
+        })
+    }
+
+    fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        check_features("StandardScaler", self.means.len(), data);
+        map_columns(data, |x, value| value * self.deviations[x] + self.means[x])
+    }
+}
+
+impl FromStr for StandardScaler {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<StandardScaler, Self::Err> {
+        match input.split_whitespace().collect::<Vec<_>>()[..] {
+            ["StandardScaler", means, deviations] => {
+                let (means, deviations) = parse_pair(means, deviations).ok_or(())?;
+                Ok(StandardScaler { means, deviations })
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+// scales every feature to a range, [0, 1] by default
+#[derive(Clone, Debug)]
+pub struct MinMaxScaler {
+    range: (f64, f64),
+    minimums: Vec<f64>,
+    maximums: Vec<f64>,
+}
+
+impl MinMaxScaler {
+    pub fn new() -> MinMaxScaler {
+        MinMaxScaler {
+            range: (0.0, 1.0),
+            minimums: vec![],
+            maximums: vec![],
+        }
+    }
+
+    pub fn with_range(mut self, low: f64, high: f64) -> MinMaxScaler {
+        assert!(low < high, "The range of a MinMaxScaler must not be empty");
+        self.range = (low, high);
+        self
+    }
+
+    pub fn minimums(&self) -> &[f64] {
+        &self.minimums
+    }
+
+    pub fn maximums(&self) -> &[f64] {
+        &self.maximums
+    }
+
+    // factor of the values of a feature, a constant feature being mapped
+    // to the low end of the range
+    fn scale(&self, x: usize) -> f64 {
+        (self.range.1 - self.range.0) / scale_or_one(self.maximums[x] - self.minimums[x])
+    }
+}
+
+impl Default for MinMaxScaler {
+    fn default() -> Self {
+        MinMaxScaler::new()
+    }
+}
+
+impl Preprocessor for MinMaxScaler {
+    fn name(&self) -> String {
+        format!(
+            "MinMaxScaler {} {} {} {}",
+            self.range.0,
+            self.range.1,
+            format_values(&self.minimums),
+            format_values(&self.maximums)
+        )
+    }
+
+    fn fit(&mut self, data: &Matrix) {
+        let columns = columns(data);
+        let extremum = |f: fn(f64, f64) -> f64| {
+            columns
+                .iter()
+                .map(|column| column.iter().copied().reduce(f).unwrap_or(0.0))
+                .collect()
+        };
+        self.minimums = extremum(f64::min);
+        self.maximums = extremum(f64::max);
+    }
+
+    fn transform(&self, data: &Matrix) -> Matrix {
+        check_features("MinMaxScaler", self.minimums.len(), data);
+        map_columns(data, |x, value| {
+            self.range.0 + (value - self.minimums[x]) * self.scale(x)
+        })
+    }
+
+    fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        check_features("MinMaxScaler", self.minimums.len(), data);
+        map_columns(data, |x, value| {
+            (value - self.range.0) / self.scale(x) + self.minimums[x]
+        })
+    }
+}
+
+impl FromStr for MinMaxScaler {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<MinMaxScaler, Self::Err> {
+        match input.split_whitespace().collect::<Vec<_>>()[..] {
+            ["MinMaxScaler", low, high, minimums, maximums] => {
+                let (minimums, maximums) = parse_pair(minimums, maximums).ok_or(())?;
+                Ok(MinMaxScaler {
+                    range: (low.parse().map_err(|_| ())?, high.parse().map_err(|_| ())?),
+                    minimums,
+                    maximums,
+                })
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+// centers every feature on its median and scales it by its interquartile
+// range, so that outliers weigh less than with a StandardScaler
+#[derive(Clone, Debug, Default)]
+pub struct RobustScaler {
+    medians: Vec<f64>,
+    ranges: Vec<f64>,
+}
+
+impl RobustScaler {
+    pub fn new() -> RobustScaler {
+        RobustScaler::default()
+    }
+
+    pub fn medians(&self) -> &[f64] {
+        &self.medians
+    }
+
+    pub fn ranges(&self) -> &[f64] {
+        &self.ranges
+    }
+}
+
+impl Preprocessor for RobustScaler {
+    fn name(&self) -> String {
+        format!(
+            "RobustScaler {} {}",
+            format_values(&self.medians),
+            format_values(&self.ranges)
+        )
+    }
+
+    fn fit(&mut self, data: &Matrix) {
+        let mut columns = columns(data);
+        for column in columns.iter_mut() {
+            column.sort_by(f64::total_cmp);
+        }
+        self.medians = columns.iter().map(|column| quantile(column, 0.5)).collect();
+        self.ranges = columns
+            .iter()
+            .map(|column| scale_or_one(quantile(column, 0.75) - quantile(column, 0.25)))
+            .collect();
+    }
+
+    fn transform(&self, data: &Matrix) -> Matrix {
+        check_features("RobustScaler", self.medians.len(), data);
+        map_columns(data, |x, value| (value - self.medians[x]) / self.ranges[x])
+    }
+
+    fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        check_features("RobustScaler", self.medians.len(), data);
+        map_columns(data, |x, value| value * self.ranges[x] + self.medians[x])
+    }
+}
+
+impl FromStr for RobustScaler {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<RobustScaler, Self::Err> {
+        match input.split_whitespace().collect::<Vec<_>>()[..] {
+            ["RobustScaler", medians, ranges] => {
+                let (medians, ranges) = parse_pair(medians, ranges).ok_or(())?;
+                Ok(RobustScaler { medians, ranges })
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+pub(super) fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+// quantile q of sorted values, interpolated between the two closest ones
+pub(super) fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let position = q * (sorted.len() - 1) as f64;
+    let (low, high) = (position.floor() as usize, position.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (position - low as f64)
+}
+
+// features that do not vary are only shifted
+fn scale_or_one(scale: f64) -> f64 {
+    if scale == 0.0 {
+        1.0
+    } else {
+        scale
+    }
+}
@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+mod registry;
+pub use registry::{Constructor, Registry};
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+// builds a component from its saved descriptor, the descriptor being
+// the name of the component optionally followed by its parameters
+pub type Constructor<T> = fn(&str) -> Option<Box<T>>;
+
+pub struct Registry<T: ?Sized> {
+    constructors: HashMap<String, Constructor<T>>,
+}
+
+impl<T: ?Sized> Registry<T> {
+    pub fn new() -> Registry<T> {
+        Registry {
+            constructors: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &str, constructor: Constructor<T>) {
+        self.constructors.insert(name.to_string(), constructor);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.constructors.contains_key(name)
+    }
+
+    pub fn build(&self, descriptor: &str) -> Option<Box<T>> {
+        let descriptor = descriptor.trim();
+        let name = descriptor.split_whitespace().next()?;
+
+        self.constructors
+            .get(name)
+            .and_then(|constructor| constructor(descriptor))
+    }
+}
+
+impl<T: ?Sized> Default for Registry<T> {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
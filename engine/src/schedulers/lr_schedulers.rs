@@ -0,0 +1,258 @@
+use crate::schedulers::LrScheduler;
+use std::f64::consts::PI;
+
+// multiplies the learning rate by gamma every step_size epochs
+pub struct StepDecay {
+    pub step_size: usize,
+    pub gamma: f64,
+}
+
+impl StepDecay {
+    pub fn new(step_size: usize, gamma: f64) -> StepDecay {
+        assert!(step_size > 0, "The step size must be positive");
+        StepDecay { step_size, gamma }
+    }
+}
+
+impl LrScheduler for StepDecay {
+    fn learning_rate(&self, base_learning_rate: f64, epoch: usize, _step: usize) -> f64 {
+        base_learning_rate * self.gamma.powi((epoch / self.step_size) as i32)
+    }
+}
+
+// multiplies the learning rate by gamma every epoch
+pub struct ExponentialDecay {
+    pub gamma: f64,
+}
+
+impl ExponentialDecay {
+    pub fn new(gamma: f64) -> ExponentialDecay {
+        ExponentialDecay { gamma }
+    }
+}
+
+impl LrScheduler for ExponentialDecay {
+    fn learning_rate(&self, base_learning_rate: f64, epoch: usize, _step: usize) -> f64 {
+        base_learning_rate * self.gamma.powi(epoch as i32)
+    }
+}
+
+// anneals the learning rate down to min_learning_rate along a cosine over
+// first_cycle epochs, then restarts with cycles cycle_mult times longer
+pub struct CosineAnnealingWarmRestarts {
+    pub first_cycle: usize,
+    pub cycle_mult: usize,
+    pub min_learning_rate: f64,
+}
+
+impl CosineAnnealingWarmRestarts {
+    pub fn new(
+        first_cycle: usize,
+        cycle_mult: usize,
+        min_learning_rate: f64,
+    ) -> CosineAnnealingWarmRestarts {
+        assert!(
+            first_cycle > 0,
+            "The first cycle must last at least one epoch"
+        );
+        assert!(cycle_mult > 0, "The cycle multiplier must be positive");
+        CosineAnnealingWarmRestarts {
+            first_cycle,
+            cycle_mult,
+            min_learning_rate,
+        }
+    }
+}
+
+impl LrScheduler for CosineAnnealingWarmRestarts {
+    fn learning_rate(&self, base_learning_rate: f64, epoch: usize, _step: usize) -> f64 {
+        let mut cycle = self.first_cycle;
+        let mut position = epoch;
+        while position >= cycle {
+            position -= cycle;
+            cycle *= self.cycle_mult;
+        }
+
+        let progress = position as f64 / cycle as f64;
+        self.min_learning_rate
+            + (base_learning_rate - self.min_learning_rate) * (1.0 + (PI * progress).cos()) / 2.0
+    }
+}
+
+// ramps the learning rate up linearly during the first warmup_steps updates,
+// then hands over to the wrapped scheduler if any
+pub struct LinearWarmup {
+    pub warmup_steps: usize,
+    scheduler: Option<Box<dyn LrScheduler>>,
+}
+
+impl LinearWarmup {
+    pub fn new(warmup_steps: usize) -> LinearWarmup {
+        LinearWarmup {
+            warmup_steps,
+            scheduler: None,
+        }
+    }
+
+    pub fn then(mut self, scheduler: Box<dyn LrScheduler>) -> LinearWarmup {
+        self.scheduler = Some(scheduler);
+        self
+    }
+}
+
+impl LrScheduler for LinearWarmup {
+    fn learning_rate(&self, base_learning_rate: f64, epoch: usize, step: usize) -> f64 {
+        if step < self.warmup_steps {
+            return base_learning_rate * (step + 1) as f64 / self.warmup_steps as f64;
+        }
+
+        match &self.scheduler {
+            Some(scheduler) => scheduler.learning_rate(base_learning_rate, epoch, step),
+            None => base_learning_rate,
+        }
+    }
+
+    fn on_epoch_end(&mut self, epoch: usize, loss: f64) {
+        if let Some(scheduler) = &mut self.scheduler {
+            scheduler.on_epoch_end(epoch, loss);
+        }
+    }
+
+    fn state(&self) -> Vec<f64> {
+        match &self.scheduler {
+            Some(scheduler) => scheduler.state(),
+            None => vec![],
+        }
+    }
+
+    fn load_state(&mut self, state: &[f64]) {
+        if let Some(scheduler) = &mut self.scheduler {
+            scheduler.load_state(state);
+        }
+    }
+}
+
+// one-cycle policy over total_steps updates: the learning rate rises from
+// base / div_factor to the base learning rate during the first pct_start of
+// the steps, then anneals down to base / (div_factor * final_div_factor)
+pub struct OneCycle {
+    pub total_steps: usize,
+    pub pct_start: f64,
+    pub div_factor: f64,
+    pub final_div_factor: f64,
+}
+
+impl OneCycle {
+    pub fn new(total_steps: usize) -> OneCycle {
+        OneCycle {
+            total_steps,
+            pct_start: 0.3,
+            div_factor: 25.0,
+            final_div_factor: 1E4,
+        }
+    }
+
+    pub fn with_pct_start(mut self, pct_start: f64) -> OneCycle {
+        self.pct_start = pct_start;
+        self
+    }
+
+    pub fn with_div_factors(mut self, div_factor: f64, final_div_factor: f64) -> OneCycle {
+        self.div_factor = div_factor;
+        self.final_div_factor = final_div_factor;
+        self
+    }
+}
+
+// cosine interpolation from start to end, progress going from 0 to 1
+fn cosine_interpolation(start: f64, end: f64, progress: f64) -> f64 {
+    end + (start - end) * (1.0 + (PI * progress.clamp(0.0, 1.0)).cos()) / 2.0
+}
+
+impl LrScheduler for OneCycle {
+    fn learning_rate(&self, base_learning_rate: f64, _epoch: usize, step: usize) -> f64 {
+        let initial = base_learning_rate / self.div_factor;
+        let last = initial / self.final_div_factor;
+        let warmup_steps = (self.pct_start * self.total_steps as f64).max(1.0);
+
+        let step = step as f64;
+        if step < warmup_steps {
+            cosine_interpolation(initial, base_learning_rate, step / warmup_steps)
+        } else {
+            let annealing_steps = (self.total_steps as f64 - warmup_steps).max(1.0);
+            cosine_interpolation(
+                base_learning_rate,
+                last,
+                (step - warmup_steps) / annealing_steps,
+            )
+        }
+    }
+}
+
+// multiplies the learning rate by factor once the monitored loss has not
+// improved by more than min_delta for patience epochs
+pub struct ReduceOnPlateau {
+    pub factor: f64,
+    pub patience: usize,
+    pub min_delta: f64,
+    pub min_learning_rate: f64,
+    best: f64,
+    wait: usize,
+    scale: f64,
+}
+
+impl ReduceOnPlateau {
+    pub fn new(factor: f64, patience: usize) -> ReduceOnPlateau {
+        ReduceOnPlateau {
+            factor,
+            patience,
+            min_delta: 0.0,
+            min_learning_rate: 0.0,
+            best: f64::INFINITY,
+            wait: 0,
+            scale: 1.0,
+        }
+    }
+
+    pub fn with_min_delta(mut self, min_delta: f64) -> ReduceOnPlateau {
+        self.min_delta = min_delta;
+        self
+    }
+
+    pub fn with_min_learning_rate(mut self, min_learning_rate: f64) -> ReduceOnPlateau {
+        self.min_learning_rate = min_learning_rate;
+        self
+    }
+}
+
+impl LrScheduler for ReduceOnPlateau {
+    fn learning_rate(&self, base_learning_rate: f64, _epoch: usize, _step: usize) -> f64 {
+        (base_learning_rate * self.scale).max(self.min_learning_rate)
+    }
+
+    fn on_epoch_end(&mut self, _epoch: usize, loss: f64) {
+        if loss < self.best - self.min_delta {
+            self.best = loss;
+            self.wait = 0;
+            return;
+        }
+
+        self.wait += 1;
+        if self.wait >= self.patience {
+            self.scale *= self.factor;
+            self.wait = 0;
+        }
+    }
+
+    fn state(&self) -> Vec<f64> {
+        vec![self.best, self.wait as f64, self.scale]
+    }
+
+    fn load_state(&mut self, state: &[f64]) {
+        if let [best, wait, scale] = state {
+            self.best = *best;
+            self.wait = *wait as usize;
+            self.scale = *scale;
+        }
+    }
+}
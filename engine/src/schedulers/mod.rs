@@ -0,0 +1,23 @@
+mod lr_schedulers;
+
+pub use lr_schedulers::{
+    CosineAnnealingWarmRestarts, ExponentialDecay, LinearWarmup, OneCycle, ReduceOnPlateau,
+    StepDecay,
+};
+
+pub trait LrScheduler: Send {
+    // learning rate of the upcoming update, given the learning rate of the
+    // session, the current epoch and the number of updates already done
+    fn learning_rate(&self, base_learning_rate: f64, epoch: usize, step: usize) -> f64;
+
+    // called after each epoch with the validation loss, or the training
+    // loss when the session has no validation data
+    fn on_epoch_end(&mut self, _epoch: usize, _loss: f64) {}
+
+    // internal state kept by checkpoints, empty for stateless schedulers
+    fn state(&self) -> Vec<f64> {
+        vec![]
+    }
+
+    fn load_state(&mut self, _state: &[f64]) {}
+}
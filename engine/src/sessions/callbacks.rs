@@ -0,0 +1,247 @@
+use crate::networks::DenseNetwork;
+use crate::sessions::DenseSession;
+
+use indicatif::ProgressBar;
+use std::collections::BTreeMap;
+
+// metrics handed to the callbacks, keyed by name. A batch reports its
+// "loss" and "size", an epoch its "loss", "learning_rate" and "val_loss"
+// when the session has validation data.
+pub type Logs = BTreeMap<String, f64>;
+
+// hooks called by the session along the training. Every hook gets the
+// session, hence the network through network_mut, and may end the training
+// early with stop_training.
+pub trait Callback: Send {
+    fn on_train_begin(&mut self, _session: &mut DenseSession) {}
+
+    fn on_epoch_begin(&mut self, _session: &mut DenseSession, _epoch: usize) {}
+
+    fn on_batch_begin(&mut self, _session: &mut DenseSession, _batch: usize) {}
+
+    fn on_batch_end(&mut self, _session: &mut DenseSession, _batch: usize, _logs: &Logs) {}
+
+    fn on_epoch_end(&mut self, _session: &mut DenseSession, _epoch: usize, _logs: &Logs) {}
+
+    // logs of the last completed epoch, empty if there is none
+    fn on_train_end(&mut self, _session: &mut DenseSession, _logs: &Logs) {}
+}
+
+// prints a progress bar over the training samples of each epoch, followed
+// by the metrics of the epoch
+#[derive(Default)]
+pub struct ProgressBarLogger {
+    bar: Option<ProgressBar>,
+}
+
+impl ProgressBarLogger {
+    pub fn new() -> ProgressBarLogger {
+        ProgressBarLogger { bar: None }
+    }
+}
+
+impl Callback for ProgressBarLogger {
+    fn on_epoch_begin(&mut self, session: &mut DenseSession, epoch: usize) {
+        println!("Epoch {}:", epoch);
+        self.bar = Some(ProgressBar::new(session.training_size() as u64));
+    }
+
+    fn on_batch_end(&mut self, _session: &mut DenseSession, _batch: usize, logs: &Logs) {
+        if let (Some(bar), Some(size)) = (&self.bar, logs.get("size")) {
+            bar.inc(*size as u64);
+        }
+    }
+
+    fn on_epoch_end(&mut self, _session: &mut DenseSession, _epoch: usize, logs: &Logs) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish();
+        }
+        for (name, value) in logs {
+            match name.as_str() {
+                "loss" => println!("Error ratio: {}", value),
+                "val_loss" => println!("Validation error ratio: {}", value),
+                "learning_rate" => println!("Learning rate: {}", value),
+                _ => println!("{}: {}", name, value),
+            }
+        }
+    }
+
+    fn on_train_end(&mut self, _session: &mut DenseSession, _logs: &Logs) {
+        // the last epoch was interrupted
+        if let Some(bar) = self.bar.take() {
+            bar.abandon();
+        }
+    }
+}
+
+// stops the training once the monitored metric of an epoch goes below the
+// threshold
+pub struct ThresholdStopping {
+    pub monitor: String,
+    pub threshold: f64,
+}
+
+impl ThresholdStopping {
+    pub fn new(monitor: &str, threshold: f64) -> ThresholdStopping {
+        ThresholdStopping {
+            monitor: monitor.to_string(),
+            threshold,
+        }
+    }
+}
+
+impl Callback for ThresholdStopping {
+    fn on_epoch_end(&mut self, session: &mut DenseSession, _epoch: usize, logs: &Logs) {
+        if let Some(value) = logs.get(&self.monitor) {
+            if *value < self.threshold {
+                session.stop_training();
+            }
+        }
+    }
+}
+
+// whether the monitored metric improves by decreasing, like a loss, or by
+// increasing, like an accuracy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MonitorMode {
+    Min,
+    Max,
+}
+
+// stops the training once the monitored metric has not improved by more
+// than min_delta for patience epochs, then restores the weights of the best
+// epoch unless told otherwise. A val_ metric of a session without
+// validation data falls back to its training counterpart.
+pub struct EarlyStopping {
+    pub monitor: String,
+    pub patience: usize,
+    pub min_delta: f64,
+    pub mode: MonitorMode,
+    pub restore_best_weights: bool,
+    // metric read in the logs, found at the end of the first epoch
+    tracked: Option<String>,
+    best: Option<f64>,
+    best_epoch: Option<usize>,
+    best_network: Option<DenseNetwork>,
+    stopped_epoch: Option<usize>,
+    wait: usize,
+}
+
+impl EarlyStopping {
+    pub fn new(monitor: &str, patience: usize) -> EarlyStopping {
+        EarlyStopping {
+            monitor: monitor.to_string(),
+            patience,
+            min_delta: 0.0,
+            mode: MonitorMode::Min,
+            restore_best_weights: true,
+            tracked: None,
+            best: None,
+            best_epoch: None,
+            best_network: None,
+            stopped_epoch: None,
+            wait: 0,
+        }
+    }
+
+    pub fn with_min_delta(mut self, min_delta: f64) -> EarlyStopping {
+        self.min_delta = min_delta;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: MonitorMode) -> EarlyStopping {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_restore_best_weights(mut self, restore_best_weights: bool) -> EarlyStopping {
+        self.restore_best_weights = restore_best_weights;
+        self
+    }
+
+    pub fn best(&self) -> Option<f64> {
+        self.best
+    }
+
+    pub fn best_epoch(&self) -> Option<usize> {
+        self.best_epoch
+    }
+
+    // epoch after which the training was stopped, if it was
+    pub fn stopped_epoch(&self) -> Option<usize> {
+        self.stopped_epoch
+    }
+
+    fn resolve(&self, logs: &Logs) -> String {
+        if logs.contains_key(&self.monitor) {
+            return self.monitor.clone();
+        }
+        match self.monitor.strip_prefix("val_") {
+            Some(name) if logs.contains_key(name) => {
+                eprintln!(
+                    "The monitored metric {} is not tracked, monitoring {} instead",
+                    self.monitor, name
+                );
+                name.to_string()
+            }
+            _ => {
+                eprintln!(
+                    "The monitored metric {} is not tracked, early stopping is disabled",
+                    self.monitor
+                );
+                self.monitor.clone()
+            }
+        }
+    }
+
+    fn improves(&self, value: f64) -> bool {
+        match (self.best, self.mode) {
+            (None, _) => true,
+            (Some(best), MonitorMode::Min) => value < best - self.min_delta,
+            (Some(best), MonitorMode::Max) => value > best + self.min_delta,
+        }
+    }
+}
+
+impl Callback for EarlyStopping {
+    fn on_train_begin(&mut self, _session: &mut DenseSession) {
+        self.tracked = None;
+        self.best = None;
+        self.best_epoch = None;
+        self.best_network = None;
+        self.stopped_epoch = None;
+        self.wait = 0;
+    }
+
+    fn on_epoch_end(&mut self, session: &mut DenseSession, epoch: usize, logs: &Logs) {
+        if self.tracked.is_none() {
+            self.tracked = Some(self.resolve(logs));
+        }
+        let value = match self.tracked.as_ref().and_then(|name| logs.get(name)) {
+            Some(value) => *value,
+            None => return,
+        };
+
+        if self.improves(value) {
+            self.best = Some(value);
+            self.best_epoch = Some(epoch);
+            if self.restore_best_weights {
+                self.best_network = Some(session.network().clone());
+            }
+            self.wait = 0;
+            return;
+        }
+
+        self.wait += 1;
+        if self.wait >= self.patience {
+            self.stopped_epoch = Some(epoch);
+            session.stop_training();
+        }
+    }
+
+    fn on_train_end(&mut self, session: &mut DenseSession, _logs: &Logs) {
+        if let Some(network) = self.best_network.take() {
+            *session.network_mut() = network;
+        }
+    }
+}
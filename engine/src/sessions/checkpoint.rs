@@ -0,0 +1,268 @@
+use crate::sessions::{
+    Callback, DenseSession, EpochRecord, GradientClipping, Logs, NonFinitePolicy, TrainingHistory,
+};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CHECKPOINT_PREFIX: &str = "checkpoint-";
+
+// everything but the network and the data needed to resume a session, saved
+// one "key values" line per field, with one "history" line per epoch. The
+// weights are only written when the session has some.
+pub(crate) struct SessionState {
+    pub learning_rate: f64,
+    pub epochs: usize,
+    pub minibatch: usize,
+    pub threshold: Option<f64>,
+    pub verbose: bool,
+    pub seed: u64,
+    pub completed_epochs: usize,
+    pub step: usize,
+    pub current_learning_rate: f64,
+    pub scheduler: Vec<f64>,
+    pub class_weights: Option<Vec<f64>>,
+    pub sample_weights: Option<Vec<f64>>,
+    pub non_finite_policy: NonFinitePolicy,
+    pub gradient_clipping: Option<GradientClipping>,
+    pub history: TrainingHistory,
+}
+
+impl SessionState {
+    pub fn save(&self, path: &Path) {
+        let threshold = match self.threshold {
+            Some(threshold) => threshold.to_string(),
+            None => "none".to_string(),
+        };
+        let scheduler = values(&self.scheduler);
+        let non_finite_policy = match self.non_finite_policy {
+            NonFinitePolicy::Ignore => "ignore",
+            NonFinitePolicy::Stop => "stop",
+            NonFinitePolicy::Rollback => "rollback",
+        };
+        let gradient_clipping = match self.gradient_clipping {
+            Some(GradientClipping::Value(value)) => format!("value {}", value),
+            Some(GradientClipping::GlobalNorm(norm)) => format!("global_norm {}", norm),
+            None => "none".to_string(),
+        };
+
+        let mut content = format!(
+            "learning_rate {}\nepochs {}\nminibatch {}\nthreshold {}\nverbose {}\nseed {}\n\
+             completed_epochs {}\nstep {}\ncurrent_learning_rate {}\nscheduler{}\n\
+             non_finite_policy {}\ngradient_clipping {}\n",
+            self.learning_rate,
+            self.epochs,
+            self.minibatch,
+            threshold,
+            self.verbose,
+            self.seed,
+            self.completed_epochs,
+            self.step,
+            self.current_learning_rate,
+            scheduler,
+            non_finite_policy,
+            gradient_clipping
+        );
+        if let Some(class_weights) = &self.class_weights {
+            content.push_str(&format!("class_weights{}\n", values(class_weights)));
+        }
+        if let Some(sample_weights) = &self.sample_weights {
+            content.push_str(&format!("sample_weights{}\n", values(sample_weights)));
+        }
+        for record in &self.history.epochs {
+            content.push_str(&format!(
+                "history epoch={} wall_time={}",
+                record.epoch, record.wall_time
+            ));
+            for (name, value) in &record.logs {
+                content.push_str(&format!(" {}={}", name, value));
+            }
+            content.push('\n');
+        }
+
+        fs::write(path, content).expect("Could not save the session at the given path.");
+    }
+
+    pub fn load(path: &Path) -> SessionState {
+        let contents = fs::read_to_string(path).expect("Loading path is invalid");
+        let mut state = SessionState {
+            learning_rate: 0.0,
+            epochs: 0,
+            minibatch: 1,
+            threshold: None,
+            verbose: false,
+            seed: 0,
+            completed_epochs: 0,
+            step: 0,
+            current_learning_rate: 0.0,
+            scheduler: vec![],
+            class_weights: None,
+            sample_weights: None,
+            non_finite_policy: NonFinitePolicy::Stop,
+            gradient_clipping: None,
+            history: TrainingHistory::new(),
+        };
+
+        for line in contents.lines() {
+            let mut words = line.split_whitespace();
+            let key = match words.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            let value = words.clone().next().unwrap_or("");
+            match key {
+                "learning_rate" => state.learning_rate = parse(value),
+                "epochs" => state.epochs = parse(value),
+                "minibatch" => state.minibatch = parse(value),
+                "threshold" if value == "none" => state.threshold = None,
+                "threshold" => state.threshold = Some(parse(value)),
+                "verbose" => state.verbose = parse(value),
+                "seed" => state.seed = parse(value),
+                "completed_epochs" => state.completed_epochs = parse(value),
+                "step" => state.step = parse(value),
+                "current_learning_rate" => state.current_learning_rate = parse(value),
+                "scheduler" => state.scheduler = words.map(parse).collect(),
+                "class_weights" => state.class_weights = Some(words.map(parse).collect()),
+                "sample_weights" => state.sample_weights = Some(words.map(parse).collect()),
+                "non_finite_policy" => {
+                    state.non_finite_policy = match value {
+                        "ignore" => NonFinitePolicy::Ignore,
+                        "stop" => NonFinitePolicy::Stop,
+                        "rollback" => NonFinitePolicy::Rollback,
+                        _ => panic!("Invalid session value {}", value),
+                    }
+                }
+                "gradient_clipping" => {
+                    let bound = words.nth(1).map(parse);
+                    state.gradient_clipping = match (value, bound) {
+                        ("none", None) => None,
+                        ("value", Some(value)) => Some(GradientClipping::Value(value)),
+                        ("global_norm", Some(norm)) => Some(GradientClipping::GlobalNorm(norm)),
+                        _ => panic!("Invalid session value {}", value),
+                    }
+                }
+                "history" => state.history.epochs.push(parse_record(words)),
+                _ => panic!("Unknown session field {}", key),
+            }
+        }
+        state
+    }
+}
+
+fn values(values: &[f64]) -> String {
+    values.iter().map(|value| format!(" {}", value)).collect()
+}
+
+fn parse_record<'a, I: Iterator<Item = &'a str>>(pairs: I) -> EpochRecord {
+    let mut record = EpochRecord {
+        epoch: 0,
+        wall_time: 0.0,
+        logs: Logs::new(),
+    };
+    for pair in pairs {
+        let (name, value) = pair.split_once('=').expect("Invalid metric");
+        match name {
+            "epoch" => record.epoch = parse(value),
+            "wall_time" => record.wall_time = parse(value),
+            _ => {
+                record.logs.insert(name.to_string(), parse(value));
+            }
+        }
+    }
+    record
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> T {
+    match value.parse::<T>() {
+        Ok(value) => value,
+        Err(_) => panic!("Invalid session value {}", value),
+    }
+}
+
+pub(crate) fn network_path(checkpoint: &Path) -> PathBuf {
+    checkpoint.join("network.save")
+}
+
+pub(crate) fn session_path(checkpoint: &Path) -> PathBuf {
+    checkpoint.join("session.save")
+}
+
+// checkpoints of the directory along with their epoch, oldest first
+fn list_checkpoints(directory: &Path) -> Vec<(usize, PathBuf)> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut checkpoints = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let epoch = name
+                .strip_prefix(CHECKPOINT_PREFIX)?
+                .parse::<usize>()
+                .ok()?;
+            Some((epoch, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    checkpoints.sort();
+    checkpoints
+}
+
+// most recent checkpoint written in the directory by a ModelCheckpoint
+pub fn latest_checkpoint(directory: &str) -> Option<PathBuf> {
+    list_checkpoints(Path::new(directory))
+        .pop()
+        .map(|(_, path)| path)
+}
+
+// saves the session in directory/checkpoint-<epoch> every given number of
+// epochs, only keeping the keep_last most recent checkpoints
+pub struct ModelCheckpoint {
+    pub directory: PathBuf,
+    pub every: usize,
+    pub keep_last: usize,
+}
+
+impl ModelCheckpoint {
+    pub fn new(directory: &str) -> ModelCheckpoint {
+        ModelCheckpoint {
+            directory: PathBuf::from(directory),
+            every: 1,
+            keep_last: 3,
+        }
+    }
+
+    pub fn every(mut self, every: usize) -> ModelCheckpoint {
+        assert!(every > 0, "Checkpoints must be at least one epoch apart");
+        self.every = every;
+        self
+    }
+
+    pub fn keep_last(mut self, keep_last: usize) -> ModelCheckpoint {
+        assert!(keep_last > 0, "At least one checkpoint must be kept");
+        self.keep_last = keep_last;
+        self
+    }
+
+    fn rotate(&self) {
+        let checkpoints = list_checkpoints(&self.directory);
+        let nb_old = checkpoints.len().saturating_sub(self.keep_last);
+        for (_, path) in checkpoints.into_iter().take(nb_old) {
+            fs::remove_dir_all(path).expect("Could not remove an old checkpoint.");
+        }
+    }
+}
+
+impl Callback for ModelCheckpoint {
+    fn on_epoch_end(&mut self, session: &mut DenseSession, epoch: usize, _logs: &Logs) {
+        if !(epoch + 1).is_multiple_of(self.every) {
+            return;
+        }
+
+        let name = format!("{}{:06}", CHECKPOINT_PREFIX, epoch + 1);
+        let path = self.directory.join(name);
+        session.save_checkpoint(path.to_str().expect("Invalid checkpoint path"));
+        self.rotate();
+    }
+}
@@ -0,0 +1,82 @@
+use crate::data::{k_fold_indices, stratified_k_fold_indices};
+use crate::maths::Matrix;
+use crate::sessions::{DenseSession, Logs, Session};
+
+use std::collections::BTreeMap;
+
+// test logs of each fold of a cross-validation
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrossValidation {
+    pub folds: Vec<Logs>,
+}
+
+impl CrossValidation {
+    pub fn values(&self, name: &str) -> Vec<f64> {
+        self.folds
+            .iter()
+            .filter_map(|logs| logs.get(name).copied())
+            .collect()
+    }
+
+    pub fn mean(&self, name: &str) -> f64 {
+        let values = self.values(name);
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    // population standard deviation over the folds
+    pub fn std(&self, name: &str) -> f64 {
+        let values = self.values(name);
+        let mean = self.mean(name);
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        variance.sqrt()
+    }
+
+    // mean and standard deviation of every metric
+    pub fn summary(&self) -> BTreeMap<String, (f64, f64)> {
+        let names = self.folds.iter().flat_map(|logs| logs.keys());
+        names
+            .map(|name| (name.clone(), (self.mean(name), self.std(name))))
+            .collect()
+    }
+}
+
+// fits one session per fold, built by builder from the training data and
+// the fold left out as testing data, so the builder is expected to create a
+// fresh network each time. Folds are taken in the order of the data, which
+// should be shuffled beforehand if it is sorted.
+pub fn cross_validate<F>(
+    mut builder: F,
+    data: &[(Matrix, Matrix)],
+    k: usize,
+    stratified: bool,
+) -> CrossValidation
+where
+    F: FnMut(Vec<(Matrix, Matrix)>, Vec<(Matrix, Matrix)>) -> DenseSession,
+{
+    let folds = if stratified {
+        stratified_k_fold_indices(data, k)
+    } else {
+        k_fold_indices(data.len(), k)
+    };
+
+    let mut fold_of = vec![0; data.len()];
+    for (fold, indices) in folds.iter().enumerate() {
+        indices.iter().for_each(|&index| fold_of[index] = fold);
+    }
+
+    let mut results = CrossValidation::default();
+    for fold in 0..k {
+        let (mut training_data, mut testing_data) = (vec![], vec![]);
+        for (index, sample) in data.iter().enumerate() {
+            if fold_of[index] == fold {
+                testing_data.push(sample.clone());
+            } else {
+                training_data.push(sample.clone());
+            }
+        }
+
+        let mut session = builder(training_data, testing_data);
+        results.folds.push(session.fit().test);
+    }
+    results
+}
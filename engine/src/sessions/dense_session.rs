@@ -1,24 +1,72 @@
+use crate::augmentation::Augmentation;
+use crate::data::{Batch, DataLoader, Dataset, Subset};
 use crate::maths::Matrix;
-use crate::networks::{DenseNetwork, Network, SupervisedNetwork};
-use crate::sessions::Session;
+use crate::metrics::{ConfusionMatrix, Metric};
+use crate::networks::{DenseNetwork, Gradients, Network, SupervisedNetwork};
+use crate::schedulers::LrScheduler;
+use crate::sessions::checkpoint::{network_path, session_path, SessionState};
+use crate::sessions::hogwild::SharedParameters;
+use crate::sessions::{
+    Callback, Divergence, EpochRecord, GradientClipping, Logs, NonFinitePolicy, NonFiniteSource,
+    ProgressBarLogger, Session, ThresholdStopping, TrainingHistory, TrainingStrategy,
+};
 
-use indicatif::ProgressBar;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Instant;
 
 pub struct DenseSession {
     network: DenseNetwork,
-    training_data: Vec<(Matrix, Matrix)>,
-    testing_data: Vec<(Matrix, Matrix)>,
+    training_data: Arc<dyn Dataset>,
+    testing_data: Arc<dyn Dataset>,
     learning_rate: f64,
     epoch: usize,
-    threshold: f64,
-    stop_on_threshold: bool,
+    threshold: Option<f64>,
     verbose: bool,
     minibatch: usize,
+    // the data of each epoch is shuffled from seed + epoch, so that a
+    // resumed session visits it in the same order
+    seed: u64,
+    completed_epochs: usize,
+    // epoch to start from on the next training, set when resuming
+    resume_epoch: usize,
+    history: TrainingHistory,
+    // state read from a checkpoint, waiting for with_scheduler
+    scheduler_state: Option<Vec<f64>>,
+    class_weights: Option<Matrix>,
+    // lines up with training_data, which is why the data is visited through
+    // a shuffled permutation instead of being shuffled itself
+    sample_weights: Option<Vec<f64>>,
+    validation_data: Arc<dyn Dataset>,
+    scheduler: Option<Box<dyn LrScheduler>>,
+    gradient_clipping: Option<GradientClipping>,
+    non_finite_policy: NonFinitePolicy,
+    last_good_network: Option<DenseNetwork>,
+    divergence: Option<Divergence>,
+    current_learning_rate: f64,
+    // number of updates applied to the network
+    step: usize,
+    callbacks: Vec<Box<dyn Callback>>,
+    stop_requested: bool,
+    metrics: Vec<Box<dyn Metric>>,
+    test_metrics: Logs,
+    test_confusion_matrix: Option<ConfusionMatrix>,
+    // shares the minibatches out when set
+    pool: Option<ThreadPool>,
+    strategy: TrainingStrategy,
+    nb_prefetch_threads: usize,
+    augmentation: Option<Arc<Augmentation>>,
 }
 
 impl DenseSession {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network: DenseNetwork,
         learning_rate: f64,
@@ -29,134 +77,775 @@ impl DenseSession {
         verbose: bool,
         minibatch: Option<usize>
     ) -> DenseSession {
-        let t = threshold.unwrap_or(0.0);
-        let stop_on_threshold = t == 0.0;
-        DenseSession {
+        DenseSession::from_datasets(
             network,
             learning_rate,
             training_data,
             testing_data,
             epoch,
-            threshold: t,
-            stop_on_threshold,
+            threshold,
+            verbose,
+            minibatch,
+        )
+    }
+
+    // same as new, the data being read from any dataset, file-backed ones
+    // included
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_datasets<D, T>(
+        network: DenseNetwork,
+        learning_rate: f64,
+        training_data: D,
+        testing_data: T,
+        epoch: usize,
+        threshold: Option<f64>,
+        verbose: bool,
+        minibatch: Option<usize>,
+    ) -> DenseSession
+    where
+        D: Dataset + 'static,
+        T: Dataset + 'static,
+    {
+        let mut callbacks: Vec<Box<dyn Callback>> = vec![];
+        if verbose {
+            callbacks.push(Box::new(ProgressBarLogger::new()));
+        }
+        if let Some(threshold) = threshold {
+            callbacks.push(Box::new(ThresholdStopping::new("loss", threshold)));
+        }
+        DenseSession {
+            network,
+            learning_rate,
+            training_data: Arc::new(training_data),
+            testing_data: Arc::new(testing_data),
+            epoch,
+            threshold,
             verbose,
             minibatch: minibatch.unwrap_or(1),
+            seed: thread_rng().gen(),
+            completed_epochs: 0,
+            resume_epoch: 0,
+            history: TrainingHistory::new(),
+            scheduler_state: None,
+            class_weights: None,
+            sample_weights: None,
+            validation_data: Arc::new(vec![]),
+            scheduler: None,
+            gradient_clipping: None,
+            non_finite_policy: NonFinitePolicy::Stop,
+            last_good_network: None,
+            divergence: None,
+            current_learning_rate: learning_rate,
+            step: 0,
+            callbacks,
+            stop_requested: false,
+            metrics: vec![],
+            test_metrics: Logs::new(),
+            test_confusion_matrix: None,
+            pool: None,
+            strategy: TrainingStrategy::Synchronous,
+            nb_prefetch_threads: 0,
+            augmentation: None,
         }
     }
 
-    fn compute_delta(&mut self, index: usize) -> (Matrix, f64) {
-        let (input, output): &(Matrix, Matrix) = &self.training_data[index];
-        self.network.feed_forward(input);
-        let error = self.network.loss.compute_error(&self.network.value(), output);
-        let delta = self.network.compute_output_delta(output);
+    // reported in the logs of each epoch, prefixed with val_ on the
+    // validation data, and by test
+    pub fn with_metrics(mut self, metrics: Vec<Box<dyn Metric>>) -> DenseSession {
+        self.metrics = metrics;
+        self
+    }
 
-        (delta, error)
+    // mean loss and metrics of the last test
+    pub fn test_metrics(&self) -> &Logs {
+        &self.test_metrics
     }
 
-    fn batch_training(&mut self) {
-        let mut batch_counter : usize = 0;
-        let (w, h) = self.network.output_shape();
-        let mut batch_delta: Matrix = Matrix::new(w, h);
-        for ep in 0..self.epoch {
-            let mut error_sum: f64 = 0.0;
-            let bar: ProgressBar = ProgressBar::new(self.training_data.len() as u64);
-            if self.verbose {
-                println!("Epoch {}:", ep);
+    // counts of the last test, when a ConfusionMatrix is among the metrics
+    pub fn test_confusion_matrix(&self) -> Option<&ConfusionMatrix> {
+        self.test_confusion_matrix.as_ref()
+    }
+
+    // computes the gradients of each minibatch on nb_workers threads. The
+    // updates are the same as with a single thread, online training not
+    // being parallelized.
+    pub fn with_workers(mut self, nb_workers: usize) -> DenseSession {
+        assert!(nb_workers > 0, "At least one worker is needed");
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(nb_workers)
+            .build()
+            .expect("Could not start the workers");
+        self.pool = Some(pool);
+        self
+    }
+
+    // with TrainingStrategy::Hogwild, the minibatch size and the workers are
+    // ignored and the learning rate is only scheduled once per epoch
+    pub fn with_strategy(mut self, strategy: TrainingStrategy) -> DenseSession {
+        if let TrainingStrategy::Hogwild(nb_threads) = strategy {
+            assert!(nb_threads > 0, "At least one thread is needed");
+        }
+        self.strategy = strategy;
+        self
+    }
+
+    // callbacks are called in the order they were added
+    pub fn with_callback(mut self, callback: Box<dyn Callback>) -> DenseSession {
+        self.callbacks.push(callback);
+        self
+    }
+
+    pub fn network(&self) -> &DenseNetwork {
+        &self.network
+    }
+
+    pub fn network_mut(&mut self) -> &mut DenseNetwork {
+        &mut self.network
+    }
+
+    pub fn training_size(&self) -> usize {
+        self.training_data.len()
+    }
+
+    pub fn epochs(&self) -> usize {
+        self.epoch
+    }
+
+    // ends the training after the current batch
+    pub fn stop_training(&mut self) {
+        self.stop_requested = true;
+    }
+
+    // evaluated after each epoch, separately from the testing data
+    pub fn with_validation_data(self, validation_data: Vec<(Matrix, Matrix)>) -> DenseSession {
+        self.with_validation_dataset(validation_data)
+    }
+
+    pub fn with_validation_dataset<D: Dataset + 'static>(
+        mut self,
+        validation_data: D,
+    ) -> DenseSession {
+        self.validation_data = Arc::new(validation_data);
+        self
+    }
+
+    // moves the last percentage % of the training data, taken in order and
+    // before any shuffling, to the validation data. The validation loss is
+    // not weighted, so the sample weights of the moved samples are dropped.
+    pub fn with_validation_split(mut self, percentage: usize) -> DenseSession {
+        assert!(percentage < 100, "The validation split must be below 100%");
+        let nb_samples = self.training_data.len();
+        let nb_training = nb_samples - percentage * nb_samples / 100;
+
+        let data = self.training_data.clone();
+        self.validation_data = Arc::new(Subset::new(
+            data.clone(),
+            (nb_training..nb_samples).collect(),
+        ));
+        self.training_data = Arc::new(Subset::new(data, (0..nb_training).collect()));
+        if let Some(sample_weights) = &mut self.sample_weights {
+            sample_weights.truncate(nb_training);
+        }
+        self
+    }
+
+    // loads the next minibatches from nb_threads background threads, which
+    // pays off when the training data is read from a file
+    pub fn with_prefetch(mut self, nb_threads: usize) -> DenseSession {
+        self.nb_prefetch_threads = nb_threads;
+        self
+    }
+
+    // the training inputs are augmented anew every epoch, the validation
+    // and testing data being left as they are
+    pub fn with_augmentation(mut self, augmentation: Augmentation) -> DenseSession {
+        self.augmentation = Some(Arc::new(augmentation));
+        self
+    }
+
+    // a resumed session hands its saved state over to the scheduler
+    pub fn with_scheduler(mut self, mut scheduler: Box<dyn LrScheduler>) -> DenseSession {
+        if let Some(state) = self.scheduler_state.take() {
+            scheduler.load_state(&state);
+        }
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> DenseSession {
+        self.seed = seed;
+        self
+    }
+
+    // epochs of the last training, along with its test when fitted
+    pub fn history(&self) -> &TrainingHistory {
+        &self.history
+    }
+
+    pub fn completed_epochs(&self) -> usize {
+        self.completed_epochs
+    }
+
+    // writes the network and the state of the session into the directory
+    // at path, along with its class and sample weights, non-finite policy
+    // and gradient clipping. The data, validation data included, the
+    // scheduler, callbacks, metrics, augmentation and strategy are not saved.
+    pub fn save_checkpoint(&self, path: &str) {
+        let path = Path::new(path);
+        fs::create_dir_all(path).expect("Could not create the checkpoint directory.");
+
+        self.network
+            .save_network(network_path(path).to_str().expect("Invalid checkpoint path"));
+        let state = SessionState {
+            learning_rate: self.learning_rate,
+            epochs: self.epoch,
+            minibatch: self.minibatch,
+            threshold: self.threshold,
+            verbose: self.verbose,
+            seed: self.seed,
+            completed_epochs: self.completed_epochs,
+            step: self.step,
+            current_learning_rate: self.current_learning_rate,
+            scheduler: self.scheduler.as_ref().map(|s| s.state()).unwrap_or_default(),
+            class_weights: self
+                .class_weights
+                .as_ref()
+                .map(|weights| (0..weights.len()).map(|i| weights.get(i)).collect()),
+            sample_weights: self.sample_weights.clone(),
+            non_finite_policy: self.non_finite_policy,
+            gradient_clipping: self.gradient_clipping,
+            history: self.history.clone(),
+        };
+        state.save(&session_path(path));
+    }
+
+    // restores a session saved by save_checkpoint, training then continues
+    // from the epoch following the checkpoint. The training data must be the
+    // one of the saved session, the validation data, scheduler, callbacks
+    // and other unsaved options having to be given again.
+    pub fn resume(
+        path: &str,
+        training_data: Vec<(Matrix, Matrix)>,
+        testing_data: Vec<(Matrix, Matrix)>,
+    ) -> DenseSession {
+        let path = Path::new(path);
+        let network =
+            DenseNetwork::load_network(network_path(path).to_str().expect("Invalid checkpoint path"));
+        let state = SessionState::load(&session_path(path));
+
+        let mut session = DenseSession::new(
+            network,
+            state.learning_rate,
+            training_data,
+            testing_data,
+            state.epochs,
+            state.threshold,
+            state.verbose,
+            Some(state.minibatch),
+        )
+        .with_seed(state.seed)
+        .with_non_finite_policy(state.non_finite_policy);
+        if let Some(class_weights) = state.class_weights {
+            session = session.with_class_weights(Matrix::from(class_weights));
+        }
+        if let Some(sample_weights) = state.sample_weights {
+            session = session.with_sample_weights(sample_weights);
+        }
+        session.gradient_clipping = state.gradient_clipping;
+        session.completed_epochs = state.completed_epochs;
+        session.resume_epoch = state.completed_epochs;
+        session.step = state.step;
+        session.current_learning_rate = state.current_learning_rate;
+        session.history = state.history;
+        if !state.scheduler.is_empty() {
+            session.scheduler_state = Some(state.scheduler);
+        }
+        session
+    }
+
+    pub fn with_gradient_clipping(mut self, gradient_clipping: GradientClipping) -> DenseSession {
+        self.gradient_clipping = Some(gradient_clipping);
+        self
+    }
+
+    pub fn with_non_finite_policy(mut self, non_finite_policy: NonFinitePolicy) -> DenseSession {
+        self.non_finite_policy = non_finite_policy;
+        self
+    }
+
+    // set when the last training stopped on a NaN or infinite value
+    pub fn divergence(&self) -> Option<Divergence> {
+        self.divergence
+    }
+
+    // rate used by the last update
+    pub fn current_learning_rate(&self) -> f64 {
+        self.current_learning_rate
+    }
+
+    pub fn with_class_weights(mut self, class_weights: Matrix) -> DenseSession {
+        self.class_weights = Some(class_weights);
+        self
+    }
+
+    pub fn with_sample_weights(mut self, sample_weights: Vec<f64>) -> DenseSession {
+        assert_eq!(
+            sample_weights.len(),
+            self.training_data.len(),
+            "One sample weight is expected per training sample"
+        );
+        self.sample_weights = Some(sample_weights);
+        self
+    }
+
+    fn compute_gradients(&mut self, index: usize, sample: &(Matrix, Matrix)) -> (Gradients, f64) {
+        let sample_weight = self.sample_weights.as_ref().map(|weights| weights[index]);
+        let (gradients, error, value) = sample_gradients(
+            &mut self.network,
+            sample,
+            self.class_weights.as_ref(),
+            sample_weight,
+        );
+        for metric in self.metrics.iter_mut() {
+            metric.update(&value, &sample.1);
+        }
+
+        (gradients, error)
+    }
+
+    // sum of the gradients and losses of the samples of the batch
+    fn batch_gradients(&mut self, batch: &Batch) -> (Gradients, f64) {
+        let (mut batch_gradients, mut error_sum) =
+            self.compute_gradients(batch.indices[0], &batch.samples[0]);
+        for i in 1..batch.indices.len() {
+            let (gradients, error) = self.compute_gradients(batch.indices[i], &batch.samples[i]);
+            batch_gradients.add(&gradients);
+            error_sum += error;
+        }
+        (batch_gradients, error_sum)
+    }
+
+    // same as batch_gradients, the samples being shared out between the
+    // workers, each on its own replica of the network. The sums are then
+    // taken in the order of the samples so that the result does not depend
+    // on the number of workers.
+    fn parallel_batch_gradients(&mut self, batch: &Batch) -> (Gradients, f64) {
+        let pool = self.pool.as_ref().expect("No worker pool");
+        let network = &self.network;
+        let class_weights = self.class_weights.as_ref();
+        let sample_weights = self.sample_weights.as_deref();
+        let samples = batch.indices.iter().zip(&batch.samples).collect::<Vec<_>>();
+
+        let shard_size = samples.len().div_ceil(pool.current_num_threads());
+        let shards: Vec<Vec<(Gradients, f64, Matrix)>> = pool.install(|| {
+            samples
+                .par_chunks(shard_size)
+                .map(|shard| {
+                    let mut replica = network.clone();
+                    shard
+                        .iter()
+                        .map(|&(&i, sample)| {
+                            let sample_weight = sample_weights.map(|weights| weights[i]);
+                            sample_gradients(&mut replica, sample, class_weights, sample_weight)
+                        })
+                        .collect()
+                })
+                .collect()
+        });
+
+        let mut sum: Option<(Gradients, f64)> = None;
+        let results = shards.into_iter().flatten().zip(&batch.samples);
+        for ((gradients, error, value), (_, expected)) in results {
+            for metric in self.metrics.iter_mut() {
+                metric.update(&value, expected);
             }
-            self.training_data.shuffle(&mut thread_rng());
-            for i in 0..self.training_data.len() {
-
-                let (output_delta, error) = self.compute_delta(i);
-                batch_delta = &output_delta + &batch_delta;
-                batch_counter += 1;
-                error_sum += error;
-
-                if batch_counter >= self.minibatch {
-                    batch_delta.map2::<f64>(|x, y| x / y, self.minibatch as f64);
-                    let deltas = self.network.feed_backward(batch_delta);
-                    self.network.update_weights(deltas, self.learning_rate);
-                    batch_counter = 0;
-                    batch_delta = Matrix::new(w, h);
+            sum = match sum {
+                None => Some((gradients, error)),
+                Some((mut batch_gradients, error_sum)) => {
+                    batch_gradients.add(&gradients);
+                    Some((batch_gradients, error_sum + error))
                 }
+            };
+        }
+        sum.expect("Empty minibatch")
+    }
 
+    fn next_learning_rate(&self, ep: usize) -> f64 {
+        match &self.scheduler {
+            Some(scheduler) => scheduler.learning_rate(self.learning_rate, ep, self.step),
+            None => self.learning_rate,
+        }
+    }
 
-                if self.verbose {
-                    bar.inc(1);
-                }
-            }
+    // clips and applies the gradients of one update, returns false once
+    // training diverged and must stop
+    fn apply_update(&mut self, ep: usize, mut gradients: Gradients, error: f64) -> bool {
+        let check = self.non_finite_policy != NonFinitePolicy::Ignore;
 
-            let err_ratio = error_sum / (self.training_data.len() as f64);
-            if self.verbose {
-                bar.finish();
-                println!("Error ratio: {}", err_ratio);
+        if check && !error.is_finite() {
+            return self.diverge(ep, NonFiniteSource::Loss);
+        }
+        if let Some(layer) = check.then(|| gradients.first_non_finite_layer()).flatten() {
+            return self.diverge(ep, NonFiniteSource::Gradients(layer));
+        }
+
+        clip_gradients(&mut gradients, self.gradient_clipping);
+
+        self.current_learning_rate = self.next_learning_rate(ep);
+        self.network.apply_gradients(&gradients, self.current_learning_rate);
+        self.step += 1;
+
+        if let Some(layer) = check.then(|| self.network.first_non_finite_layer()).flatten() {
+            return self.diverge(ep, NonFiniteSource::Parameters(layer));
+        }
+        true
+    }
+
+    fn diverge(&mut self, ep: usize, source: NonFiniteSource) -> bool {
+        let divergence = Divergence {
+            epoch: ep,
+            step: self.step,
+            source,
+        };
+        eprintln!("Training stopped: {}", divergence);
+
+        if let Some(network) = self.last_good_network.take() {
+            eprintln!("Rolling back to the weights of the last completed epoch");
+            self.network = network;
+        }
+        self.divergence = Some(divergence);
+        false
+    }
+
+    // calls hook on every callback. The callbacks are taken out of the
+    // session meanwhile so that they can borrow it mutably.
+    fn notify<F: FnMut(&mut dyn Callback, &mut DenseSession)>(&mut self, mut hook: F) {
+        if self.callbacks.is_empty() {
+            return;
+        }
+        let mut callbacks = std::mem::take(&mut self.callbacks);
+        for callback in callbacks.iter_mut() {
+            hook(callback.as_mut(), self);
+        }
+        // keeps the callbacks added by the hooks themselves
+        callbacks.append(&mut self.callbacks);
+        self.callbacks = callbacks;
+    }
+
+    // one update over the samples of the batch, returns false once
+    // training must stop
+    fn run_batch(&mut self, ep: usize, index: usize, batch: &Batch) -> (f64, bool) {
+        self.notify(|callback, session| callback.on_batch_begin(session, index));
+
+        let size = batch.indices.len();
+        let (mut batch_gradients, error_sum) = if self.pool.is_some() && size > 1 {
+            self.parallel_batch_gradients(batch)
+        } else {
+            self.batch_gradients(batch)
+        };
+
+        if size > 1 {
+            batch_gradients.scale(1.0 / size as f64);
+        }
+        if !self.apply_update(ep, batch_gradients, error_sum) {
+            return (error_sum, false);
+        }
+
+        if !self.callbacks.is_empty() {
+            let logs = Logs::from([
+                ("loss".to_string(), error_sum / size as f64),
+                ("size".to_string(), size as f64),
+            ]);
+            self.notify(|callback, session| callback.on_batch_end(session, index, &logs));
+        }
+        (error_sum, !self.stop_requested)
+    }
+
+    // every thread takes the next sample of the shuffled data, computes its
+    // gradients on a copy of the shared parameters, which the forward pass
+    // reads in full anyway, and applies the step to the shared parameters in
+    // place. Only networks constraining the norm of their weights update
+    // their copy and write the change back, the constraint rescaling whole
+    // rows. The epoch is reported to the callbacks as a single batch.
+    fn hogwild_epoch(&mut self, ep: usize, nb_threads: usize) -> (f64, usize) {
+        self.notify(|callback, session| callback.on_batch_begin(session, 0));
+        let learning_rate = self.next_learning_rate(ep);
+        let check = self.non_finite_policy != NonFinitePolicy::Ignore;
+
+        let shared = SharedParameters::new(&self.network);
+        let next = AtomicUsize::new(0);
+        let diverged = OnceLock::new();
+        let network = &self.network;
+        let constrained = network
+            .regularizations()
+            .iter()
+            .any(|regularization| regularization.max_norm.is_some());
+        let training_data = &*self.training_data;
+        let order = self.loader().order(ep);
+        let class_weights = self.class_weights.as_ref();
+        let sample_weights = self.sample_weights.as_deref();
+        let gradient_clipping = self.gradient_clipping;
+        let augmentation = self.seeded_augmentation();
+        let augmentation = augmentation.as_deref();
+
+        let results: Vec<(f64, Vec<(usize, Matrix)>)> = thread::scope(|scope| {
+            let threads = (0..nb_threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut replica = network.clone();
+                        let mut error_sum = 0.0;
+                        let mut outputs = vec![];
+                        while diverged.get().is_none() {
+                            let position = next.fetch_add(1, Ordering::Relaxed);
+                            if position >= order.len() {
+                                break;
+                            }
+                            let index = order[position];
+                            let sample_weight = sample_weights.map(|weights| weights[index]);
+
+                            let mut sample = training_data.get(index);
+                            if let Some(augmentation) = augmentation {
+                                sample = Cow::Owned(augmentation.augment(&sample, ep, index));
+                            }
+
+                            shared.read_into(&mut replica);
+                            let (mut gradients, error, value) = sample_gradients(
+                                &mut replica,
+                                &sample,
+                                class_weights,
+                                sample_weight,
+                            );
+                            if check && !error.is_finite() {
+                                let _ = diverged.set(NonFiniteSource::Loss);
+                                break;
+                            }
+                            if let Some(layer) =
+                                check.then(|| gradients.first_non_finite_layer()).flatten()
+                            {
+                                let _ = diverged.set(NonFiniteSource::Gradients(layer));
+                                break;
+                            }
+                            clip_gradients(&mut gradients, gradient_clipping);
+
+                            if constrained {
+                                let before =
+                                    (replica.weights().to_vec(), replica.biases().to_vec());
+                                replica.apply_gradients(&gradients, learning_rate);
+                                shared.add_change(&before, &replica);
+                            } else {
+                                shared.apply_gradients(&gradients, learning_rate, &replica);
+                            }
+
+                            error_sum += error;
+                            outputs.push((position, value));
+                        }
+                        (error_sum, outputs)
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().expect("A hogwild thread panicked"))
+                .collect()
+        });
+        shared.read_into(&mut self.network);
+        self.current_learning_rate = learning_rate;
+
+        let error_sum: f64 = results.iter().map(|(error_sum, _)| error_sum).sum();
+        let mut outputs = results
+            .into_iter()
+            .flat_map(|(_, outputs)| outputs)
+            .collect::<Vec<_>>();
+        outputs.sort_by_key(|(position, _)| *position);
+        self.step += outputs.len();
+        for (position, value) in &outputs {
+            let sample = self.training_data.get(order[*position]);
+            for metric in self.metrics.iter_mut() {
+                metric.update(value, &sample.1);
             }
+        }
+
+        let size = outputs.len();
+        if let Some(source) = diverged.into_inner() {
+            self.diverge(ep, source);
+            return (error_sum, size);
+        }
+        if let Some(layer) = check.then(|| self.network.first_non_finite_layer()).flatten() {
+            self.diverge(ep, NonFiniteSource::Parameters(layer));
+            return (error_sum, size);
+        }
+
+        if !self.callbacks.is_empty() {
+            let logs = Logs::from([
+                ("loss".to_string(), error_sum / size as f64),
+                ("size".to_string(), size as f64),
+            ]);
+            self.notify(|callback, session| callback.on_batch_end(session, 0, &logs));
+        }
+        (error_sum, size)
+    }
+
+    // sum of the losses of the epoch along with the number of samples it
+    // went through, fewer than the training data when stopped early
+    fn run_epoch(&mut self, ep: usize) -> (f64, usize) {
+        if let TrainingStrategy::Hogwild(nb_threads) = self.strategy {
+            return self.hogwild_epoch(ep, nb_threads);
+        }
 
-            if self.stop_on_threshold && err_ratio < self.threshold {
+        let mut error_sum: f64 = 0.0;
+        let mut nb_samples = 0;
+        for (index, batch) in self.loader().batches(ep).enumerate() {
+            let (error, keep_going) = self.run_batch(ep, index, &batch);
+            error_sum += error;
+            nb_samples += batch.indices.len();
+            if !keep_going {
                 break;
             }
         }
+        (error_sum, nb_samples)
     }
 
-    fn online_training(&mut self) {
-        for ep in 0..self.epoch {
-            let mut error_sum: f64 = 0.0;
-            let bar: ProgressBar = ProgressBar::new(self.training_data.len() as u64);
-            if self.verbose {
-                println!("Epoch {}:", ep);
-            }
-            self.training_data.shuffle(&mut thread_rng());
-            for i in 0..self.training_data.len() {
-                let (output_delta, error) = self.compute_delta(i);
-                error_sum += error;
+    fn epoch_logs(&mut self, error_sum: f64, nb_samples: usize) -> Logs {
+        let mut logs = Logs::new();
+        let err_ratio = error_sum / (nb_samples.max(1) as f64)
+            + self.network.regularization_penalty();
+        logs.insert("loss".to_string(), err_ratio);
+        collect_metrics(&mut logs, &mut self.metrics, "");
+        if !self.validation_data.is_empty() {
+            let validation_ratio =
+                evaluate(&mut self.network, &*self.validation_data, &mut self.metrics);
+            logs.insert("val_loss".to_string(), validation_ratio);
+            collect_metrics(&mut logs, &mut self.metrics, "val_");
+        }
+        logs.insert("learning_rate".to_string(), self.current_learning_rate);
+        logs
+    }
 
-                let deltas = self.network.feed_backward(output_delta);
-                self.network.update_weights(deltas, self.learning_rate);
+    fn loader(&self) -> DataLoader {
+        let loader = DataLoader::new(self.training_data.clone(), self.minibatch)
+            .with_shuffle(self.seed)
+            .with_prefetch(self.nb_prefetch_threads);
+        match self.seeded_augmentation() {
+            Some(augmentation) => loader.with_augmentation(augmentation),
+            None => loader,
+        }
+    }
 
-                if self.verbose {
-                    bar.inc(1);
-                }
+    // an augmentation without a seed of its own follows the seed of the
+    // session, so that seeded sessions stay reproducible
+    fn seeded_augmentation(&self) -> Option<Arc<Augmentation>> {
+        self.augmentation
+            .as_ref()
+            .map(|augmentation| match augmentation.seed() {
+                Some(_) => augmentation.clone(),
+                None => Arc::new(augmentation.as_ref().clone().with_seed(self.seed)),
+            })
+    }
+
+    fn snapshot_network(&mut self) {
+        if self.non_finite_policy == NonFinitePolicy::Rollback {
+            self.last_good_network = Some(self.network.clone());
+        }
+    }
+
+    fn run_epochs(&mut self) {
+        self.divergence = None;
+        self.stop_requested = false;
+        let first_epoch = std::mem::take(&mut self.resume_epoch);
+        if first_epoch == 0 {
+            self.history = TrainingHistory::new();
+            self.step = 0;
+        }
+        self.completed_epochs = first_epoch;
+        self.snapshot_network();
+        self.notify(|callback, session| callback.on_train_begin(session));
+
+        let mut logs = match self.history.last() {
+            Some(record) => record.logs.clone(),
+            None => Logs::new(),
+        };
+        for ep in first_epoch..self.epoch {
+            let start = Instant::now();
+            self.notify(|callback, session| callback.on_epoch_begin(session, ep));
+            self.metrics.iter_mut().for_each(|metric| metric.reset());
+            let (error_sum, nb_samples) = self.run_epoch(ep);
+            if self.divergence.is_some() {
+                // the partial epoch must not leak into the test metrics
+                self.metrics.iter_mut().for_each(|metric| metric.reset());
+                break;
             }
+            self.snapshot_network();
 
-            let err_ratio = error_sum / (self.training_data.len() as f64);
-            if self.verbose {
-                bar.finish();
-                println!("Error ratio: {}", err_ratio);
+            logs = self.epoch_logs(error_sum, nb_samples);
+            if let Some(scheduler) = &mut self.scheduler {
+                let loss = logs.get("val_loss").unwrap_or(&logs["loss"]);
+                scheduler.on_epoch_end(ep, *loss);
             }
+            self.completed_epochs = ep + 1;
+            self.history.epochs.push(EpochRecord {
+                epoch: ep,
+                wall_time: start.elapsed().as_secs_f64(),
+                logs: logs.clone(),
+            });
 
-            if self.stop_on_threshold && err_ratio < self.threshold {
+            self.notify(|callback, session| callback.on_epoch_end(session, ep, &logs));
+            if self.stop_requested {
                 break;
             }
         }
-    }
 
+        self.notify(|callback, session| callback.on_train_end(session, &logs));
+    }
 }
 
 impl Session<DenseNetwork> for DenseSession {
-    fn fit(&mut self) -> f64 {
+    fn fit(&mut self) -> TrainingHistory {
         self.train();
-        self.test()
+        self.test();
+        self.history.test = self.test_metrics.clone();
+        self.history.clone()
     }
 
-    fn train(&mut self) {
-        match self.minibatch {
-            1 => self.online_training(),
-            _ => self.batch_training()
-        }
+    fn train(&mut self) -> TrainingHistory {
+        self.run_epochs();
+        self.history.clone()
     }
 
 
     fn test(&mut self) -> f64 {
         let mut err: f64 = 0.0;
+        self.metrics.iter_mut().for_each(|metric| metric.reset());
         for i in 0..self.testing_data.len() {
-            let (i, o): &(Matrix, Matrix) = &self.testing_data[i];
+            let sample = self.testing_data.get(i);
+            let (i, o): &(Matrix, Matrix) = &sample;
 
             self.network.feed_forward(i);
             let error = self.network.loss.compute_error(&self.network.value(), o);
             err += error;
+            for metric in self.metrics.iter_mut() {
+                metric.update(&self.network.value(), o);
+            }
             if self.verbose {
                 print_error_output_expected(error, o, &self.network.value());
             }
         }
+
+        self.test_metrics = Logs::new();
+        self.test_confusion_matrix = None;
+        if !self.testing_data.is_empty() {
+            let loss = err / self.testing_data.len() as f64;
+            self.test_metrics.insert("loss".to_string(), loss);
+            self.test_confusion_matrix = self
+                .metrics
+                .iter()
+                .find_map(|metric| metric.confusion_matrix().cloned());
+            collect_metrics(&mut self.test_metrics, &mut self.metrics, "");
+        }
+        if self.verbose {
+            for (name, value) in &self.test_metrics {
+                println!("{}: {}", name, value);
+            }
+        }
         err
     }
 
@@ -165,6 +854,62 @@ impl Session<DenseNetwork> for DenseSession {
     }
 }
 
+// mean loss over the data, feeding the metrics along the way
+fn evaluate(
+    network: &mut DenseNetwork,
+    data: &dyn Dataset,
+    metrics: &mut [Box<dyn Metric>],
+) -> f64 {
+    let mut err: f64 = 0.0;
+    for i in 0..data.len() {
+        let sample = data.get(i);
+        let (input, output) = &*sample;
+        network.feed_forward(input);
+        let value = network.value();
+        err += network.loss.compute_error(&value, output);
+        for metric in metrics.iter_mut() {
+            metric.update(&value, output);
+        }
+    }
+    err / data.len() as f64
+}
+
+// moves the results of the metrics into the logs and resets them
+fn collect_metrics(logs: &mut Logs, metrics: &mut [Box<dyn Metric>], prefix: &str) {
+    for metric in metrics.iter_mut() {
+        logs.insert(format!("{}{}", prefix, metric.name()), metric.result());
+        metric.reset();
+    }
+}
+
+fn clip_gradients(gradients: &mut Gradients, gradient_clipping: Option<GradientClipping>) {
+    match gradient_clipping {
+        Some(GradientClipping::Value(value)) => gradients.clip_by_value(value),
+        Some(GradientClipping::GlobalNorm(norm)) => gradients.clip_by_global_norm(norm),
+        None => {}
+    }
+}
+
+// gradients, weighted loss and output of the network for one sample
+pub(crate) fn sample_gradients(
+    network: &mut DenseNetwork,
+    sample: &(Matrix, Matrix),
+    class_weights: Option<&Matrix>,
+    sample_weight: Option<f64>,
+) -> (Gradients, f64, Matrix) {
+    let (input, output) = sample;
+    network.feed_forward(input);
+
+    let value = network.value();
+    let error = network
+        .loss
+        .compute_weighted_error(&value, output, class_weights, sample_weight);
+    let delta = network.compute_weighted_output_delta(output, class_weights, sample_weight);
+    let deltas = network.feed_backward(delta);
+
+    (network.compute_gradients(&deltas), error, value)
+}
+
 fn print_error_output_expected(error: f64, expected: &Matrix, output: &Matrix) {
     println!("Expected:");
     expected.print();
@@ -0,0 +1,49 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientClipping {
+    // clamps every component of the gradients to [-value, value]
+    Value(f64),
+    // rescales the gradients so their joint euclidean norm is at most norm
+    GlobalNorm(f64),
+}
+
+// what the session does once a loss, a gradient or a parameter turns NaN or
+// infinite
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NonFinitePolicy {
+    Ignore,
+    // stops training, leaving the network as it is
+    Stop,
+    // stops training and restores the weights of the last completed epoch
+    Rollback,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NonFiniteSource {
+    Loss,
+    Gradients(usize),
+    Parameters(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Divergence {
+    pub epoch: usize,
+    pub step: usize,
+    pub source: NonFiniteSource,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source {
+            NonFiniteSource::Loss => write!(f, "the loss is not finite"),
+            NonFiniteSource::Gradients(layer) => {
+                write!(f, "the gradients of layer {} are not finite", layer)
+            }
+            NonFiniteSource::Parameters(layer) => {
+                write!(f, "the parameters of layer {} are not finite", layer)
+            }
+        }?;
+        write!(f, " (epoch {}, step {})", self.epoch, self.step)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::maths::Matrix;
+use crate::networks::{DenseNetwork, Gradients};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// how a session goes through the training data
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrainingStrategy {
+    // one update per minibatch, a minibatch of 1 meaning online training
+    Synchronous,
+    // online updates from the given number of threads, all writing to the
+    // same parameters without any lock
+    Hogwild(usize),
+}
+
+// parameters of a network shared between the hogwild threads. Every value
+// is an f64 stored in the bits of an atomic, read and written on its own:
+// concurrent updates of one parameter may overwrite each other, which
+// hogwild tolerates.
+pub(crate) struct SharedParameters {
+    weights: Vec<Vec<AtomicU64>>,
+    biases: Vec<Vec<AtomicU64>>,
+}
+
+fn share(matrix: &Matrix) -> Vec<AtomicU64> {
+    (0..matrix.len())
+        .map(|i| AtomicU64::new(matrix.get(i).to_bits()))
+        .collect()
+}
+
+fn read(values: &[AtomicU64], matrix: &mut Matrix) {
+    for (i, value) in values.iter().enumerate() {
+        matrix.set(i, f64::from_bits(value.load(Ordering::Relaxed)));
+    }
+}
+
+fn add(value: &AtomicU64, change: f64) {
+    let current = f64::from_bits(value.load(Ordering::Relaxed));
+    value.store((current + change).to_bits(), Ordering::Relaxed);
+}
+
+// adds after - before to the shared values, skipping the parameters left
+// untouched so that sparse updates stay sparse
+fn add_change(values: &[AtomicU64], before: &Matrix, after: &Matrix) {
+    for (i, value) in values.iter().enumerate() {
+        let change = after.get(i) - before.get(i);
+        if change != 0.0 {
+            add(value, change);
+        }
+    }
+}
+
+impl SharedParameters {
+    pub fn new(network: &DenseNetwork) -> SharedParameters {
+        SharedParameters {
+            weights: network.weights().iter().map(share).collect(),
+            biases: network.biases().iter().map(share).collect(),
+        }
+    }
+
+    // copies the current shared values into the network
+    pub fn read_into(&self, network: &mut DenseNetwork) {
+        let (weights, biases) = network.parameters_mut();
+        for l in 0..weights.len() {
+            read(&self.weights[l], &mut weights[l]);
+            read(&self.biases[l], &mut biases[l]);
+        }
+    }
+
+    // subtracts the step of the gradients from the shared values, in place
+    // and on top of what the other threads wrote meanwhile. The penalties
+    // and weight decay are taken from network, the copy the gradients were
+    // computed on. Only the parameters with a non zero step are written, so
+    // that sparse gradients make sparse updates.
+    pub fn apply_gradients(
+        &self,
+        gradients: &Gradients,
+        learning_rate: f64,
+        network: &DenseNetwork,
+    ) {
+        let decay = learning_rate * network.weight_decay();
+        for l in 0..self.weights.len() {
+            let regularization = &network.regularizations()[l];
+            let weights = &network.weights()[l];
+            for (i, value) in self.weights[l].iter().enumerate() {
+                let w = weights.get(i);
+                let step = gradients.weights[l].get(i) + regularization.derivative(w);
+                let change = -(learning_rate * step + decay * w);
+                if change != 0.0 {
+                    add(value, change);
+                }
+            }
+            for (i, value) in self.biases[l].iter().enumerate() {
+                let change = -learning_rate * gradients.biases[l].get(i);
+                if change != 0.0 {
+                    add(value, change);
+                }
+            }
+        }
+    }
+
+    // applies the change a thread made to its copy of the parameters, on
+    // top of what the other threads wrote meanwhile
+    pub fn add_change(&self, before: &(Vec<Matrix>, Vec<Matrix>), after: &DenseNetwork) {
+        for l in 0..self.weights.len() {
+            add_change(&self.weights[l], &before.0[l], &after.weights()[l]);
+            add_change(&self.biases[l], &before.1[l], &after.biases()[l]);
+        }
+    }
+}
@@ -1,10 +1,25 @@
+mod callbacks;
+mod checkpoint;
+mod cross_validation;
 mod dense_session;
+mod divergence;
+mod hogwild;
+mod training_history;
 use crate::networks::Network;
+pub use callbacks::{
+    Callback, EarlyStopping, Logs, MonitorMode, ProgressBarLogger, ThresholdStopping,
+};
+pub use checkpoint::{latest_checkpoint, ModelCheckpoint};
+pub use cross_validation::{cross_validate, CrossValidation};
+pub(crate) use dense_session::sample_gradients;
 pub use dense_session::DenseSession;
+pub use divergence::{Divergence, GradientClipping, NonFinitePolicy, NonFiniteSource};
+pub use hogwild::TrainingStrategy;
+pub use training_history::{EpochRecord, TrainingHistory};
 
 pub trait Session<T: Network> {
-    fn fit(&mut self) -> f64;
-    fn train(&mut self);
+    fn fit(&mut self) -> TrainingHistory;
+    fn train(&mut self) -> TrainingHistory;
     fn test(&mut self) -> f64;
 
     fn release_network(self) -> T;
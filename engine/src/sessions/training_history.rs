@@ -0,0 +1,140 @@
+use crate::sessions::Logs;
+
+use std::collections::BTreeSet;
+use std::fs;
+
+// logs of one completed epoch, wall_time being the seconds it took
+// including the validation
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpochRecord {
+    pub epoch: usize,
+    pub wall_time: f64,
+    pub logs: Logs,
+}
+
+// what a training went through, epoch by epoch, followed by the logs of
+// the test when the session was fitted
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrainingHistory {
+    pub epochs: Vec<EpochRecord>,
+    pub test: Logs,
+}
+
+impl TrainingHistory {
+    pub fn new() -> TrainingHistory {
+        TrainingHistory::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.epochs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&EpochRecord> {
+        self.epochs.last()
+    }
+
+    // value of the metric at each epoch, NaN where it was not tracked
+    pub fn metric(&self, name: &str) -> Vec<f64> {
+        self.epochs
+            .iter()
+            .map(|record| record.logs.get(name).copied().unwrap_or(f64::NAN))
+            .collect()
+    }
+
+    // every metric logged by at least one epoch, in alphabetical order
+    pub fn metric_names(&self) -> Vec<String> {
+        let names = self.epochs.iter().flat_map(|record| record.logs.keys());
+        names
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    // one row per epoch, an empty cell standing for an untracked metric
+    pub fn to_csv(&self) -> String {
+        let names = self.metric_names();
+        let mut csv = String::from("epoch,wall_time");
+        for name in &names {
+            csv.push(',');
+            csv.push_str(name);
+        }
+        csv.push('\n');
+
+        for record in &self.epochs {
+            csv.push_str(&format!("{},{}", record.epoch, record.wall_time));
+            for name in &names {
+                csv.push(',');
+                if let Some(value) = record.logs.get(name) {
+                    csv.push_str(&value.to_string());
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    // {"epochs": [{"epoch": 0, "wall_time": .., "loss": .., ...}, ...],
+    // "test": {...}}, NaN and infinite values being written as null
+    pub fn to_json(&self) -> String {
+        let epochs = self
+            .epochs
+            .iter()
+            .map(|record| {
+                let mut fields = vec![
+                    format!("\"epoch\":{}", record.epoch),
+                    format!("\"wall_time\":{}", json_number(record.wall_time)),
+                ];
+                fields.extend(json_fields(&record.logs));
+                format!("{{{}}}", fields.join(","))
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\"epochs\":[{}],\"test\":{{{}}}}}",
+            epochs.join(","),
+            json_fields(&self.test).join(",")
+        )
+    }
+
+    pub fn save_csv(&self, path: &str) {
+        fs::write(path, self.to_csv()).expect("Could not save the history at the given path.");
+    }
+
+    pub fn save_json(&self, path: &str) {
+        fs::write(path, self.to_json()).expect("Could not save the history at the given path.");
+    }
+}
+
+fn json_fields(logs: &Logs) -> Vec<String> {
+    logs.iter()
+        .map(|(name, value)| format!("{}:{}", json_string(name), json_number(*value)))
+        .collect()
+}
+
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        // keeps integral values distinguishable as floats
+        format!("{:?}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct DenseShape {
     pub x: usize,
     pub y: usize,
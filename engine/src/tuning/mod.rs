@@ -0,0 +1,5 @@
+mod search_space;
+mod tuner;
+
+pub use search_space::{Domain, Params, SearchSpace, Value};
+pub use tuner::{Trial, Tuner, TuningResults};
@@ -0,0 +1,183 @@
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Float(f64),
+    Int(usize),
+    Text(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+// values of the hyperparameters of one trial, keyed by name
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Params {
+    pub values: BTreeMap<String, Value>,
+}
+
+impl Params {
+    pub fn get(&self, name: &str) -> &Value {
+        match self.values.get(name) {
+            Some(value) => value,
+            None => panic!("Unknown hyperparameter {}", name),
+        }
+    }
+
+    pub fn float(&self, name: &str) -> f64 {
+        match self.get(name) {
+            Value::Float(value) => *value,
+            Value::Int(value) => *value as f64,
+            Value::Text(_) => panic!("The hyperparameter {} is not a number", name),
+        }
+    }
+
+    pub fn int(&self, name: &str) -> usize {
+        match self.get(name) {
+            Value::Int(value) => *value,
+            _ => panic!("The hyperparameter {} is not an integer", name),
+        }
+    }
+
+    pub fn text(&self, name: &str) -> &str {
+        match self.get(name) {
+            Value::Text(value) => value,
+            _ => panic!("The hyperparameter {} is not a text", name),
+        }
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Domain {
+    Choice(Vec<Value>),
+    Uniform(f64, f64),
+    // sampled uniformly between the logarithms of the bounds
+    LogUniform(f64, f64),
+    // integers from the first bound to the second, both included
+    IntRange(usize, usize),
+}
+
+impl Domain {
+    fn sample<R: Rng>(&self, rng: &mut R) -> Value {
+        match self {
+            Domain::Choice(values) => values[rng.gen_range(0..values.len())].clone(),
+            Domain::Uniform(low, high) => Value::Float(rng.gen_range(*low..=*high)),
+            Domain::LogUniform(low, high) => {
+                Value::Float(rng.gen_range(low.ln()..=high.ln()).exp())
+            }
+            Domain::IntRange(low, high) => Value::Int(rng.gen_range(*low..=*high)),
+        }
+    }
+
+    fn grid(&self) -> Vec<Value> {
+        match self {
+            Domain::Choice(values) => values.clone(),
+            Domain::IntRange(low, high) => (*low..=*high).map(Value::Int).collect(),
+            _ => panic!("A grid search needs discrete hyperparameters"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchSpace {
+    pub domains: Vec<(String, Domain)>,
+}
+
+impl SearchSpace {
+    pub fn new() -> SearchSpace {
+        SearchSpace::default()
+    }
+
+    pub fn with(mut self, name: &str, domain: Domain) -> SearchSpace {
+        assert!(
+            !self.domains.iter().any(|(other, _)| other == name),
+            "The hyperparameter {} is already defined",
+            name
+        );
+        if let Domain::Choice(values) = &domain {
+            assert!(!values.is_empty(), "A choice needs at least one value");
+        }
+        self.domains.push((name.to_string(), domain));
+        self
+    }
+
+    pub fn floats(self, name: &str, values: Vec<f64>) -> SearchSpace {
+        self.with(
+            name,
+            Domain::Choice(values.into_iter().map(Value::Float).collect()),
+        )
+    }
+
+    pub fn ints(self, name: &str, values: Vec<usize>) -> SearchSpace {
+        self.with(
+            name,
+            Domain::Choice(values.into_iter().map(Value::Int).collect()),
+        )
+    }
+
+    pub fn texts(self, name: &str, values: Vec<&str>) -> SearchSpace {
+        let values = values.into_iter().map(|v| Value::Text(v.to_string()));
+        self.with(name, Domain::Choice(values.collect()))
+    }
+
+    pub fn log_uniform(self, name: &str, low: f64, high: f64) -> SearchSpace {
+        assert!(0.0 < low && low <= high, "Invalid log-uniform bounds");
+        self.with(name, Domain::LogUniform(low, high))
+    }
+
+    pub fn uniform(self, name: &str, low: f64, high: f64) -> SearchSpace {
+        assert!(low <= high, "Invalid uniform bounds");
+        self.with(name, Domain::Uniform(low, high))
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Params {
+        let values = self
+            .domains
+            .iter()
+            .map(|(name, domain)| (name.clone(), domain.sample(rng)));
+        Params {
+            values: values.collect(),
+        }
+    }
+
+    // cartesian product of every discrete domain, the last hyperparameter
+    // varying fastest
+    pub fn grid(&self) -> Vec<Params> {
+        let mut grid = vec![Params::default()];
+        for (name, domain) in &self.domains {
+            let values = domain.grid();
+            grid = grid
+                .into_iter()
+                .flat_map(|params| {
+                    values.iter().map(move |value| {
+                        let mut params = params.clone();
+                        params.values.insert(name.clone(), value.clone());
+                        params
+                    })
+                })
+                .collect();
+        }
+        grid
+    }
+}
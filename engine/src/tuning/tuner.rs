@@ -0,0 +1,228 @@
+use crate::sessions::{DenseSession, MonitorMode, Session, TrainingHistory};
+use crate::tuning::{Params, SearchSpace};
+
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+
+#[derive(Clone, Debug)]
+pub struct Trial {
+    pub params: Params,
+    // epochs the session was built with
+    pub epochs: usize,
+    pub score: f64,
+    pub history: TrainingHistory,
+    // the session diverged or ended on a non-finite score, which is then NaN
+    pub failed: bool,
+}
+
+// trials ranked best first, longer trials coming before shorter ones so
+// that the survivors of a successive halving lead, the failed trials last
+#[derive(Clone, Debug, Default)]
+pub struct TuningResults {
+    pub trials: Vec<Trial>,
+}
+
+impl TuningResults {
+    pub fn best(&self) -> &Trial {
+        self.trials.first().expect("No trial was run")
+    }
+
+    pub fn best_params(&self) -> &Params {
+        &self.best().params
+    }
+
+    // one line per trial: rank, score, epochs and hyperparameters
+    pub fn to_table(&self) -> String {
+        let mut table = String::from("rank\tscore\tepochs\tparams\n");
+        for (rank, trial) in self.trials.iter().enumerate() {
+            table.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                rank + 1,
+                trial.score,
+                trial.epochs,
+                trial.params
+            ));
+        }
+        table
+    }
+}
+
+// searches the hyperparameters optimizing the monitored metric, read in the
+// logs of the last training epoch. The sessions are only trained, never
+// tested, so that the testing data stays out of the search: the builder
+// should give them validation data and the tuner monitor a val_ metric.
+// Trials run in parallel, the builder creating the session of a trial from
+// its hyperparameters and number of epochs.
+pub struct Tuner {
+    pub space: SearchSpace,
+    pub monitor: String,
+    pub mode: MonitorMode,
+    seed: u64,
+}
+
+impl Tuner {
+    pub fn new(space: SearchSpace, monitor: &str, mode: MonitorMode) -> Tuner {
+        Tuner {
+            space,
+            monitor: monitor.to_string(),
+            mode,
+            seed: thread_rng().gen(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Tuner {
+        self.seed = seed;
+        self
+    }
+
+    pub fn grid_search<F>(&self, builder: F, epochs: usize) -> TuningResults
+    where
+        F: Fn(&Params, usize) -> DenseSession + Sync,
+    {
+        let trials = self.run_trials(&builder, self.space.grid(), epochs);
+        self.rank(trials)
+    }
+
+    pub fn random_search<F>(&self, builder: F, nb_trials: usize, epochs: usize) -> TuningResults
+    where
+        F: Fn(&Params, usize) -> DenseSession + Sync,
+    {
+        let trials = self.run_trials(&builder, self.sample(nb_trials, 0), epochs);
+        self.rank(trials)
+    }
+
+    // trains nb_configs random configurations for min_epochs, keeps the best
+    // 1 / eta of them and trains those again eta times longer, until one is
+    // left or max_epochs is reached
+    pub fn successive_halving<F>(
+        &self,
+        builder: F,
+        nb_configs: usize,
+        min_epochs: usize,
+        max_epochs: usize,
+        eta: usize,
+    ) -> TuningResults
+    where
+        F: Fn(&Params, usize) -> DenseSession + Sync,
+    {
+        let configs = self.sample(nb_configs, 0);
+        let trials = self.halve(&builder, configs, min_epochs, max_epochs, eta);
+        self.rank(trials)
+    }
+
+    // runs successive halvings trading the number of configurations against
+    // their starting budget, from many short trials to a few full ones
+    pub fn hyperband<F>(&self, builder: F, max_epochs: usize, eta: usize) -> TuningResults
+    where
+        F: Fn(&Params, usize) -> DenseSession + Sync,
+    {
+        assert!(eta > 1, "eta must be at least 2");
+        let mut s_max = 0;
+        while eta.pow(s_max + 1) <= max_epochs {
+            s_max += 1;
+        }
+
+        let mut trials = vec![];
+        for s in (0..=s_max).rev() {
+            let nb_configs = ((s_max + 1) as f64 / (s + 1) as f64 * eta.pow(s) as f64).ceil();
+            let min_epochs = max_epochs / eta.pow(s);
+            let configs = self.sample(nb_configs as usize, s as u64 + 1);
+            trials.extend(self.halve(&builder, configs, min_epochs, max_epochs, eta));
+        }
+        self.rank(trials)
+    }
+
+    // every bracket of a search draws its configurations from its own seed
+    fn sample(&self, nb_configs: usize, bracket: u64) -> Vec<Params> {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(bracket));
+        (0..nb_configs)
+            .map(|_| self.space.sample(&mut rng))
+            .collect()
+    }
+
+    fn halve<F>(
+        &self,
+        builder: &F,
+        mut configs: Vec<Params>,
+        min_epochs: usize,
+        max_epochs: usize,
+        eta: usize,
+    ) -> Vec<Trial>
+    where
+        F: Fn(&Params, usize) -> DenseSession + Sync,
+    {
+        assert!(eta > 1, "eta must be at least 2");
+        assert!(min_epochs > 0, "Trials must last at least one epoch");
+        let mut trials = vec![];
+        let mut epochs = min_epochs;
+        loop {
+            let rung = self.rank(self.run_trials(builder, configs, epochs));
+            let nb_kept = rung.trials.len() / eta;
+            configs = rung
+                .trials
+                .iter()
+                .filter(|trial| !trial.failed)
+                .take(nb_kept)
+                .map(|trial| trial.params.clone())
+                .collect();
+            trials.extend(rung.trials);
+
+            if configs.is_empty() || epochs >= max_epochs {
+                return trials;
+            }
+            epochs = (epochs * eta).min(max_epochs);
+        }
+    }
+
+    fn run_trials<F>(&self, builder: &F, configs: Vec<Params>, epochs: usize) -> Vec<Trial>
+    where
+        F: Fn(&Params, usize) -> DenseSession + Sync,
+    {
+        configs
+            .into_par_iter()
+            .map(|params| {
+                let mut session = builder(&params, epochs);
+                let history = session.train();
+                let score = self.score(&history, session.divergence().is_some());
+                Trial {
+                    params,
+                    epochs,
+                    score,
+                    failed: score.is_nan(),
+                    history,
+                }
+            })
+            .collect()
+    }
+
+    // NaN when the trial failed: bad hyperparameters are expected to
+    // diverge, possibly before completing a single epoch
+    fn score(&self, history: &TrainingHistory, diverged: bool) -> f64 {
+        let Some(record) = history.last().filter(|_| !diverged) else {
+            return f64::NAN;
+        };
+        match record.logs.get(&self.monitor) {
+            Some(score) if score.is_finite() => *score,
+            Some(_) => f64::NAN,
+            None => panic!("The monitored metric {} is not tracked", self.monitor),
+        }
+    }
+
+    fn compare(&self, a: &Trial, b: &Trial) -> Ordering {
+        let by_score = match self.mode {
+            MonitorMode::Min => a.score.total_cmp(&b.score),
+            MonitorMode::Max => b.score.total_cmp(&a.score),
+        };
+        a.failed
+            .cmp(&b.failed)
+            .then(b.epochs.cmp(&a.epochs))
+            .then(by_score)
+    }
+
+    fn rank(&self, mut trials: Vec<Trial>) -> TuningResults {
+        trials.sort_by(|a, b| self.compare(a, b));
+        TuningResults { trials }
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod augmentation_tests {
+    use bricks::augmentation::{
+        Augmentation, Cutout, ElasticDistortion, GaussianNoise, HorizontalFlip, RandomRotation,
+        RandomScale, RandomShift, Transform,
+    };
+    use bricks::data::DataLoader;
+    use bricks::maths::Matrix;
+    use bricks::shapes::DenseShape;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::sync::Arc;
+
+    // a 3x3 image of the values 1 to 9, row after row
+    fn image() -> (Matrix, DenseShape) {
+        let values = (1..=9).map(|value| value as f64).collect::<Vec<_>>();
+        (Matrix::from(values), DenseShape::new(3, 3, 1))
+    }
+
+    fn values(matrix: &Matrix) -> Vec<f64> {
+        (0..matrix.len()).map(|i| matrix.get(i)).collect()
+    }
+
+    #[test]
+    fn test_identity_transforms() {
+        let (image, shape) = image();
+        let mut rng = StdRng::seed_from_u64(0);
+        let transforms: Vec<Box<dyn Transform>> = vec![
+            Box::new(RandomShift::new(0, 0)),
+            Box::new(RandomRotation::new(0.0)),
+            Box::new(RandomScale::new(1.0, 1.0)),
+            Box::new(ElasticDistortion::new(0.0, 1.0)),
+            Box::new(GaussianNoise::new(0.0)),
+            Box::new(HorizontalFlip::new(0.0)),
+        ];
+        for transform in transforms {
+            let result = transform.apply(&image, &shape, &mut rng);
+            assert_eq!((result.w, result.h), (1, 9));
+            for (a, b) in values(&result).iter().zip(values(&image)) {
+                assert!((a - b).abs() < 1E-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_geometric_transforms() {
+        let (image, shape) = image();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let flipped = HorizontalFlip::new(1.0).apply(&image, &shape, &mut rng);
+        assert_eq!(
+            values(&flipped),
+            vec![3.0, 2.0, 1.0, 6.0, 5.0, 4.0, 9.0, 8.0, 7.0]
+        );
+        // channels stay together
+        let rgb = Matrix::from(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let flipped = HorizontalFlip::new(1.0).apply(&rgb, &DenseShape::new(2, 1, 3), &mut rng);
+        assert_eq!(values(&flipped), vec![4.0, 5.0, 6.0, 1.0, 2.0, 3.0]);
+
+        // shifted by one pixel along one axis at most
+        let shifted = RandomShift::new(1, 0).apply(&image, &shape, &mut rng);
+        let candidates = [
+            vec![0.0, 1.0, 2.0, 0.0, 4.0, 5.0, 0.0, 7.0, 8.0],
+            values(&image),
+            vec![2.0, 3.0, 0.0, 5.0, 6.0, 0.0, 8.0, 9.0, 0.0],
+        ];
+        assert!(candidates.contains(&values(&shifted)));
+
+        // the center does not move
+        for _ in 0..10 {
+            let rotated = RandomRotation::new(30.0).apply(&image, &shape, &mut rng);
+            assert!((rotated.get(4) - 5.0).abs() < 1E-9);
+            let scaled = RandomScale::new(0.8, 1.2).apply(&image, &shape, &mut rng);
+            assert!((scaled.get(4) - 5.0).abs() < 1E-9);
+        }
+
+        let shape = DenseShape::new(8, 8, 1);
+        let distorted =
+            ElasticDistortion::new(2.0, 1.0).apply(&Matrix::from(vec![1.0; 64]), &shape, &mut rng);
+        assert!(distorted.is_finite());
+        assert!(values(&distorted)
+            .iter()
+            .all(|&v| (0.0..=1.0 + 1E-9).contains(&v)));
+    }
+
+    #[test]
+    fn test_noise_and_cutout() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let shape = DenseShape::new(100, 100, 1);
+        let noisy = GaussianNoise::new(0.5).apply(&Matrix::new(1, 10000), &shape, &mut rng);
+        let mean = noisy.sum() / 10000.0;
+        let deviation = (values(&noisy)
+            .iter()
+            .map(|v| (v - mean).powi(2))
+            .sum::<f64>()
+            / 10000.0)
+            .sqrt();
+        assert!(mean.abs() < 0.02);
+        assert!((deviation - 0.5).abs() < 0.02);
+
+        let shape = DenseShape::new(6, 6, 2);
+        for _ in 0..20 {
+            let cut = Cutout::new(2).apply(&Matrix::from(vec![1.0; 72]), &shape, &mut rng);
+            let zeros = values(&cut).iter().filter(|&&v| v == 0.0).count();
+            // both channels of one to four pixels
+            assert!(zeros % 2 == 0 && (2..=8).contains(&zeros));
+        }
+    }
+
+    #[test]
+    fn test_seeded_augmentation() {
+        let (image, shape) = image();
+        let augmentation = Augmentation::new(shape)
+            .with_transform(RandomRotation::new(15.0))
+            .with_transform(GaussianNoise::new(0.1))
+            .with_seed(3);
+        let sample = (image, Matrix::from(vec![1.0]));
+
+        let first = augmentation.augment(&sample, 0, 5);
+        assert_eq!(
+            first.0.to_string(),
+            augmentation.augment(&sample, 0, 5).0.to_string()
+        );
+        assert_eq!(first.1.to_string(), sample.1.to_string());
+        assert_ne!(
+            first.0.to_string(),
+            augmentation.augment(&sample, 1, 5).0.to_string()
+        );
+        assert_ne!(
+            first.0.to_string(),
+            augmentation.augment(&sample, 0, 6).0.to_string()
+        );
+
+        // the same batches whatever the prefetching
+        let data = (0..10)
+            .map(|i| {
+                (
+                    Matrix::from(vec![i as f64; 9]),
+                    Matrix::from(vec![i as f64]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let augmentation = Arc::new(augmentation);
+        let loader = DataLoader::new(data, 3)
+            .with_shuffle(7)
+            .with_augmentation(augmentation.clone());
+        let prefetching = loader.clone().with_prefetch(2);
+        for epoch in 0..2 {
+            for (a, b) in loader.batches(epoch).zip(prefetching.batches(epoch)) {
+                assert_eq!(a.indices, b.indices);
+                for (a, b) in a.samples.iter().zip(&b.samples) {
+                    assert_eq!(a.0.to_string(), b.0.to_string());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,752 @@
+#[cfg(test)]
+mod data_tests {
+    use bricks::data::{
+        balanced_class_weights, k_fold_indices, load_data, load_mnist, ordered_split,
+        shuffled_split, split_data, stratified_k_fold_indices, stratified_split, Column, CsvError,
+        CsvLoader, DataLoader, Dataset, FileDataset, IdxArray, IdxError, IdxType, Subset,
+    };
+    use bricks::data::{load_npz_data, npy_samples, npz_samples};
+    use bricks::data::{CacheError, CacheType, CacheWriter, MappedDataset};
+    use bricks::data::{ClassNames, Image, ImageError, ImageFolder};
+    use bricks::maths::{save_npz, Matrix, NpyError};
+    use bricks::shapes::DenseShape;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_balanced_class_weights() {
+        let data = vec![
+            (Matrix::from(vec![0.0]), Matrix::from(vec![1.0, 0.0, 0.0])),
+            (Matrix::from(vec![0.0]), Matrix::from(vec![1.0, 0.0, 0.0])),
+            (Matrix::from(vec![0.0]), Matrix::from(vec![1.0, 0.0, 0.0])),
+            (Matrix::from(vec![0.0]), Matrix::from(vec![0.0, 1.0, 0.0])),
+        ];
+        let weights = balanced_class_weights(&data);
+
+        assert_eq!(weights.len(), 3);
+        assert!((weights.get(0) - 4.0 / 9.0).abs() < 1E-12);
+        assert!((weights.get(1) - 4.0 / 3.0).abs() < 1E-12);
+        assert_eq!(weights.get(2), 0.0);
+    }
+
+    #[test]
+    fn test_balanced_binary_class_weights() {
+        let data = vec![
+            (Matrix::from(vec![0.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![0.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![0.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![0.0]), Matrix::from(vec![1.0])),
+        ];
+        let weights = balanced_class_weights(&data);
+
+        assert_eq!(weights.len(), 2);
+        assert!((weights.get(0) - 2.0 / 3.0).abs() < 1E-12);
+        assert!((weights.get(1) - 2.0).abs() < 1E-12);
+    }
+
+    // 10 samples, the input being the index and the first 4 of class 1
+    fn labelled_data() -> Vec<(Matrix, Matrix)> {
+        (0..10)
+            .map(|i| {
+                (
+                    Matrix::from(vec![i as f64]),
+                    Matrix::from(vec![(i < 4) as usize as f64]),
+                )
+            })
+            .collect()
+    }
+
+    fn inputs(data: &[(Matrix, Matrix)]) -> Vec<usize> {
+        data.iter()
+            .map(|(input, _)| input.get(0) as usize)
+            .collect()
+    }
+
+    #[test]
+    fn test_split_data_order() {
+        let (tail, head) = split_data(labelled_data(), 30);
+        assert_eq!(inputs(&head), vec![0, 1, 2]);
+        assert_eq!(inputs(&tail), vec![3, 4, 5, 6, 7, 8, 9]);
+
+        let (training, testing) = ordered_split(labelled_data(), 30);
+        assert_eq!(inputs(&training), vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(inputs(&testing), vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn test_shuffled_split() {
+        let (training, testing) = shuffled_split(labelled_data(), 30, 7);
+        assert_eq!((training.len(), testing.len()), (7, 3));
+
+        let mut all = inputs(&training);
+        all.extend(inputs(&testing));
+        all.sort();
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+
+        let (same_training, _) = shuffled_split(labelled_data(), 30, 7);
+        assert_eq!(inputs(&training), inputs(&same_training));
+    }
+
+    #[test]
+    fn test_stratified_split() {
+        let (training, testing) = stratified_split(labelled_data(), 50, 3);
+        let positives =
+            |data: &[(Matrix, Matrix)]| data.iter().filter(|(_, o)| o.get(0) == 1.0).count();
+
+        assert_eq!((training.len(), testing.len()), (5, 5));
+        assert_eq!(positives(&training), 2);
+        assert_eq!(positives(&testing), 2);
+
+        // two classes of 5 samples, 0 to 4 and 5 to 9, must not be drawn from
+        // the same positions
+        let halves = (0..10)
+            .map(|i| {
+                (
+                    Matrix::from(vec![i as f64]),
+                    Matrix::from(vec![(i < 5) as usize as f64]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let correlated = (0..10).all(|seed| {
+            let (_, testing) = stratified_split(halves.clone(), 40, seed);
+            let mut positions = inputs(&testing)
+                .into_iter()
+                .map(|i| i % 5)
+                .collect::<Vec<_>>();
+            positions.sort();
+            positions.dedup();
+            positions.len() == 2
+        });
+        assert!(!correlated);
+    }
+
+    #[test]
+    fn test_k_fold_indices() {
+        let folds = k_fold_indices(10, 3);
+        assert_eq!(folds, vec![vec![0, 1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]);
+
+        let folds = stratified_k_fold_indices(&labelled_data(), 2);
+        assert_eq!(folds, vec![vec![0, 2, 4, 6, 8], vec![1, 3, 5, 7, 9]]);
+    }
+
+    #[test]
+    fn test_data_loader_batches() {
+        let loader = DataLoader::new(labelled_data(), 4);
+        assert_eq!(loader.len(), 3);
+        let batches = loader.into_iter().collect::<Vec<_>>();
+        let sizes = batches.iter().map(|b| b.samples.len()).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![4, 4, 2]);
+        assert_eq!(batches[1].indices, vec![4, 5, 6, 7]);
+        assert_eq!(inputs(&batches[2].samples), vec![8, 9]);
+
+        let loader = loader.with_drop_last(true);
+        assert_eq!(loader.len(), 2);
+        assert_eq!(loader.batches(0).count(), 2);
+    }
+
+    #[test]
+    fn test_data_loader_shuffle() {
+        let loader = DataLoader::new(labelled_data(), 3).with_shuffle(5);
+        let mut order = loader.order(0);
+        assert_eq!(loader.order(0), order);
+        assert_ne!(loader.order(1), order);
+
+        let visited = loader
+            .batches(0)
+            .flat_map(|batch| inputs(&batch.samples))
+            .collect::<Vec<_>>();
+        assert_eq!(visited, order);
+        order.sort_unstable();
+        assert_eq!(order, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_data_loader_prefetch() {
+        let loader = DataLoader::new(labelled_data(), 3).with_shuffle(2);
+        let prefetching = loader.clone().with_prefetch(2);
+        for epoch in 0..3 {
+            let expected = loader.batches(epoch).map(|b| b.indices).collect::<Vec<_>>();
+            let batches = prefetching.batches(epoch).collect::<Vec<_>>();
+            assert_eq!(
+                batches
+                    .iter()
+                    .map(|b| b.indices.clone())
+                    .collect::<Vec<_>>(),
+                expected
+            );
+            for batch in batches {
+                assert_eq!(inputs(&batch.samples), batch.indices);
+            }
+        }
+
+        // stopping early leaves the threads to end on their own
+        assert_eq!(prefetching.batches(0).take(1).count(), 1);
+    }
+
+    #[test]
+    fn test_file_dataset() {
+        for path in [
+            "../examples/xor/training_data.dat",
+            "../examples/digit_counter/training_data.dat",
+        ] {
+            let dataset = FileDataset::open(path);
+            let data = load_data(path);
+            assert_eq!(dataset.len(), data.len());
+            for i in (0..data.len()).rev() {
+                let (input, output) = &*dataset.get(i);
+                assert_eq!(input.to_string(), data[i].0.to_string());
+                assert_eq!(output.to_string(), data[i].1.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_subset() {
+        let subset = Subset::new(Arc::new(labelled_data()), vec![7, 2, 9]);
+        assert_eq!(subset.len(), 3);
+        assert_eq!(subset.get(1).0.get(0), 2.0);
+        assert_eq!(subset.get(2).0.get(0), 9.0);
+    }
+
+    #[test]
+    fn test_csv_loader() {
+        let csv = "length,colour,\"weight, kg\",species\n\
+                   1.5,2,3.25,cat\n\
+                   \n\
+                   0.5, 1 ,,dog\r\n\
+                   2,0,1,\"cat\"\n";
+        let loader = CsvLoader::new(vec!["species"])
+            .with_features(vec![Column::from("weight, kg"), Column::from(0)]);
+        let data = loader.parse(csv).unwrap();
+
+        assert_eq!(data.feature_names, vec!["weight, kg", "length"]);
+        assert_eq!(data.target_names, vec!["species"]);
+        assert_eq!(
+            data.classes,
+            vec![Some(vec!["cat".to_string(), "dog".to_string()])]
+        );
+        assert_eq!(data.samples.len(), 3);
+        assert_eq!(
+            data.samples[0].0.to_string(),
+            Matrix::from(vec![3.25, 1.5]).to_string()
+        );
+        assert_eq!(
+            data.samples[1].1.to_string(),
+            Matrix::from(vec![0.0, 1.0]).to_string()
+        );
+        assert_eq!(
+            data.samples[2].1.to_string(),
+            Matrix::from(vec![1.0, 0.0]).to_string()
+        );
+        // an empty feature is missing
+        assert!(data.samples[1].0.get(0).is_nan());
+    }
+
+    #[test]
+    fn test_csv_without_header() {
+        let csv = "0;1;2\n3;4;10\n5;6;2\n";
+        let loader = CsvLoader::new(vec![2])
+            .with_header(false)
+            .with_delimiter(';');
+        let data = loader.clone().parse(csv).unwrap();
+        assert_eq!(data.feature_names, vec!["0", "1"]);
+        assert_eq!(data.classes, vec![None]);
+        assert_eq!(data.samples[1].1.get(0), 10.0);
+
+        // numeric labels sorted as numbers once one-hot encoded
+        let data = loader.with_one_hot(true).parse(csv).unwrap();
+        assert_eq!(
+            data.classes,
+            vec![Some(vec!["2".to_string(), "10".to_string()])]
+        );
+        assert_eq!(
+            data.samples[1].1.to_string(),
+            Matrix::from(vec![0.0, 1.0]).to_string()
+        );
+
+        let error = CsvLoader::new(vec!["label"]).with_header(false).parse(csv);
+        assert_eq!(
+            error.err(),
+            Some(CsvError::UnknownColumn(Column::from("label")))
+        );
+    }
+
+    #[test]
+    fn test_csv_bad_rows() {
+        let csv = "a,b,label\n1,2,x\n1,two,y\n\n3,4\n5,6,\n7,8,\"z\n";
+        let loader = CsvLoader::new(vec!["label"]);
+        match loader.parse(csv) {
+            Err(CsvError::BadRows(rows)) => {
+                let lines = rows.iter().map(|(line, _)| *line).collect::<Vec<_>>();
+                assert_eq!(lines, vec![3, 5, 6, 7]);
+                assert!(rows[0].1.contains("\"two\" in column b"));
+                assert_eq!(rows[1].1, "expected 3 fields, found 2");
+            }
+            _ => panic!("Expected bad rows"),
+        }
+
+        let data = loader.with_skip_bad_rows(true).parse(csv).unwrap();
+        assert_eq!(data.samples.len(), 1);
+        assert_eq!(data.skipped.len(), 4);
+    }
+
+    #[test]
+    fn test_csv_matches_load_data() {
+        let data = load_data("../examples/digit_counter/training_data.dat");
+        let csv = data
+            .iter()
+            .map(|(input, output)| {
+                let values = (0..input.len()).map(|i| input.get(i));
+                let values = values.chain((0..output.len()).map(|i| output.get(i)));
+                values.map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let nb_inputs = data[0].0.len();
+        let targets = (nb_inputs..nb_inputs + data[0].1.len()).collect();
+        let loaded = CsvLoader::new(targets)
+            .with_header(false)
+            .parse(&csv)
+            .unwrap();
+        assert_eq!(loaded.samples.len(), data.len());
+        for (sample, expected) in loaded.samples.iter().zip(&data) {
+            assert_eq!((sample.0.w, sample.0.h), (expected.0.w, expected.0.h));
+            assert_eq!(sample.0.to_string(), expected.0.to_string());
+            assert_eq!(sample.1.to_string(), expected.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_idx_parse() {
+        // 2 x 3 array of signed 16 bits integers
+        let bytes = vec![
+            0, 0, 0x0B, 2, 0, 0, 0, 2, 0, 0, 0, 3, 0, 1, 0, 2, 0xFF, 0xFF, 1, 0, 0x80, 0, 0, 0,
+        ];
+        let array = IdxArray::parse(&bytes).unwrap();
+        assert_eq!(array.element_type, IdxType::I16);
+        assert_eq!(array.dimensions, vec![2, 3]);
+        assert_eq!(array.values, vec![1.0, 2.0, -1.0, 256.0, -32768.0, 0.0]);
+        assert_eq!(array.len(), 2);
+        assert_eq!(array.to_bytes(), bytes);
+
+        assert_eq!(IdxArray::parse(&[1, 0, 8, 0]), Err(IdxError::BadMagic));
+        assert_eq!(
+            IdxArray::parse(&[0, 0, 7, 0]),
+            Err(IdxError::UnknownType(7))
+        );
+        assert_eq!(
+            IdxArray::parse(&bytes[..bytes.len() - 1]),
+            Err(IdxError::Truncated(24, 23))
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(IdxArray::parse(&trailing), Err(IdxError::TrailingBytes(1)));
+    }
+
+    #[test]
+    fn test_idx_round_trip() {
+        let types = [
+            IdxType::U8,
+            IdxType::I8,
+            IdxType::I16,
+            IdxType::I32,
+            IdxType::F32,
+            IdxType::F64,
+        ];
+        let dimensions = [vec![], vec![4], vec![2, 2], vec![1, 2, 1, 2]];
+        for element_type in types {
+            for dimensions in &dimensions {
+                let nb_values = dimensions.iter().product::<usize>();
+                let array = IdxArray {
+                    element_type,
+                    dimensions: dimensions.clone(),
+                    values: (0..nb_values).map(|i| i as f64 * 2.0 + 1.0).collect(),
+                };
+                let bytes = array.to_bytes();
+                assert_eq!(
+                    bytes.len(),
+                    4 + 4 * dimensions.len() + nb_values * element_type.size()
+                );
+                assert_eq!(IdxArray::parse(&bytes).unwrap(), array);
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_mnist() {
+        let directory = std::env::temp_dir().join(format!("bricks_idx_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let images_path = directory.join("images-idx3-ubyte");
+        let labels_path = directory.join("labels-idx1-ubyte");
+
+        let images = IdxArray {
+            element_type: IdxType::U8,
+            dimensions: vec![3, 28, 28],
+            values: (0..3 * 784).map(|i| (i % 256) as f64).collect(),
+        };
+        assert_eq!(images.item_shape().range, DenseShape::new(28, 28, 1).range);
+        images.save(images_path.to_str().unwrap());
+        let labels = IdxArray {
+            element_type: IdxType::U8,
+            dimensions: vec![3],
+            values: vec![7.0, 0.0, 9.0],
+        };
+        labels.save(labels_path.to_str().unwrap());
+
+        let (images_path, labels_path) =
+            (images_path.to_str().unwrap(), labels_path.to_str().unwrap());
+        let data = load_mnist(images_path, labels_path, true);
+        assert_eq!(data.len(), 3);
+        assert_eq!((data[1].0.w, data[1].0.h), (1, 784));
+        assert_eq!(data[1].0.get(0), (784 % 256) as f64 / 255.0);
+        assert_eq!(data[0].0.get(255), 1.0);
+        assert_eq!((data[2].1.w, data[2].1.h), (1, 10));
+        assert_eq!(data[0].1.get(7), 1.0);
+        assert_eq!(data[0].1.sum(), 1.0);
+
+        let raw = load_mnist(images_path, labels_path, false);
+        assert_eq!(raw[0].0.get(255), 255.0);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_npy_samples() {
+        let x = Matrix::reshape(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3);
+        let y = Matrix::from(vec![0.0, 1.0, 0.0]);
+        let samples = npy_samples(&x, &y);
+        assert_eq!(samples.len(), 3);
+        assert_eq!(
+            samples[1].0.to_string(),
+            Matrix::from(vec![3.0, 4.0]).to_string()
+        );
+        assert_eq!(
+            samples[1].1.to_string(),
+            Matrix::from(vec![1.0]).to_string()
+        );
+
+        let mut arrays = BTreeMap::new();
+        arrays.insert("X".to_string(), x);
+        arrays.insert("labels".to_string(), Matrix::from(vec![0.0, 1.0]));
+        assert_eq!(
+            npz_samples(&arrays, "X", "y").err(),
+            Some(NpyError::MissingArray("y".to_string()))
+        );
+        assert_eq!(
+            npz_samples(&arrays, "X", "labels").err(),
+            Some(NpyError::RowMismatch(3, 2))
+        );
+
+        arrays.insert("y".to_string(), Matrix::reshape(vec![1.0; 6], 2, 3));
+        let path = std::env::temp_dir().join(format!("bricks_npz_{}.npz", std::process::id()));
+        let path = path.to_str().unwrap();
+        save_npz(path, &arrays);
+        let samples = load_npz_data(path, "X", "y");
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(samples.len(), 3);
+        assert_eq!((samples[2].0.get(1), samples[2].1.h), (6.0, 2));
+    }
+
+    #[test]
+    fn test_binary_cache() {
+        let path = std::env::temp_dir().join(format!("bricks_cache_{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        let dataset = FileDataset::open("../examples/digit_counter/training_data.dat");
+        let header = CacheWriter::new(CacheType::F64)
+            .write(&dataset, path)
+            .unwrap();
+        assert_eq!(header.nb_samples, dataset.len());
+
+        let cache = MappedDataset::open(path).unwrap();
+        assert_eq!(cache.len(), dataset.len());
+        assert_eq!(cache.header().element_type, CacheType::F64);
+        for i in 0..dataset.len() {
+            let (input, output) = &*cache.get(i);
+            assert_eq!(input.to_string(), dataset.get(i).0.to_string());
+            assert_eq!(output.to_string(), dataset.get(i).1.to_string());
+        }
+
+        // shapes are kept, values are rounded to f32
+        let data = vec![
+            (
+                Matrix::from(vec![0.1, 0.2, 0.3, 0.4]),
+                Matrix::from(vec![1.0]),
+            ),
+            (
+                Matrix::from(vec![0.5, 0.6, 0.7, 0.8]),
+                Matrix::from(vec![0.0]),
+            ),
+        ];
+        CacheWriter::new(CacheType::F32)
+            .with_shapes(DenseShape::new(2, 2, 1), DenseShape::one_d(1))
+            .write(&data, path)
+            .unwrap();
+        let cache = MappedDataset::open(path).unwrap();
+        assert_eq!(cache.header().input_shape.y, 2);
+        assert_eq!(cache.get(1).0.get(2), 0.7f32 as f64);
+        assert_eq!((cache.get(1).0.h, cache.get(1).1.get(0)), (4, 0.0));
+        drop(cache);
+
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::write(path, &bytes[..bytes.len() - 4]).unwrap();
+        assert_eq!(
+            MappedDataset::open(path).err(),
+            Some(CacheError::Truncated(bytes.len(), bytes.len() - 4))
+        );
+        std::fs::write(
+            path,
+            b"not a cache file but long enough to hold a header of 64 bytes..",
+        )
+        .unwrap();
+        assert_eq!(MappedDataset::open(path).err(), Some(CacheError::BadMagic));
+
+        // input shape of u32::MAX in every dimension
+        let mut corrupt = bytes.clone();
+        corrupt[24..36].fill(0xFF);
+        std::fs::write(path, &corrupt).unwrap();
+        assert_eq!(
+            MappedDataset::open(path).err(),
+            Some(CacheError::ShapeOverflow)
+        );
+
+        let data = vec![
+            (Matrix::from(vec![0.0, 1.0]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![0.0]), Matrix::from(vec![1.0])),
+        ];
+        assert_eq!(
+            CacheWriter::new(CacheType::F64).write(&data, path).err(),
+            Some(CacheError::BadSample(1))
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    // a PNG of the given rows, each starting with its filter type
+    fn png(
+        width: u32,
+        height: u32,
+        color_type: u8,
+        depth: u8,
+        rows: &[u8],
+        palette: &[u8],
+    ) -> Vec<u8> {
+        let chunk = |kind: &[u8], data: &[u8]| {
+            let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+            chunk.extend_from_slice(kind);
+            chunk.extend_from_slice(data);
+            chunk.extend_from_slice(&[0; 4]);
+            chunk
+        };
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        bytes.extend(chunk(b"IHDR", &header));
+        if !palette.is_empty() {
+            bytes.extend(chunk(b"PLTE", palette));
+        }
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(rows, 6);
+        bytes.extend(chunk(b"IDAT", &compressed));
+        bytes.extend(chunk(b"IEND", &[]));
+        bytes
+    }
+
+    // a 24 bits BMP of 2x2 pixels: red, green on the top row, blue, white
+    // on the bottom one
+    fn bmp() -> Vec<u8> {
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&70u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&54u32.to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&2i32.to_le_bytes());
+        bytes.extend_from_slice(&2i32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&24u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 24]);
+        // bottom row first, in blue, green, red order, padded to 8 bytes
+        bytes.extend_from_slice(&[255, 0, 0, 255, 255, 255, 0, 0]);
+        bytes.extend_from_slice(&[0, 0, 255, 0, 255, 0, 0, 0]);
+        bytes
+    }
+
+    fn pixels(image: &Image) -> Vec<u8> {
+        image
+            .values
+            .iter()
+            .map(|v| (v * 255.0).round() as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_image_decoding() {
+        let binary = [b"P5\n# a comment\n3 1\n255\n".to_vec(), vec![0, 128, 255]].concat();
+        let text = b"P2 3 1 255 0 128\n255".to_vec();
+        for bytes in [binary, text] {
+            let image = Image::decode(&bytes).unwrap();
+            assert_eq!((image.width, image.height, image.channels), (3, 1, 1));
+            assert_eq!(pixels(&image), vec![0, 128, 255]);
+        }
+        let wide = [b"P6 1 1 65535\n".to_vec(), vec![255, 255, 0, 0, 128, 0]].concat();
+        let image = Image::decode(&wide).unwrap();
+        assert_eq!(image.channels, 3);
+        assert_eq!(pixels(&image), vec![255, 0, 128]);
+
+        let image = Image::decode(&bmp()).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (2, 2, 3));
+        assert_eq!(
+            pixels(&image),
+            vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]
+        );
+
+        // rows filtered with sub, up, average and paeth
+        let rows = [
+            vec![1, 10, 20, 30, 5, 5, 5],
+            vec![2, 1, 1, 1, 0, 0, 0],
+            vec![3, 0, 0, 0, 0, 0, 0],
+            vec![4, 0, 0, 0, 1, 2, 3],
+        ]
+        .concat();
+        let image = Image::decode(&png(2, 4, 2, 8, &rows, &[])).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (2, 4, 3));
+        assert_eq!(
+            pixels(&image),
+            vec![
+                10, 20, 30, 15, 25, 35, 11, 21, 31, 15, 25, 35, 5, 10, 15, 10, 17, 25, 5, 10, 15,
+                11, 19, 28
+            ]
+        );
+
+        // 4 bits of gray, then 2 bits indices of a palette
+        let image = Image::decode(&png(3, 1, 0, 4, &[0, 0x0F, 0x80], &[])).unwrap();
+        assert_eq!(pixels(&image), vec![0, 255, 136]);
+        let palette = [255, 0, 0, 0, 0, 255];
+        let image = Image::decode(&png(2, 1, 3, 2, &[0, 0b0100_0000], &palette)).unwrap();
+        assert_eq!(pixels(&image), vec![0, 0, 255, 255, 0, 0]);
+
+        assert_eq!(Image::decode(b"GIF89a"), Err(ImageError::UnknownFormat));
+        assert_eq!(
+            Image::decode(&png(2, 4, 2, 8, &rows[..20], &[])),
+            Err(ImageError::Truncated)
+        );
+    }
+
+    // a 32 bits BMP of one orange pixel stored with bit fields
+    fn bit_fields_bmp(masks: [u32; 3]) -> Vec<u8> {
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&70u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&66u32.to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&1i32.to_le_bytes());
+        bytes.extend_from_slice(&1i32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&32u16.to_le_bytes());
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 20]);
+        for mask in masks {
+            bytes.extend_from_slice(&mask.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0, 128, 255, 255]);
+        bytes
+    }
+
+    #[test]
+    fn test_image_edge_cases() {
+        for bytes in [b"P2 0 3 255\n".to_vec(), png(0, 2, 0, 8, &[], &[])] {
+            assert!(matches!(Image::decode(&bytes), Err(ImageError::Invalid(_))));
+        }
+
+        let image = Image::decode(&bit_fields_bmp([0xFF0000, 0xFF00, 0xFF])).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (1, 1, 3));
+        assert_eq!(pixels(&image), vec![255, 128, 0]);
+        assert!(matches!(
+            Image::decode(&bit_fields_bmp([0xFF, 0xFF00, 0xFF0000])),
+            Err(ImageError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_image_conversions() {
+        let image = Image::decode(&bmp()).unwrap();
+        let gray = image.to_grayscale();
+        assert_eq!(gray.channels, 1);
+        assert_eq!(pixels(&gray), vec![76, 150, 29, 255]);
+        assert_eq!(gray.to_rgb().values.len(), 12);
+
+        let small = gray.resize(1, 1);
+        assert!((small.values[0] - gray.values.iter().sum::<f64>() / 4.0).abs() < 1E-9);
+        let large = gray.resize(4, 4);
+        assert_eq!(large.values.len(), 16);
+        assert_eq!(
+            (large.values[0], large.values[15]),
+            (gray.values[0], gray.values[3])
+        );
+        assert_eq!((large.to_matrix().w, large.to_matrix().h), (1, 16));
+    }
+
+    #[test]
+    fn test_image_folder() {
+        let root = std::env::temp_dir().join(format!("bricks_images_{}", std::process::id()));
+        for class in ["dog", "cat", "empty"] {
+            std::fs::create_dir_all(root.join(class)).unwrap();
+        }
+        std::fs::write(root.join("cat").join("a.PGM"), b"P2 2 2 4 0 1 2 4").unwrap();
+        std::fs::write(root.join("dog").join("b.bmp"), bmp()).unwrap();
+        std::fs::write(
+            root.join("dog").join("c.png"),
+            png(1, 1, 0, 8, &[0, 255], &[]),
+        )
+        .unwrap();
+        std::fs::write(root.join("dog").join("notes.txt"), b"not an image").unwrap();
+        std::fs::write(root.join("readme.txt"), b"not a class").unwrap();
+
+        let root_path = root.to_str().unwrap();
+        let data = ImageFolder::new(DenseShape::new(2, 2, 1))
+            .load(root_path)
+            .unwrap();
+        assert_eq!(data.classes.names(), &["cat", "dog"]);
+        assert_eq!(data.len(), 3);
+        assert_eq!(
+            data.samples[0].0.to_string(),
+            Matrix::from(vec![0.0, 0.25, 0.5, 1.0]).to_string()
+        );
+        // resized from a single pixel
+        assert_eq!(
+            data.samples[2].0.to_string(),
+            Matrix::from(vec![1.0; 4]).to_string()
+        );
+        let labels = data
+            .samples
+            .iter()
+            .map(|(_, output)| data.classes.name_of(output));
+        assert_eq!(labels.collect::<Vec<_>>(), vec!["cat", "dog", "dog"]);
+        assert_eq!(data.paths[1], root.join("dog").join("b.bmp"));
+
+        let rgb = ImageFolder::new(DenseShape::new(2, 2, 3))
+            .with_normalize(false)
+            .load(root_path)
+            .unwrap();
+        assert_eq!(rgb.samples[1].0.len(), 12);
+        assert_eq!(rgb.samples[1].0.get(0), 255.0);
+
+        let path = root.join("classes.txt");
+        data.classes.save(path.to_str().unwrap());
+        let classes = ClassNames::load(path.to_str().unwrap());
+        assert_eq!(classes, data.classes);
+        assert_eq!(classes.index_of("dog"), Some(1));
+
+        std::fs::write(root.join("cat").join("d.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        assert!(matches!(
+            ImageFolder::new(DenseShape::new(2, 2, 1)).load(root_path),
+            Err(ImageError::InFile(_, _))
+        ));
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            ImageFolder::new(DenseShape::new(2, 2, 1))
+                .load(root_path)
+                .err(),
+            Some(ImageError::NoClasses)
+        );
+    }
+}
@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod distributed_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::data::load_data;
+    use bricks::distributed::{
+        read_message, write_message, Message, ParameterServer, UpdateMode, Worker,
+    };
+    use bricks::losses::Loss;
+    use bricks::maths::Matrix;
+    use bricks::metrics::{Accuracy, Metric};
+    use bricks::networks::{DenseNetwork, Gradients, Network};
+    use bricks::shapes::DenseShape;
+    use std::io::Cursor;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    fn xor_network() -> DenseNetwork {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(8),
+            DenseShape::one_d(1),
+        ];
+        DenseNetwork::new(activations, Loss::MeanSquaredError, shape)
+    }
+
+    fn accuracy(network: &mut DenseNetwork, data: &[(Matrix, Matrix)]) -> f64 {
+        let mut accuracy = Accuracy::new();
+        for (input, expected) in data {
+            network.feed_forward(input);
+            accuracy.update(&network.value(), expected);
+        }
+        accuracy.result()
+    }
+
+    // every worker trains on the whole xor data, one thread per process
+    fn train_xor(mode: UpdateMode, nb_workers: usize) -> (DenseNetwork, Vec<DenseNetwork>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let data = load_data("../examples/xor/training_data.dat");
+
+        let server = ParameterServer::new(xor_network(), 1.0, mode);
+        let server = thread::spawn(move || server.serve(&listener, nb_workers));
+
+        let workers = (0..nb_workers)
+            .map(|rank| {
+                let data = data.clone();
+                thread::spawn(move || {
+                    let mut worker = Worker::connect(address, xor_network(), data, 3000, 1)
+                        .with_seed(rank as u64);
+                    let history = worker.train();
+                    assert_eq!(history.len(), 3000);
+                    worker.release_network()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let workers = workers.into_iter().map(|w| w.join().unwrap()).collect();
+        (server.join().unwrap(), workers)
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let mut weights = Matrix::new(2, 3);
+        weights.set(4, -1.5);
+        let gradients = Gradients {
+            weights: vec![weights],
+            biases: vec![Matrix::from(vec![0.25, f64::NAN])],
+        };
+        let mut buffer = vec![];
+        write_message(&mut buffer, &Message::Pull).unwrap();
+        let push = Message::Push {
+            version: 7,
+            nb_samples: 32,
+            gradients,
+        };
+        write_message(&mut buffer, &push).unwrap();
+        write_message(&mut buffer, &Message::Done).unwrap();
+
+        let mut stream = Cursor::new(buffer);
+        assert!(matches!(read_message(&mut stream).unwrap(), Message::Pull));
+        match read_message(&mut stream).unwrap() {
+            Message::Push {
+                version,
+                nb_samples,
+                gradients,
+            } => {
+                assert_eq!((version, nb_samples), (7, 32));
+                assert_eq!((gradients.weights[0].w, gradients.weights[0].h), (2, 3));
+                assert_eq!(gradients.weights[0].get(4), -1.5);
+                assert_eq!(gradients.biases[0].get(0), 0.25);
+                assert!(gradients.biases[0].get(1).is_nan());
+            }
+            _ => panic!("Expected a push"),
+        }
+        assert!(matches!(read_message(&mut stream).unwrap(), Message::Done));
+        assert!(read_message(&mut stream).is_err());
+    }
+
+    #[test]
+    fn test_truncated_message() {
+        let mut buffer = vec![];
+        write_message(&mut buffer, &Message::Pull).unwrap();
+        // announces a longer body than the one sent
+        buffer[3] = 9;
+        assert!(read_message(&mut Cursor::new(buffer)).is_err());
+
+        let unknown_tag = vec![0, 0, 0, 1, 42];
+        assert!(read_message(&mut Cursor::new(unknown_tag)).is_err());
+    }
+
+    #[test]
+    fn test_malformed_push() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = ParameterServer::new(xor_network(), 1.0, UpdateMode::Synchronous);
+        let server = thread::spawn(move || server.serve(&listener, 2));
+
+        // gradients of a single layer, for a network of two
+        let mut stream = TcpStream::connect(address).unwrap();
+        let push = Message::Push {
+            version: 0,
+            nb_samples: 1,
+            gradients: Gradients {
+                weights: vec![Matrix::new(2, 8)],
+                biases: vec![Matrix::new(1, 8)],
+            },
+        };
+        write_message(&mut stream, &push).unwrap();
+        // the server drops the connection instead of replying
+        assert!(read_message(&mut stream).is_err());
+
+        // and keeps serving the other workers
+        let data = load_data("../examples/xor/training_data.dat");
+        let mut worker = Worker::connect(address, xor_network(), data, 10, 1);
+        assert_eq!(worker.train().len(), 10);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_synchronous_training() {
+        let (mut network, workers) = train_xor(UpdateMode::Synchronous, 2);
+        let data = load_data("../examples/xor/training_data.dat");
+        assert_eq!(accuracy(&mut network, &data), 1.0);
+
+        // every step waits for all the workers, which end up with the final
+        // parameters of the server
+        for worker in workers {
+            assert_eq!(
+                worker.weights()[0].to_string(),
+                network.weights()[0].to_string()
+            );
+            assert_eq!(
+                worker.biases()[1].to_string(),
+                network.biases()[1].to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_bounded_staleness_training() {
+        let (mut network, _) = train_xor(UpdateMode::BoundedStaleness(2), 3);
+        let data = load_data("../examples/xor/training_data.dat");
+        assert_eq!(accuracy(&mut network, &data), 1.0);
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod hogwild_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::data::load_data;
+    use bricks::losses::Loss;
+    use bricks::maths::Matrix;
+    use bricks::networks::{DenseNetwork, Regularization};
+    use bricks::sessions::{DenseSession, NonFiniteSource, Session, TrainingStrategy};
+    use bricks::shapes::DenseShape;
+
+    #[test]
+    fn test_hogwild_xor() {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(8),
+            DenseShape::one_d(1),
+        ];
+        let network = DenseNetwork::new(activations, Loss::MeanSquaredError, shape);
+        let data = load_data("../examples/xor/training_data.dat");
+
+        let mut session = DenseSession::new(
+            network,
+            1.0,
+            data.clone(),
+            data,
+            5000,
+            Some(0.01),
+            false,
+            None,
+        )
+        .with_strategy(TrainingStrategy::Hogwild(4));
+        let history = session.fit();
+
+        assert!(session.divergence().is_none());
+        assert!(history.test["loss"] < 0.05);
+    }
+
+    #[test]
+    fn test_hogwild_digit_counter() {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Softmax];
+        let shape = vec![
+            DenseShape::one_d(4),
+            DenseShape::one_d(64),
+            DenseShape::one_d(16),
+        ];
+        let loss = Loss::CategoricalCrossEntropy(0.0);
+        let network = DenseNetwork::new(activations, loss, shape);
+        let data = load_data("../examples/digit_counter/training_data.dat");
+
+        let mut session = DenseSession::new(
+            network,
+            0.5,
+            data.clone(),
+            data,
+            2000,
+            Some(0.05),
+            false,
+            None,
+        )
+        .with_strategy(TrainingStrategy::Hogwild(4));
+        let history = session.fit();
+
+        assert!(session.divergence().is_none());
+        assert!(history.test["loss"] < 0.1);
+        assert!(history.len() < 2000);
+    }
+
+    fn regularized_network() -> DenseNetwork {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(3),
+            DenseShape::one_d(1),
+        ];
+        DenseNetwork::new(activations, Loss::MeanSquaredError, shape)
+            .with_regularization(Regularization::elastic_net(1E-3, 1E-2))
+            .with_weight_decay(1E-2)
+    }
+
+    #[test]
+    fn test_single_thread_hogwild_matches_online_training() {
+        let network = regularized_network();
+        let train = |strategy| {
+            let mut session = DenseSession::new(
+                network.clone(),
+                0.5,
+                load_data("../examples/xor/training_data.dat"),
+                vec![],
+                3,
+                None,
+                false,
+                None,
+            )
+            .with_seed(4)
+            .with_strategy(strategy);
+            session.train();
+            session.release_network()
+        };
+
+        let hogwild = train(TrainingStrategy::Hogwild(1));
+        let online = train(TrainingStrategy::Synchronous);
+        for l in 0..online.weights().len() {
+            let weights = &hogwild.weights()[l] - &online.weights()[l];
+            let biases = &hogwild.biases()[l] - &online.biases()[l];
+            assert!(weights.powi(2).sum() < 1E-20);
+            assert!(biases.powi(2).sum() < 1E-20);
+        }
+    }
+
+    #[test]
+    fn test_hogwild_non_finite_gradients() {
+        // the infinite input saturates the hidden layer, the loss staying
+        // finite while its gradients are not
+        let data = vec![(
+            Matrix::from(vec![f64::INFINITY, 0.0]),
+            Matrix::from(vec![1.0]),
+        )];
+        let mut session = DenseSession::new(
+            regularized_network(),
+            0.5,
+            data,
+            vec![],
+            3,
+            None,
+            false,
+            None,
+        )
+        .with_strategy(TrainingStrategy::Hogwild(2));
+        session.train();
+
+        let divergence = session.divergence().expect("The gradients are not finite");
+        assert!(matches!(divergence.source, NonFiniteSource::Gradients(_)));
+        assert_eq!(session.network().first_non_finite_layer(), None);
+    }
+}
@@ -0,0 +1,242 @@
+#[cfg(test)]
+mod losses_tests {
+    use bricks::activations::{Activation, DenseActivation};
+    use bricks::losses::{sample_loss_weight, Loss, LossFunction};
+    use bricks::maths::Matrix;
+    use std::str::FromStr;
+
+    const STEP: f64 = 1E-6;
+    const TOLERANCE: f64 = 1E-4;
+
+    fn assert_gradient(loss: &Loss, values: &Matrix, expected: &Matrix) {
+        let analytic = loss.compute_differential_error(values, expected);
+
+        for i in 0..values.len() {
+            let mut plus = values.clone();
+            plus.set(i, values.get(i) + STEP);
+            let mut minus = values.clone();
+            minus.set(i, values.get(i) - STEP);
+
+            let numeric = (loss.compute_error(&plus, expected)
+                - loss.compute_error(&minus, expected))
+                / (2.0 * STEP);
+            assert!(
+                (numeric - analytic.get(i)).abs() < TOLERANCE,
+                "{}: component {} has gradient {} but expected {}",
+                loss,
+                i,
+                analytic.get(i),
+                numeric
+            );
+        }
+    }
+
+    #[test]
+    fn test_regression_losses_values() {
+        let values = Matrix::from(vec![1.0, 4.0]);
+        let expected = Matrix::from(vec![2.0, 2.0]);
+
+        assert_eq!(
+            Loss::MeanAbsoluteError.compute_error(&values, &expected),
+            3.0
+        );
+        assert_eq!(
+            Loss::Huber(1.0).compute_error(&values, &expected),
+            0.5 + 1.5
+        );
+        assert_eq!(
+            Loss::Quantile(0.9).compute_error(&values, &expected),
+            0.9 + 0.1 * 2.0
+        );
+        assert_eq!(
+            Loss::MeanAbsolutePercentageError.compute_error(&values, &expected),
+            150.0
+        );
+
+        let log_cosh = Loss::LogCosh.compute_error(&values, &expected);
+        assert!((log_cosh - (1.0f64.cosh().ln() + 2.0f64.cosh().ln())).abs() < 1E-12);
+        assert!(Loss::LogCosh
+            .compute_error(&Matrix::from(vec![1000.0]), &Matrix::from(vec![0.0]))
+            .is_finite());
+    }
+
+    #[test]
+    fn test_regression_losses_gradients() {
+        let values = Matrix::from(vec![0.3, -1.7, 2.5, 0.05]);
+        let expected = Matrix::from(vec![0.1, -0.4, 0.5, 1.0]);
+
+        for loss in [
+            Loss::MeanSquaredError,
+            Loss::MeanAbsoluteError,
+            Loss::Huber(1.0),
+            Loss::Huber(0.1),
+            Loss::LogCosh,
+            Loss::Quantile(0.25),
+            Loss::Quantile(0.9),
+            Loss::MeanAbsolutePercentageError,
+        ] {
+            assert_gradient(&loss, &values, &expected);
+        }
+    }
+
+    #[test]
+    fn test_loss_parameters_round_trip() {
+        for loss in [Loss::Huber(1.5), Loss::Quantile(0.9), Loss::LogCosh] {
+            assert_eq!(Loss::from_str(&loss.name()), Ok(loss));
+        }
+        assert_eq!(Loss::Huber(1.5).name(), "Huber 1.5");
+        assert!(Loss::from_str("Huber").is_err());
+    }
+
+    #[test]
+    fn test_classification_losses_values() {
+        let values = Matrix::from(vec![0.8, 0.2]);
+        let expected = Matrix::from(vec![1.0, 0.0]);
+        let bce = -(0.8f64.ln() + 0.8f64.ln());
+
+        assert!((Loss::CrossEntropy.compute_error(&values, &expected) - bce).abs() < 1E-12);
+        assert!(
+            (Loss::CategoricalCrossEntropy(0.0).compute_error(&values, &expected) + 0.8f64.ln())
+                .abs()
+                < 1E-12
+        );
+        assert!(
+            (Loss::Focal(0.0, 0.5).compute_error(&values, &expected) - 0.5 * bce).abs() < 1E-12
+        );
+        assert!(Loss::KullbackLeiblerDivergence.compute_error(&expected, &expected) < 1E-9);
+
+        let logits = Matrix::from(vec![2.0, -1.0]);
+        let probabilities = Matrix::from(vec![
+            1.0 / (1.0 + (-2.0f64).exp()),
+            1.0 / (1.0 + 1.0f64.exp()),
+        ]);
+        assert!(
+            (Loss::BinaryCrossEntropyWithLogits.compute_error(&logits, &expected)
+                - Loss::CrossEntropy.compute_error(&probabilities, &expected))
+            .abs()
+                < 1E-12
+        );
+        assert!(Loss::BinaryCrossEntropyWithLogits
+            .compute_error(&Matrix::from(vec![-1000.0]), &Matrix::from(vec![1.0]))
+            .is_finite());
+
+        assert_eq!(Loss::Hinge.compute_error(&logits, &expected), 0.0);
+        assert_eq!(
+            Loss::SquaredHinge.compute_error(&Matrix::from(vec![0.5]), &Matrix::from(vec![-1.0])),
+            2.25
+        );
+    }
+
+    #[test]
+    fn test_classification_losses_propagate_nan() {
+        let values = Matrix::from(vec![f64::NAN, 0.5]);
+        let expected = Matrix::from(vec![1.0, 0.0]);
+
+        for loss in [
+            Loss::CrossEntropy,
+            Loss::CategoricalCrossEntropy(0.0),
+            Loss::Focal(2.0, 0.25),
+        ] {
+            assert!(loss.compute_error(&values, &expected).is_nan());
+        }
+    }
+
+    #[test]
+    fn test_classification_losses_gradients() {
+        let probabilities = Matrix::from(vec![0.7, 0.2, 0.1]);
+        let soft_labels = Matrix::from(vec![0.6, 0.3, 0.1]);
+        let labels = Matrix::from(vec![1.0, 0.0, 0.0]);
+        let margins = Matrix::from(vec![0.4, -2.0, 1.3]);
+
+        for loss in [
+            Loss::CrossEntropy,
+            Loss::CategoricalCrossEntropy(0.0),
+            Loss::CategoricalCrossEntropy(0.1),
+            Loss::Focal(2.0, 0.25),
+            Loss::Focal(0.5, 0.75),
+            Loss::KullbackLeiblerDivergence,
+        ] {
+            assert_gradient(&loss, &probabilities, &labels);
+            assert_gradient(&loss, &probabilities, &soft_labels);
+        }
+
+        for loss in [
+            Loss::BinaryCrossEntropyWithLogits,
+            Loss::Hinge,
+            Loss::SquaredHinge,
+        ] {
+            assert_gradient(&loss, &margins, &labels);
+        }
+    }
+
+    #[test]
+    fn test_softmax_with_categorical_cross_entropy_gradient() {
+        let raw = Matrix::from(vec![1.0, 2.0, 0.5]);
+        let expected = Matrix::from(vec![0.0, 1.0, 0.0]);
+
+        let mut probabilities = raw.clone();
+        DenseActivation::Softmax.apply(&mut probabilities);
+        let gradient = Loss::CategoricalCrossEntropy(0.0)
+            .compute_differential_error(&probabilities, &expected);
+        let delta = DenseActivation::Softmax.backward(&raw, &gradient);
+
+        for i in 0..raw.len() {
+            let combined = probabilities.get(i) - expected.get(i);
+            assert!((delta.get(i) - combined).abs() < 1E-9);
+        }
+    }
+
+    #[test]
+    fn test_classification_loss_parameters_round_trip() {
+        for loss in [Loss::CategoricalCrossEntropy(0.1), Loss::Focal(2.0, 0.25)] {
+            assert_eq!(Loss::from_str(&loss.name()), Ok(loss));
+        }
+        assert_eq!(
+            Loss::from_str("CategoricalCrossEntropy"),
+            Ok(Loss::CategoricalCrossEntropy(0.0))
+        );
+    }
+
+    #[test]
+    fn test_weighted_losses() {
+        let values = Matrix::from(vec![0.3, 0.7]);
+        let expected = Matrix::from(vec![0.0, 1.0]);
+        let class_weights = Matrix::from(vec![1.0, 3.0]);
+        let loss = Loss::CategoricalCrossEntropy(0.0);
+
+        let error = loss.compute_error(&values, &expected);
+        let weighted =
+            loss.compute_weighted_error(&values, &expected, Some(&class_weights), Some(0.5));
+        assert!((weighted - 1.5 * error).abs() < 1E-12);
+
+        let gradient = loss.compute_differential_error(&values, &expected);
+        let weighted_gradient = loss.compute_weighted_differential_error(
+            &values,
+            &expected,
+            Some(&class_weights),
+            None,
+        );
+        for i in 0..values.len() {
+            assert!((weighted_gradient.get(i) - 3.0 * gradient.get(i)).abs() < 1E-12);
+        }
+
+        assert_eq!(
+            loss.compute_weighted_error(&values, &expected, None, None),
+            error
+        );
+    }
+
+    #[test]
+    fn test_binary_class_weights() {
+        let class_weights = Matrix::from(vec![0.25, 4.0]);
+
+        assert_eq!(
+            sample_loss_weight(&Matrix::from(vec![1.0]), Some(&class_weights), None),
+            4.0
+        );
+        assert_eq!(
+            sample_loss_weight(&Matrix::from(vec![0.0]), Some(&class_weights), Some(2.0)),
+            0.5
+        );
+    }
+}
@@ -1,17 +1,195 @@
 #[cfg(test)]
 mod maths_tests {
-    use bricks::maths::Matrix;
+    use bricks::maths::{npz_bytes, parse_npz, Matrix, NpyError, NpyType};
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_matrix_add() {
         let mat1: Matrix = Matrix::reshape(vec![1.0, 2.0, 3.0, 4.0], 2, 2);
         let mat2: Matrix = Matrix::reshape(vec![1.0, 2.0, 3.0, 4.0], 2, 2);
 
-        let res = (&mat1 + &mat2);
+        let res = &mat1 + &mat2;
 
         assert_eq!(2.0, res.get(0));
         assert_eq!(4.0, res.get(1));
         assert_eq!(6.0, res.get(2));
         assert_eq!(8.0, res.get(3));
     }
+
+    // a .npy file of version 1.0 with the given header
+    fn npy(header: &str, values: &[u8]) -> Vec<u8> {
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(values);
+        bytes
+    }
+
+    // a zip archive of one entry compressed with deflate, as written by
+    // numpy.savez_compressed
+    fn deflated_npz(name: &str, data: &[u8]) -> Vec<u8> {
+        let compressed = miniz_oxide::deflate::compress_to_vec(data, 6);
+        let crc = {
+            let mut crc = !0u32;
+            for &byte in data {
+                crc ^= byte as u32;
+                for _ in 0..8 {
+                    crc = (crc >> 1) ^ (0xEDB88320 & (crc & 1).wrapping_neg());
+                }
+            }
+            !crc
+        };
+        let mut fields = vec![20, 0, 0, 0, 8, 0, 0, 0, 0x21, 0];
+        fields.extend_from_slice(&crc.to_le_bytes());
+        fields.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&[0, 0]);
+
+        let mut bytes = 0x04034b50u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&fields);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&compressed);
+        let directory_offset = bytes.len() as u32;
+        bytes.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        bytes.extend_from_slice(&[20, 0]);
+        bytes.extend_from_slice(&fields);
+        bytes.extend_from_slice(&[0; 10]);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+        let directory_length = bytes.len() as u32 - directory_offset;
+        bytes.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        bytes.extend_from_slice(&directory_length.to_le_bytes());
+        bytes.extend_from_slice(&directory_offset.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_npy_round_trip() {
+        let matrix = Matrix::reshape(vec![1.0, -2.5, 3.25, 0.1, 5.0, 6.0], 3, 2);
+        let bytes = matrix.to_npy(NpyType::F64);
+        // the values start on a 64 bytes boundary
+        assert_eq!((bytes.len() - 6 * 8) % 64, 0);
+        let read = Matrix::from_npy(&bytes).unwrap();
+        assert_eq!((read.w, read.h), (3, 2));
+        assert_eq!(read.to_string(), matrix.to_string());
+
+        let read = Matrix::from_npy(&matrix.to_npy(NpyType::F32)).unwrap();
+        assert_eq!((read.w, read.h), (3, 2));
+        assert_eq!(read.get(3), 0.1f32 as f64);
+        assert_eq!(read.get(1), -2.5);
+
+        let path = std::env::temp_dir().join(format!("bricks_npy_{}.npy", std::process::id()));
+        let path = path.to_str().unwrap();
+        matrix.save_npy(path);
+        assert_eq!(Matrix::load_npy(path).to_string(), matrix.to_string());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_npy_orders_and_types() {
+        // values 1 to 6 of a 2x3 array, column after column
+        let values = [1.0f32, 4.0, 2.0, 5.0, 3.0, 6.0]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect::<Vec<_>>();
+        let header = "{'descr': '>f4', 'fortran_order': True, 'shape': (2, 3), }\n";
+        let matrix = Matrix::from_npy(&npy(header, &values)).unwrap();
+        assert_eq!((matrix.w, matrix.h), (3, 2));
+        assert_eq!(
+            (0..6).map(|i| matrix.get(i)).collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+
+        // 1-D arrays are columns
+        let values = [-3i64, 7]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (2,), }\n";
+        let matrix = Matrix::from_npy(&npy(header, &values)).unwrap();
+        assert_eq!((matrix.w, matrix.h), (1, 2));
+        assert_eq!((matrix.get(0), matrix.get(1)), (-3.0, 7.0));
+
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (1, 1, 1), }\n";
+        assert_eq!(
+            Matrix::from_npy(&npy(header, &[0; 8])).err(),
+            Some(NpyError::UnsupportedShape(vec![1, 1, 1]))
+        );
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (2,), }\n";
+        assert_eq!(
+            Matrix::from_npy(&npy(header, &[0; 8])).err(),
+            Some(NpyError::Truncated)
+        );
+        let header = "{'descr': '<c16', 'fortran_order': False, 'shape': (1,), }\n";
+        assert!(matches!(
+            Matrix::from_npy(&npy(header, &[0; 16])),
+            Err(NpyError::UnsupportedType(_))
+        ));
+        assert_eq!(
+            Matrix::from_npy(b"PK\x03\x04").err(),
+            Some(NpyError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn test_npz_archives() {
+        let mut arrays = BTreeMap::new();
+        arrays.insert(
+            "X".to_string(),
+            Matrix::reshape(vec![1.0, 2.0, 3.0, 4.0], 2, 2),
+        );
+        arrays.insert("y".to_string(), Matrix::from(vec![0.0, 1.0]));
+        let bytes = npz_bytes(&arrays, NpyType::F64);
+        let read = parse_npz(&bytes).unwrap();
+        assert_eq!(read.keys().collect::<Vec<_>>(), vec!["X", "y"]);
+        assert_eq!(read["X"].to_string(), arrays["X"].to_string());
+        assert_eq!((read["y"].w, read["y"].h), (1, 2));
+
+        // a changed value fails the crc of its entry
+        let mut corrupted = bytes.clone();
+        let at = corrupted.len() / 4;
+        corrupted[at] ^= 0xFF;
+        assert!(matches!(
+            parse_npz(&corrupted),
+            Err(NpyError::BadArchive(_))
+        ));
+
+        let compressed = deflated_npz("weights.npy", &arrays["X"].to_npy(NpyType::F32));
+        let read = parse_npz(&compressed).unwrap();
+        assert_eq!(read["weights"].to_string(), arrays["X"].to_string());
+    }
+
+    #[test]
+    fn test_truncated_zip64_extra_field() {
+        // an entry with saturated sizes, the central directory being
+        // the end of the file
+        let mut directory = 0x02014b50u32.to_le_bytes().to_vec();
+        directory.extend_from_slice(&[0; 16]);
+        directory.extend_from_slice(&[0xFF; 8]);
+        directory.extend_from_slice(&[1, 0, 12, 0]);
+        directory.extend_from_slice(&[0; 14]);
+        directory.push(b'x');
+        // the zip64 field announces both sizes but only holds the first
+        directory.extend_from_slice(&[1, 0, 16, 0]);
+        directory.extend_from_slice(&1u64.to_le_bytes());
+
+        let mut bytes = 0x06054b50u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&22u32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&directory);
+        assert!(matches!(parse_npz(&bytes), Err(NpyError::BadArchive(_))));
+
+        // a zip64 local header offset at the end of the address space
+        let last = bytes.len() - 12;
+        bytes[last..last + 4].copy_from_slice(&[1, 0, 8, 0]);
+        bytes[22 + 20..22 + 28].copy_from_slice(&[0; 8]);
+        bytes[22 + 42..22 + 46].copy_from_slice(&[0xFF; 4]);
+        bytes[last + 4..].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(parse_npz(&bytes), Err(NpyError::BadArchive(_))));
+    }
 }
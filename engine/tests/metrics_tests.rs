@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod metrics_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::losses::{Loss, LossFunction};
+    use bricks::maths::Matrix;
+    use bricks::metrics::{
+        Accuracy, Average, ConfusionMatrix, F1Score, LogLoss, MeanAbsoluteError, Metric, Precision,
+        RSquared, Recall, RocAuc, RootMeanSquaredError, TopKAccuracy,
+    };
+    use bricks::networks::DenseNetwork;
+    use bricks::sessions::{DenseSession, Session};
+    use bricks::shapes::DenseShape;
+
+    fn one_hot(class: usize, nb_classes: usize) -> Matrix {
+        let mut values = vec![0.0; nb_classes];
+        values[class] = 1.0;
+        Matrix::from(values)
+    }
+
+    // (expected, predicted) pairs with 4 correct predictions out of 6
+    fn feed(metric: &mut dyn Metric) {
+        let pairs = [(0, 0), (0, 1), (1, 1), (1, 1), (2, 1), (2, 2)];
+        for (expected, predicted) in pairs {
+            metric.update(&one_hot(predicted, 3), &one_hot(expected, 3));
+        }
+    }
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!(
+            (value - expected).abs() < 1E-12,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_confusion_matrix() {
+        let mut confusion_matrix = ConfusionMatrix::new(3);
+        let pairs = [(0, 0), (0, 1), (1, 1), (1, 1), (2, 1), (2, 2)];
+        for (expected, predicted) in pairs {
+            confusion_matrix.update(&one_hot(predicted, 3), &one_hot(expected, 3));
+        }
+
+        assert_eq!(
+            confusion_matrix.counts(),
+            [vec![1, 1, 0], vec![0, 2, 0], vec![0, 1, 1]]
+        );
+        assert_close(confusion_matrix.accuracy(), 4.0 / 6.0);
+        assert_close(confusion_matrix.precision(Average::Class(1)), 0.5);
+        assert_close(confusion_matrix.recall(Average::Class(0)), 0.5);
+        assert_close(confusion_matrix.f1(Average::Class(2)), 2.0 / 3.0);
+
+        confusion_matrix.reset();
+        assert_eq!(confusion_matrix.total(), 0);
+    }
+
+    #[test]
+    fn test_classification_metrics() {
+        let cases: Vec<(Box<dyn Metric>, f64)> = vec![
+            (Box::new(Accuracy::new()), 4.0 / 6.0),
+            (Box::new(Precision::new(3, Average::Macro)), 2.5 / 3.0),
+            (Box::new(Precision::new(3, Average::Micro)), 4.0 / 6.0),
+            (Box::new(Recall::new(3, Average::Macro)), 2.0 / 3.0),
+            (Box::new(Recall::new(3, Average::Micro)), 4.0 / 6.0),
+            (Box::new(F1Score::new(3, Average::Macro)), 2.0 / 3.0),
+            (Box::new(F1Score::new(3, Average::Micro)), 4.0 / 6.0),
+        ];
+
+        for (mut metric, expected) in cases {
+            feed(metric.as_mut());
+            assert_close(metric.result(), expected);
+            metric.reset();
+            assert_eq!(metric.result(), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_top_k_accuracy() {
+        let output = Matrix::from(vec![0.1, 0.5, 0.4]);
+        let expected = one_hot(2, 3);
+
+        let mut top_1 = TopKAccuracy::new(1);
+        let mut top_2 = TopKAccuracy::new(2);
+        top_1.update(&output, &expected);
+        top_2.update(&output, &expected);
+
+        assert_eq!(top_1.result(), 0.0);
+        assert_eq!(top_2.result(), 1.0);
+        assert_eq!(top_2.name(), "top_2_accuracy");
+
+        // a single output is thresholded like the accuracy of a binary problem
+        top_1.reset();
+        top_1.update(&Matrix::from(vec![0.7]), &Matrix::from(vec![1.0]));
+        top_1.update(&Matrix::from(vec![0.7]), &Matrix::from(vec![0.0]));
+        assert_eq!(top_1.result(), 0.5);
+    }
+
+    #[test]
+    fn test_roc_auc() {
+        let mut roc_auc = RocAuc::new();
+        let samples = [(0.8, 1.0), (0.4, 1.0), (0.5, 0.0), (0.1, 0.0)];
+        for (score, label) in samples {
+            roc_auc.update(&Matrix::from(vec![score]), &Matrix::from(vec![label]));
+        }
+        assert_close(roc_auc.result(), 0.75);
+
+        // a tie between a positive and a negative sample counts for half
+        roc_auc.reset();
+        for (score, label) in [(0.5, 1.0), (0.5, 0.0)] {
+            roc_auc.update(&Matrix::from(vec![score]), &Matrix::from(vec![label]));
+        }
+        assert_close(roc_auc.result(), 0.5);
+    }
+
+    #[test]
+    fn test_log_loss() {
+        let mut log_loss = LogLoss::new();
+        log_loss.update(&Matrix::from(vec![0.8]), &Matrix::from(vec![1.0]));
+        log_loss.update(&Matrix::from(vec![0.4]), &Matrix::from(vec![0.0]));
+        assert_close(log_loss.result(), -(0.8f64.ln() + 0.6f64.ln()) / 2.0);
+
+        let mut log_loss = LogLoss::new();
+        log_loss.update(&Matrix::from(vec![0.2, 0.7, 0.1]), &one_hot(1, 3));
+        assert_close(log_loss.result(), -(0.7f64.ln()));
+
+        // a confident mistake is clamped like in the training loss
+        let (output, expected) = (Matrix::from(vec![0.0]), Matrix::from(vec![1.0]));
+        let mut log_loss = LogLoss::new();
+        log_loss.update(&output, &expected);
+        assert_eq!(
+            log_loss.result(),
+            Loss::CrossEntropy.compute_error(&output, &expected)
+        );
+    }
+
+    #[test]
+    fn test_regression_metrics() {
+        let mut mae = MeanAbsoluteError::new();
+        let mut rmse = RootMeanSquaredError::new();
+        let mut r2 = RSquared::new();
+
+        for (output, expected) in [(1.0, 1.0), (2.0, 2.0), (4.0, 3.0)] {
+            let (output, expected) = (Matrix::from(vec![output]), Matrix::from(vec![expected]));
+            mae.update(&output, &expected);
+            rmse.update(&output, &expected);
+            r2.update(&output, &expected);
+        }
+
+        assert_close(mae.result(), 1.0 / 3.0);
+        assert_close(rmse.result(), (1.0f64 / 3.0).sqrt());
+        assert_close(r2.result(), 0.5);
+
+        // each output gets its own mean, far from zero for the first one:
+        // a constant prediction scores 0 on it and the exact second output 1
+        let mut r2 = RSquared::new();
+        for (y, z) in [(0.0, 0.0), (1.0, 100.0), (2.0, 200.0)] {
+            let expected = Matrix::from(vec![1E9 + y, z]);
+            r2.update(&Matrix::from(vec![1E9 + 1.0, z]), &expected);
+        }
+        assert_close(r2.result(), 0.5);
+    }
+
+    #[test]
+    fn test_session_reports_metrics() {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(3),
+            DenseShape::one_d(1),
+        ];
+        let network = DenseNetwork::new(activations, Loss::CrossEntropy, shape);
+        let data = vec![
+            (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![0.0, 1.0]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![1.0, 1.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![0.0, 0.0]), Matrix::from(vec![0.0])),
+        ];
+
+        let mut session = DenseSession::new(
+            network,
+            0.1,
+            data.clone(),
+            data.clone(),
+            3,
+            None,
+            false,
+            None,
+        )
+        .with_validation_data(data)
+        .with_metrics(vec![
+            Box::new(Accuracy::new()),
+            Box::new(RocAuc::new()),
+            Box::new(TopKAccuracy::new(1)),
+        ]);
+        let history = session.fit();
+
+        assert_eq!(history.len(), 3);
+        for record in &history.epochs {
+            for name in ["accuracy", "roc_auc", "val_accuracy", "val_roc_auc"] {
+                assert!((0.0..=1.0).contains(&record.logs[name]));
+            }
+        }
+        let accuracy = history.test["accuracy"];
+        assert!([0.0, 0.25, 0.5, 0.75, 1.0].contains(&accuracy));
+        assert_eq!(history.test["top_1_accuracy"], accuracy);
+        assert_eq!(&history.test, session.test_metrics());
+        assert!(session.test_confusion_matrix().is_none());
+    }
+
+    #[test]
+    fn test_session_confusion_matrix() {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Softmax];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(4),
+            DenseShape::one_d(3),
+        ];
+        let network = DenseNetwork::new(activations, Loss::CrossEntropy, shape);
+        let data = (0..6)
+            .map(|i| (Matrix::from(vec![i as f64 / 6.0, 1.0]), one_hot(i % 3, 3)))
+            .collect::<Vec<_>>();
+
+        let mut session = DenseSession::new(network, 0.1, data.clone(), data, 2, None, false, None)
+            .with_metrics(vec![Box::new(ConfusionMatrix::new(3))]);
+        let history = session.fit();
+
+        let confusion_matrix = session.test_confusion_matrix().unwrap();
+        assert_eq!(confusion_matrix.total(), 6);
+        assert_eq!(
+            history.test["confusion_matrix_accuracy"],
+            confusion_matrix.accuracy()
+        );
+        assert!(history.epochs[1]
+            .logs
+            .contains_key("confusion_matrix_accuracy"));
+    }
+}
@@ -1,11 +1,11 @@
 #[cfg(test)]
 mod network_tests {
-    use bricks::activations::dense_activation::DenseActivation;
-    use bricks::losses::losses::Loss;
-    use bricks::maths::matrix::Matrix;
-    use bricks::networks::dense_network::DenseNetwork;
-    use bricks::sessions::dense_session::DenseSession;
-    use bricks::shapes::dense_shape::DenseShape;
+    use bricks::activations::DenseActivation;
+    use bricks::losses::Loss;
+    use bricks::maths::{load_npz, Matrix};
+    use bricks::networks::DenseNetwork;
+    use bricks::sessions::{DenseSession, Session};
+    use bricks::shapes::DenseShape;
 
     #[test]
     fn test_xor_build() {
@@ -15,7 +15,7 @@ mod network_tests {
             DenseShape::new(3, 1, 1),
             DenseShape::new(1, 1, 1),
         ];
-        let mut model = DenseNetwork::new(activations, Loss::MeanSquaredError, shape, None);
+        let model = DenseNetwork::new(activations, Loss::MeanSquaredError, shape);
 
         let training_data = vec![
             (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![1.0])),
@@ -26,8 +26,71 @@ mod network_tests {
         let testing_data = training_data.clone();
 
         let mut session =
-            DenseSession::new(model, 1E-2, training_data, testing_data, 50000, Some(0.005));
+            DenseSession::new(model, 1E-2, training_data, testing_data, 50000, Some(0.005), false, None);
 
-        assert_eq!(session.fit() < 0.05, true);
+        session.train();
+        assert!(session.test() < 0.05);
+    }
+
+    #[test]
+    fn test_sample_weights() {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![DenseShape::one_d(2), DenseShape::one_d(3), DenseShape::one_d(1)];
+        let model = DenseNetwork::new(activations, Loss::CrossEntropy, shape);
+
+        // the same input is labelled both ways, only the first label counts
+        let training_data = vec![
+            (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![0.0])),
+        ];
+        let testing_data = vec![training_data[0].clone()];
+
+        let mut session =
+            DenseSession::new(model, 5E-1, training_data, testing_data, 500, None, false, None)
+                .with_sample_weights(vec![1.0, 0.0]);
+
+        session.train();
+        assert!(session.test() < 0.1);
+    }
+
+    #[test]
+    #[should_panic(expected = "One sample weight is expected per training sample")]
+    fn test_sample_weights_must_line_up() {
+        let activations = vec![DenseActivation::Sigmoid];
+        let shape = vec![DenseShape::one_d(1), DenseShape::one_d(1)];
+        let model = DenseNetwork::new(activations, Loss::CrossEntropy, shape);
+        let training_data = vec![(Matrix::from(vec![1.0]), Matrix::from(vec![1.0]))];
+
+        let _ = DenseSession::new(model, 1E-1, training_data, vec![], 1, None, false, None)
+            .with_sample_weights(vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_save_npz() {
+        let activations = vec![DenseActivation::Relu, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(4),
+            DenseShape::one_d(3),
+            DenseShape::one_d(2),
+        ];
+        let model = DenseNetwork::new(activations, Loss::MeanSquaredError, shape);
+
+        let path = std::env::temp_dir().join(format!("bricks_weights_{}.npz", std::process::id()));
+        let path = path.to_str().unwrap();
+        model.save_npz(path);
+        let arrays = load_npz(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            arrays.keys().collect::<Vec<_>>(),
+            vec!["biases_0", "biases_1", "weights_0", "weights_1"]
+        );
+        // numpy shapes (outputs, inputs) and (outputs, 1)
+        assert_eq!((arrays["weights_0"].h, arrays["weights_0"].w), (3, 4));
+        assert_eq!((arrays["biases_1"].h, arrays["biases_1"].w), (2, 1));
+        assert_eq!(
+            arrays["weights_1"].to_string(),
+            model.weights()[1].to_string()
+        );
     }
 }
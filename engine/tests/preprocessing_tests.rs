@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod preprocessing_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::losses::Loss;
+    use bricks::maths::Matrix;
+    use bricks::networks::{DenseNetwork, Network};
+    use bricks::preprocessing::{
+        build_preprocessor, inputs_matrix, load_model, save_model, ImputeStrategy, Imputer,
+        LabelEncoder, MinMaxScaler, OneHotEncoder, Pipeline, Preprocessor, RobustScaler,
+        StandardScaler,
+    };
+    use bricks::shapes::DenseShape;
+
+    // 4 samples of 2 features
+    fn features() -> Matrix {
+        Matrix::reshape(vec![1.0, 10.0, 2.0, 20.0, 3.0, 30.0, 10.0, 40.0], 2, 4)
+    }
+
+    fn values(matrix: &Matrix) -> Vec<f64> {
+        (0..matrix.len()).map(|i| matrix.get(i)).collect()
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1E-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_scalers() {
+        let data = features();
+
+        let mut standard = StandardScaler::new();
+        let scaled = standard.fit_transform(&data);
+        assert_close(standard.means(), &[4.0, 25.0]);
+        assert_close(&[values(&scaled).iter().step_by(2).sum::<f64>()], &[0.0]);
+        assert_close(
+            &values(&standard.inverse_transform(&scaled)),
+            &values(&data),
+        );
+
+        let mut min_max = MinMaxScaler::new().with_range(-1.0, 1.0);
+        let scaled = min_max.fit_transform(&data);
+        assert_close(
+            &values(&scaled),
+            &[
+                -1.0,
+                -1.0,
+                -7.0 / 9.0,
+                -1.0 / 3.0,
+                -5.0 / 9.0,
+                1.0 / 3.0,
+                1.0,
+                1.0,
+            ],
+        );
+        assert_close(&values(&min_max.inverse_transform(&scaled)), &values(&data));
+
+        // the outlier 10 does not move the median of 2.5 nor the quartiles
+        let mut robust = RobustScaler::new();
+        let scaled = robust.fit_transform(&data);
+        assert_close(robust.medians(), &[2.5, 25.0]);
+        assert_close(robust.ranges(), &[4.75 - 1.75, 32.5 - 17.5]);
+        assert_close(&values(&robust.inverse_transform(&scaled)), &values(&data));
+
+        // a sample of a network, as a column
+        let sample = robust.transform_sample(&Matrix::from(vec![4.0, 40.0]));
+        assert_eq!((sample.w, sample.h), (1, 2));
+        assert_close(&values(&sample), &[0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_encoders() {
+        let data = Matrix::reshape(vec![3.0, 0.0, 1.0, 1.0, 3.0, 1.0], 2, 3);
+        let mut one_hot = OneHotEncoder::new();
+        let encoded = one_hot.fit_transform(&data);
+        assert_eq!((encoded.w, encoded.h), (4, 3));
+        assert_eq!(
+            values(&encoded),
+            vec![0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0]
+        );
+        assert_eq!(values(&one_hot.inverse_transform(&encoded)), values(&data));
+
+        let mut labels = LabelEncoder::new();
+        let classes = labels.fit_transform(&Matrix::from(vec![7.0, -1.0, 7.0, 3.0]));
+        assert_eq!(labels.classes(), &[-1.0, 3.0, 7.0]);
+        assert_eq!(values(&classes), vec![2.0, 0.0, 2.0, 1.0]);
+        assert_eq!(
+            values(&labels.inverse_transform(&Matrix::from(vec![1.0, 0.0]))),
+            vec![3.0, -1.0]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown category 2 of feature 0")]
+    fn test_unknown_category() {
+        let mut one_hot = OneHotEncoder::new();
+        one_hot.fit(&Matrix::from(vec![0.0, 1.0]));
+        one_hot.transform(&Matrix::from(vec![2.0]));
+    }
+
+    #[test]
+    fn test_imputer() {
+        let data = Matrix::reshape(vec![1.0, f64::NAN, 2.0, 4.0, 9.0, 5.0, f64::NAN, 6.0], 2, 4);
+        let mut mean = Imputer::new(ImputeStrategy::Mean);
+        assert_close(
+            &values(&mean.fit_transform(&data)),
+            &[1.0, 5.0, 2.0, 4.0, 9.0, 5.0, 4.0, 6.0],
+        );
+
+        let mut median = Imputer::new(ImputeStrategy::Median);
+        median.fit(&data);
+        assert_close(median.values(), &[2.0, 5.0]);
+
+        // missing values are left out when fitting the scalers
+        let mut scaler = StandardScaler::new();
+        scaler.fit(&data);
+        assert_close(scaler.means(), &[4.0, 5.0]);
+    }
+
+    #[test]
+    fn test_pipeline_persistence() {
+        let data = vec![
+            (Matrix::from(vec![1.0, f64::NAN]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![2.0, 20.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![6.0, 40.0]), Matrix::from(vec![1.0])),
+        ];
+        let mut pipeline = Pipeline::new()
+            .with_step(Imputer::new(ImputeStrategy::Median))
+            .with_step(StandardScaler::new());
+        pipeline.fit_inputs(&data);
+        let transformed = pipeline.transform_inputs(&data);
+        assert_close(
+            &values(&inputs_matrix(&transformed)),
+            &values(&pipeline.transform(&inputs_matrix(&data))),
+        );
+
+        let path = std::env::temp_dir().join(format!("bricks_pipeline_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        pipeline.save(path);
+        let loaded = Pipeline::load(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.steps().len(), 2);
+        for (a, b) in pipeline.steps().iter().zip(loaded.steps()) {
+            assert_eq!(a.name(), b.name());
+        }
+        let sample = Matrix::from(vec![3.0, f64::NAN]);
+        assert_eq!(
+            loaded.transform_sample(&sample).to_string(),
+            pipeline.transform_sample(&sample).to_string()
+        );
+        assert_close(
+            &values(&loaded.inverse_transform_sample(&transformed[1].0)),
+            &[2.0, 20.0],
+        );
+
+        for preprocessor in [
+            Box::new(MinMaxScaler::new().with_range(-1.0, 2.0)) as Box<dyn Preprocessor>,
+            Box::new(RobustScaler::new()),
+            Box::new(OneHotEncoder::new()),
+            Box::new(LabelEncoder::new()),
+        ] {
+            let mut preprocessor = preprocessor;
+            preprocessor.fit(&features());
+            let built = build_preprocessor(&preprocessor.name()).unwrap();
+            assert_eq!(built.name(), preprocessor.name());
+        }
+        assert!(build_preprocessor("StandardScaler [1,2]").is_none());
+        // one parameter per feature in every vector
+        for descriptor in [
+            "StandardScaler [1,2] [1]",
+            "MinMaxScaler 0 1 [0] [1,2]",
+            "RobustScaler [] [1]",
+        ] {
+            assert!(build_preprocessor(descriptor).is_none());
+        }
+        assert!(build_preprocessor("StandardScaler [1,2] [1,1]").is_some());
+    }
+
+    #[test]
+    fn test_model_persistence() {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(3),
+            DenseShape::one_d(1),
+        ];
+        let mut network = DenseNetwork::new(activations, Loss::MeanSquaredError, shape);
+        let mut pipeline = Pipeline::new().with_step(StandardScaler::new());
+        pipeline.fit(&features());
+
+        let directory = std::env::temp_dir().join(format!("bricks_model_{}", std::process::id()));
+        let directory = directory.to_str().unwrap();
+        save_model(directory, &network, &pipeline);
+        let (mut loaded_network, loaded_pipeline) = load_model(directory);
+        std::fs::remove_dir_all(directory).unwrap();
+
+        let sample = Matrix::from(vec![3.0, 25.0]);
+        let input = pipeline.transform_sample(&sample);
+        assert_eq!(
+            loaded_pipeline.transform_sample(&sample).to_string(),
+            input.to_string()
+        );
+        network.feed_forward(&input);
+        loaded_network.feed_forward(&input);
+        assert_eq!(
+            loaded_network.value().to_string(),
+            network.value().to_string()
+        );
+    }
+}
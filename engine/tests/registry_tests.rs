@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod registry_tests {
+    use bricks::activations::{register_activation, Activation, DenseActivation};
+    use bricks::losses::{register_loss, LossFunction};
+    use bricks::maths::Matrix;
+    use bricks::networks::{DenseNetwork, Network};
+    use bricks::shapes::DenseShape;
+    use std::sync::Arc;
+
+    struct Identity;
+
+    impl Activation for Identity {
+        fn name(&self) -> String {
+            "Identity".to_string()
+        }
+
+        fn apply(&self, _mat: &mut Matrix) {}
+
+        fn derivative(&self, mat: &mut Matrix) {
+            mat.map(|_| 1.0);
+        }
+    }
+
+    struct ScaledAbsoluteError {
+        scale: f64,
+    }
+
+    impl LossFunction for ScaledAbsoluteError {
+        fn name(&self) -> String {
+            format!("ScaledAbsoluteError {}", self.scale)
+        }
+
+        fn compute_error(&self, values: &Matrix, expected: &Matrix) -> f64 {
+            let mut sum = 0.0;
+            for i in 0..values.len() {
+                sum += (values.get(i) - expected.get(i)).abs();
+            }
+            sum * self.scale
+        }
+
+        fn compute_differential_error(&self, values: &Matrix, expected: &Matrix) -> Matrix {
+            let mut diff = values - expected;
+            diff.map(f64::signum);
+            diff.multiply(self.scale)
+        }
+    }
+
+    fn build_identity(_descriptor: &str) -> Option<Box<dyn Activation>> {
+        Some(Box::new(Identity))
+    }
+
+    fn build_scaled_absolute_error(descriptor: &str) -> Option<Box<dyn LossFunction>> {
+        let scale = descriptor.split_whitespace().nth(1)?.parse::<f64>().ok()?;
+        Some(Box::new(ScaledAbsoluteError { scale }))
+    }
+
+    #[test]
+    fn test_custom_components_round_trip() {
+        register_activation("Identity", build_identity);
+        register_loss("ScaledAbsoluteError", build_scaled_absolute_error);
+
+        let activations: Vec<Arc<dyn Activation>> =
+            vec![DenseActivation::Sigmoid.into(), Arc::new(Identity)];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(3),
+            DenseShape::one_d(1),
+        ];
+        let loss: Arc<dyn LossFunction> = Arc::new(ScaledAbsoluteError { scale: 0.5 });
+        let mut network = DenseNetwork::new(activations, loss, shape);
+
+        let path = std::env::temp_dir().join("bricks_registry_round_trip.save");
+        let path = path.to_str().unwrap();
+        network.save_network(path);
+        let mut loaded = DenseNetwork::load_network(path);
+        std::fs::remove_file(path).unwrap();
+
+        let input = Matrix::from(vec![0.3, -0.7]);
+        network.feed_forward(&input);
+        loaded.feed_forward(&input);
+
+        assert_eq!(network.value().get(0), loaded.value().get(0));
+        assert_eq!(loaded.loss.name(), "ScaledAbsoluteError 0.5");
+    }
+
+    #[test]
+    #[should_panic(expected = "was it registered")]
+    fn test_unknown_activation_fails_to_load() {
+        let path = std::env::temp_dir().join("bricks_registry_unknown.save");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "1 1\nUnknownActivation\n0.5\n0.5\nMeanSquaredError").unwrap();
+        let result = std::panic::catch_unwind(|| DenseNetwork::load_network(path));
+        std::fs::remove_file(path).unwrap();
+        if let Err(err) = result {
+            std::panic::resume_unwind(err);
+        }
+    }
+}
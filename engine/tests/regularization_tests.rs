@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod regularization_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::losses::Loss;
+    use bricks::maths::Matrix;
+    use bricks::networks::{DenseNetwork, Gradients, Network, Regularization, SupervisedNetwork};
+    use bricks::shapes::DenseShape;
+
+    fn build_network() -> DenseNetwork {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(3),
+            DenseShape::one_d(4),
+            DenseShape::one_d(2),
+        ];
+        DenseNetwork::new(activations, Loss::MeanSquaredError, shape)
+    }
+
+    #[test]
+    fn test_elastic_net_penalty_and_gradient() {
+        let weights = Matrix::reshape(vec![1.0, -2.0, 0.0, 3.0], 2, 2);
+        let regularization = Regularization::elastic_net(0.1, 0.5);
+
+        let penalty = 0.1 * 6.0 + 0.25 * 14.0;
+        assert!((regularization.penalty(&weights) - penalty).abs() < 1E-12);
+
+        let gradient = regularization.gradient(&weights);
+        let expected = [0.1 + 0.5, -0.1 - 1.0, 0.0, 0.1 + 1.5];
+        for (i, value) in expected.iter().enumerate() {
+            assert!((gradient.get(i) - value).abs() < 1E-12);
+        }
+    }
+
+    #[test]
+    fn test_max_norm_constraint() {
+        let mut weights = Matrix::reshape(vec![3.0, 4.0, 0.3, 0.4], 2, 2);
+        Regularization::max_norm(1.0).constrain(&mut weights);
+
+        assert!((weights.get_at(0, 0) - 0.6).abs() < 1E-12);
+        assert!((weights.get_at(0, 1) - 0.8).abs() < 1E-12);
+        assert_eq!(weights.get_at(1, 0), 0.3);
+        assert_eq!(weights.get_at(1, 1), 0.4);
+    }
+
+    #[test]
+    fn test_regularization_penalty_and_weight_decay() {
+        let mut network = build_network()
+            .with_regularization(Regularization::l2(0.1))
+            .with_weight_decay(0.5);
+
+        let mut penalty = 0.0;
+        for weights in network.weights() {
+            penalty += 0.05 * weights.powi(2).sum();
+        }
+        assert!((network.regularization_penalty() - penalty).abs() < 1E-12);
+
+        // without any gradient from the loss, the weights only shrink by
+        // the l2 gradient and the decoupled weight decay
+        let before = network.weights().to_vec();
+        let gradients = Gradients::zeros_like(network.weights(), network.biases());
+        network.apply_gradients(&gradients, 0.1);
+        for (old, new) in before.iter().zip(network.weights()) {
+            for i in 0..old.len() {
+                let expected = old.get(i) * (1.0 - 0.1 * 0.5) - 0.1 * 0.1 * old.get(i);
+                assert!((new.get(i) - expected).abs() < 1E-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_regularization_is_saved() {
+        let network = build_network()
+            .with_layer_regularization(
+                1,
+                Regularization::elastic_net(1E-3, 1E-4).with_max_norm(2.0),
+            )
+            .with_weight_decay(1E-2);
+
+        let path = std::env::temp_dir().join("bricks_regularization.save");
+        let path = path.to_str().unwrap();
+        network.save_network(path);
+        let loaded = DenseNetwork::load_network(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.regularizations(), network.regularizations());
+        assert_eq!(loaded.regularizations()[0], Regularization::default());
+        assert_eq!(loaded.weight_decay(), 1E-2);
+    }
+}
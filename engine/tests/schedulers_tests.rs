@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod schedulers_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::losses::Loss;
+    use bricks::maths::Matrix;
+    use bricks::networks::DenseNetwork;
+    use bricks::schedulers::{
+        CosineAnnealingWarmRestarts, ExponentialDecay, LinearWarmup, LrScheduler, OneCycle,
+        ReduceOnPlateau, StepDecay,
+    };
+    use bricks::sessions::{DenseSession, Session};
+    use bricks::shapes::DenseShape;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1E-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_decays() {
+        let step = StepDecay::new(2, 0.5);
+        assert_close(step.learning_rate(1.0, 1, 0), 1.0);
+        assert_close(step.learning_rate(1.0, 2, 0), 0.5);
+        assert_close(step.learning_rate(1.0, 5, 0), 0.25);
+
+        let exponential = ExponentialDecay::new(0.9);
+        assert_close(exponential.learning_rate(2.0, 2, 0), 2.0 * 0.81);
+    }
+
+    #[test]
+    fn test_cosine_annealing_warm_restarts() {
+        let cosine = CosineAnnealingWarmRestarts::new(2, 2, 0.0);
+
+        assert_close(cosine.learning_rate(1.0, 0, 0), 1.0);
+        assert_close(cosine.learning_rate(1.0, 1, 0), 0.5);
+        // restart, the second cycle lasting 4 epochs
+        assert_close(cosine.learning_rate(1.0, 2, 0), 1.0);
+        assert_close(cosine.learning_rate(1.0, 4, 0), 0.5);
+        assert_close(cosine.learning_rate(1.0, 6, 0), 1.0);
+    }
+
+    #[test]
+    fn test_linear_warmup() {
+        let warmup = LinearWarmup::new(4).then(Box::new(ExponentialDecay::new(0.5)));
+
+        assert_close(warmup.learning_rate(1.0, 0, 0), 0.25);
+        assert_close(warmup.learning_rate(1.0, 0, 3), 1.0);
+        assert_close(warmup.learning_rate(1.0, 1, 4), 0.5);
+    }
+
+    #[test]
+    fn test_one_cycle() {
+        let one_cycle = OneCycle::new(100).with_div_factors(10.0, 100.0);
+
+        assert_close(one_cycle.learning_rate(1.0, 0, 0), 0.1);
+        assert_close(one_cycle.learning_rate(1.0, 0, 30), 1.0);
+        assert!(one_cycle.learning_rate(1.0, 0, 15) > 0.1);
+        assert!(one_cycle.learning_rate(1.0, 0, 15) < 1.0);
+        assert_close(one_cycle.learning_rate(1.0, 0, 100), 1E-3);
+    }
+
+    #[test]
+    fn test_reduce_on_plateau() {
+        let mut plateau = ReduceOnPlateau::new(0.5, 2).with_min_learning_rate(0.2);
+
+        plateau.on_epoch_end(0, 1.0);
+        plateau.on_epoch_end(1, 1.0);
+        assert_close(plateau.learning_rate(1.0, 2, 0), 1.0);
+        plateau.on_epoch_end(2, 1.0);
+        assert_close(plateau.learning_rate(1.0, 3, 0), 0.5);
+
+        plateau.on_epoch_end(3, 0.5);
+        plateau.on_epoch_end(4, 0.6);
+        assert_close(plateau.learning_rate(1.0, 5, 0), 0.5);
+        plateau.on_epoch_end(5, 0.6);
+        plateau.on_epoch_end(6, 0.6);
+        plateau.on_epoch_end(7, 0.6);
+        assert_close(plateau.learning_rate(1.0, 8, 0), 0.2);
+    }
+
+    #[test]
+    fn test_session_uses_scheduler() {
+        let activations = vec![DenseActivation::Sigmoid];
+        let shape = vec![DenseShape::one_d(2), DenseShape::one_d(1)];
+        let network = DenseNetwork::new(activations, Loss::MeanSquaredError, shape);
+        let training_data = vec![
+            (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![0.0, 1.0]), Matrix::from(vec![0.0])),
+        ];
+
+        let mut session =
+            DenseSession::new(network, 1.0, training_data, vec![], 3, None, false, Some(2))
+                .with_scheduler(Box::new(ExponentialDecay::new(0.5)));
+        session.train();
+
+        assert_close(session.current_learning_rate(), 0.25);
+    }
+}
@@ -0,0 +1,912 @@
+#[cfg(test)]
+mod session_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::augmentation::{Augmentation, GaussianNoise};
+    use bricks::data::{load_data, FileDataset};
+    use bricks::losses::Loss;
+    use bricks::maths::Matrix;
+    use bricks::metrics::{Accuracy, Metric};
+    use bricks::networks::{DenseNetwork, Gradients, Network};
+    use bricks::schedulers::ReduceOnPlateau;
+    use bricks::sessions::{
+        cross_validate, latest_checkpoint, Callback, DenseSession, EarlyStopping, EpochRecord,
+        GradientClipping, Logs, ModelCheckpoint, NonFinitePolicy, NonFiniteSource, Session,
+        ThresholdStopping, TrainingHistory,
+    };
+    use bricks::shapes::DenseShape;
+    use std::sync::{Arc, Mutex};
+
+    fn build_network() -> DenseNetwork {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(3),
+            DenseShape::one_d(1),
+        ];
+        DenseNetwork::new(activations, Loss::MeanSquaredError, shape)
+    }
+
+    fn xor_data() -> Vec<(Matrix, Matrix)> {
+        vec![
+            (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![0.0, 1.0]), Matrix::from(vec![1.0])),
+            (Matrix::from(vec![1.0, 1.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![0.0, 0.0]), Matrix::from(vec![0.0])),
+        ]
+    }
+
+    fn is_finite(network: &DenseNetwork) -> bool {
+        network.first_non_finite_layer().is_none()
+    }
+
+    #[test]
+    fn test_gradient_clipping() {
+        let mut gradients = Gradients {
+            weights: vec![Matrix::reshape(vec![3.0, -4.0, 0.5, 0.0], 2, 2)],
+            biases: vec![Matrix::from(vec![0.0, 0.0])],
+        };
+        assert_eq!(gradients.global_norm(), (25.25f64).sqrt());
+
+        let mut clipped = gradients.clone();
+        clipped.clip_by_value(1.0);
+        assert_eq!(clipped.weights[0].get(0), 1.0);
+        assert_eq!(clipped.weights[0].get(1), -1.0);
+        assert_eq!(clipped.weights[0].get(2), 0.5);
+
+        gradients.clip_by_global_norm(1.0);
+        assert!((gradients.global_norm() - 1.0).abs() < 1E-12);
+        assert!((gradients.weights[0].get(0) / gradients.weights[0].get(1) + 0.75).abs() < 1E-12);
+    }
+
+    // the losses on raw scores train a linear output, whose sign gives the
+    // class of the and of the inputs
+    #[test]
+    fn test_losses_on_linear_outputs() {
+        let data = vec![
+            (Matrix::from(vec![0.0, 0.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![0.0, 1.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![1.0, 1.0]), Matrix::from(vec![1.0])),
+        ];
+        let linear = DenseActivation::Linear.to_string();
+        assert_eq!(linear.parse(), Ok(DenseActivation::Linear));
+        for loss in [
+            Loss::BinaryCrossEntropyWithLogits,
+            Loss::Hinge,
+            Loss::SquaredHinge,
+        ] {
+            let shape = vec![DenseShape::one_d(2), DenseShape::one_d(1)];
+            let network = DenseNetwork::new(vec![DenseActivation::Linear], loss, shape);
+            let mut session =
+                DenseSession::new(network, 0.5, data.clone(), vec![], 500, None, false, None);
+            let losses = session.train().metric("loss");
+            assert!(losses[499] < 0.05, "{}: {}", loss, losses[499]);
+
+            let mut network = session.release_network();
+            for (input, expected) in &data {
+                network.feed_forward(input);
+                let score = network.value().get(0);
+                assert_eq!(score > 0.0, expected.get(0) == 1.0, "{}: {}", loss, score);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clipped_updates_are_bounded() {
+        let network = build_network();
+        let before = network.clone();
+        let training_data = vec![xor_data().remove(0)];
+
+        let mut session =
+            DenseSession::new(network, 1.0, training_data, vec![], 1, None, false, None)
+                .with_gradient_clipping(GradientClipping::GlobalNorm(1E-2));
+        session.train();
+        let after = session.release_network();
+
+        let mut squared_change = 0.0;
+        for l in 0..after.weights().len() {
+            squared_change += (&after.weights()[l] - &before.weights()[l]).powi(2).sum();
+            squared_change += (&after.biases()[l] - &before.biases()[l]).powi(2).sum();
+        }
+        assert!(squared_change.sqrt() <= 1E-2 + 1E-12);
+    }
+
+    #[test]
+    fn test_non_finite_loss_stops_training() {
+        let mut training_data = xor_data();
+        training_data.push((Matrix::from(vec![f64::NAN, 0.0]), Matrix::from(vec![1.0])));
+
+        let mut session = DenseSession::new(
+            build_network(),
+            1E-1,
+            training_data,
+            vec![],
+            10,
+            None,
+            false,
+            None,
+        );
+        session.train();
+
+        let divergence = session
+            .divergence()
+            .expect("The NaN input should stop training");
+        assert_eq!(divergence.epoch, 0);
+        assert_eq!(divergence.source, NonFiniteSource::Loss);
+        assert!(is_finite(&session.release_network()));
+    }
+
+    // number of samples seen since the last reset
+    struct SampleCount(usize);
+
+    impl Metric for SampleCount {
+        fn name(&self) -> String {
+            "samples".to_string()
+        }
+
+        fn update(&mut self, _output: &Matrix, _expected: &Matrix) {
+            self.0 += 1;
+        }
+
+        fn result(&self) -> f64 {
+            self.0 as f64
+        }
+
+        fn reset(&mut self) {
+            self.0 = 0;
+        }
+    }
+
+    #[test]
+    fn test_divergence_does_not_leak_into_test_metrics() {
+        let mut training_data = xor_data();
+        training_data.push((Matrix::from(vec![f64::NAN, 0.0]), Matrix::from(vec![1.0])));
+
+        let mut session = DenseSession::new(
+            build_network(),
+            1E-1,
+            training_data,
+            xor_data(),
+            10,
+            None,
+            false,
+            None,
+        )
+        .with_metrics(vec![Box::new(SampleCount(0))]);
+        let history = session.fit();
+
+        assert!(session.divergence().is_some());
+        assert_eq!(history.test["samples"], 4.0);
+    }
+
+    #[test]
+    fn test_non_finite_parameters_are_reported() {
+        let mut session = DenseSession::new(
+            build_network(),
+            f64::INFINITY,
+            xor_data(),
+            vec![],
+            10,
+            None,
+            false,
+            None,
+        );
+        session.train();
+
+        let divergence = session.divergence().unwrap();
+        assert!(matches!(divergence.source, NonFiniteSource::Parameters(_)));
+        assert_eq!(divergence.step, 1);
+        assert!(!is_finite(&session.release_network()));
+    }
+
+    #[test]
+    fn test_rollback_restores_last_good_weights() {
+        let network = build_network();
+        let before = network.clone();
+
+        let mut session = DenseSession::new(
+            network,
+            f64::INFINITY,
+            xor_data(),
+            vec![],
+            10,
+            None,
+            false,
+            None,
+        )
+        .with_non_finite_policy(NonFinitePolicy::Rollback);
+        session.train();
+        assert!(session.divergence().is_some());
+
+        let after = session.release_network();
+        assert!(is_finite(&after));
+        assert_eq!(
+            after.weights()[0].to_string(),
+            before.weights()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_ignore_policy_keeps_training() {
+        let mut session = DenseSession::new(
+            build_network(),
+            f64::INFINITY,
+            xor_data(),
+            vec![],
+            2,
+            None,
+            false,
+            None,
+        )
+        .with_non_finite_policy(NonFinitePolicy::Ignore);
+        session.train();
+
+        assert!(session.divergence().is_none());
+    }
+
+    struct Recorder {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Callback for Recorder {
+        fn on_train_begin(&mut self, _session: &mut DenseSession) {
+            self.events.lock().unwrap().push("train_begin".to_string());
+        }
+
+        fn on_epoch_begin(&mut self, _session: &mut DenseSession, epoch: usize) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("epoch_begin {}", epoch));
+        }
+
+        fn on_batch_begin(&mut self, _session: &mut DenseSession, batch: usize) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("batch_begin {}", batch));
+        }
+
+        fn on_batch_end(&mut self, _session: &mut DenseSession, batch: usize, logs: &Logs) {
+            let event = format!("batch_end {} {}", batch, logs["size"]);
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn on_epoch_end(&mut self, _session: &mut DenseSession, epoch: usize, logs: &Logs) {
+            assert!(logs.contains_key("loss"));
+            assert!(logs.contains_key("learning_rate"));
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("epoch_end {}", epoch));
+        }
+
+        fn on_train_end(&mut self, _session: &mut DenseSession, _logs: &Logs) {
+            self.events.lock().unwrap().push("train_end".to_string());
+        }
+    }
+
+    #[test]
+    fn test_callback_hooks_order() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorder = Recorder {
+            events: events.clone(),
+        };
+
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            2,
+            None,
+            false,
+            Some(3),
+        )
+        .with_callback(Box::new(recorder));
+        session.train();
+
+        let expected = vec![
+            "train_begin",
+            "epoch_begin 0",
+            "batch_begin 0",
+            "batch_end 0 3",
+            "batch_begin 1",
+            "batch_end 1 1",
+            "epoch_end 0",
+            "epoch_begin 1",
+            "batch_begin 0",
+            "batch_end 0 3",
+            "batch_begin 1",
+            "batch_end 1 1",
+            "epoch_end 1",
+            "train_end",
+        ];
+        assert_eq!(*events.lock().unwrap(), expected);
+    }
+
+    struct StopAfterBatches {
+        batches: usize,
+    }
+
+    impl Callback for StopAfterBatches {
+        fn on_batch_end(&mut self, session: &mut DenseSession, _batch: usize, _logs: &Logs) {
+            self.batches -= 1;
+            if self.batches == 0 {
+                session.stop_training();
+            }
+        }
+    }
+
+    #[test]
+    fn test_callback_stops_training() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorder = Recorder {
+            events: events.clone(),
+        };
+
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            10,
+            None,
+            false,
+            None,
+        )
+        .with_callback(Box::new(StopAfterBatches { batches: 6 }))
+        .with_callback(Box::new(recorder));
+        session.train();
+
+        let events = events.lock().unwrap();
+        let batches = events.iter().filter(|e| e.starts_with("batch_end")).count();
+        assert_eq!(batches, 6);
+        assert_eq!(events[events.len() - 2], "epoch_end 1");
+        assert_eq!(events[events.len() - 1], "train_end");
+    }
+
+    struct BatchLosses {
+        losses: Arc<Mutex<Vec<f64>>>,
+    }
+
+    impl Callback for BatchLosses {
+        fn on_batch_end(&mut self, _session: &mut DenseSession, _batch: usize, logs: &Logs) {
+            self.losses.lock().unwrap().push(logs["loss"]);
+        }
+    }
+
+    #[test]
+    fn test_stopped_epoch_loss() {
+        let losses = Arc::new(Mutex::new(vec![]));
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            10,
+            None,
+            false,
+            Some(2),
+        )
+        .with_callback(Box::new(BatchLosses {
+            losses: losses.clone(),
+        }))
+        .with_callback(Box::new(StopAfterBatches { batches: 1 }));
+        let history = session.train();
+
+        // the loss of the epoch is the mean over the only batch it went through
+        assert_eq!(history.len(), 1);
+        let batch_loss = losses.lock().unwrap()[0];
+        assert!((history.epochs[0].logs["loss"] - batch_loss).abs() < 1E-12);
+    }
+
+    #[test]
+    fn test_threshold_stopping() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorder = Recorder {
+            events: events.clone(),
+        };
+
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            10,
+            None,
+            false,
+            None,
+        )
+        .with_callback(Box::new(ThresholdStopping::new("loss", f64::INFINITY)))
+        .with_callback(Box::new(recorder));
+        session.train();
+
+        let events = events.lock().unwrap();
+        assert!(!events.contains(&"epoch_begin 1".to_string()));
+    }
+
+    struct NetworkRecorder {
+        records: Arc<Mutex<Vec<(f64, DenseNetwork)>>>,
+    }
+
+    impl Callback for NetworkRecorder {
+        fn on_epoch_end(&mut self, session: &mut DenseSession, _epoch: usize, logs: &Logs) {
+            let record = (logs["val_loss"], session.network().clone());
+            self.records.lock().unwrap().push(record);
+        }
+    }
+
+    #[test]
+    fn test_early_stopping_restores_best_weights() {
+        let records = Arc::new(Mutex::new(vec![]));
+        let recorder = NetworkRecorder {
+            records: records.clone(),
+        };
+        // learning xor can only make things worse on the flipped labels
+        let validation_data = xor_data()
+            .into_iter()
+            .map(|(input, output)| (input, Matrix::from(vec![1.0 - output.get(0)])))
+            .collect();
+
+        let mut session = DenseSession::new(
+            build_network(),
+            1.0,
+            xor_data(),
+            vec![],
+            1000,
+            None,
+            false,
+            None,
+        )
+        .with_validation_data(validation_data)
+        .with_callback(Box::new(recorder))
+        .with_callback(Box::new(EarlyStopping::new("val_loss", 3)));
+        session.train();
+        let network = session.release_network();
+
+        let records = records.lock().unwrap();
+        assert!(records.len() < 1000);
+        let mut best = 0;
+        for (epoch, (loss, _)) in records.iter().enumerate() {
+            if *loss < records[best].0 {
+                best = epoch;
+            }
+        }
+        assert_eq!(records.len(), best + 4);
+        let restored = &records[best].1;
+        for l in 0..network.weights().len() {
+            assert_eq!(
+                (&network.weights()[l] - &restored.weights()[l])
+                    .powi(2)
+                    .sum(),
+                0.0
+            );
+            assert_eq!(
+                (&network.biases()[l] - &restored.biases()[l]).powi(2).sum(),
+                0.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_early_stopping_min_delta() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorder = Recorder {
+            events: events.clone(),
+        };
+
+        // no epoch can improve the loss by more than infinity
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            100,
+            None,
+            false,
+            None,
+        )
+        .with_callback(Box::new(recorder))
+        .with_callback(Box::new(
+            EarlyStopping::new("loss", 2).with_min_delta(f64::INFINITY),
+        ));
+        session.train();
+
+        let events = events.lock().unwrap();
+        let epochs = events.iter().filter(|e| e.starts_with("epoch_end")).count();
+        assert_eq!(epochs, 3);
+    }
+
+    #[test]
+    fn test_early_stopping_falls_back_to_training_loss() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorder = Recorder {
+            events: events.clone(),
+        };
+
+        // without validation data val_loss is not tracked, loss stands for it
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            100,
+            None,
+            false,
+            None,
+        )
+        .with_callback(Box::new(recorder))
+        .with_callback(Box::new(
+            EarlyStopping::new("val_loss", 2).with_min_delta(f64::INFINITY),
+        ));
+        session.train();
+
+        let events = events.lock().unwrap();
+        let epochs = events.iter().filter(|e| e.starts_with("epoch_end")).count();
+        assert_eq!(epochs, 3);
+    }
+
+    #[test]
+    fn test_threshold_is_honoured() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorder = Recorder {
+            events: events.clone(),
+        };
+
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            100,
+            Some(f64::INFINITY),
+            false,
+            None,
+        )
+        .with_callback(Box::new(recorder));
+        session.train();
+
+        let events = events.lock().unwrap();
+        assert!(!events.contains(&"epoch_begin 1".to_string()));
+    }
+
+    #[test]
+    fn test_validation_split() {
+        let session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            vec![],
+            1,
+            None,
+            false,
+            None,
+        )
+        .with_sample_weights(vec![1.0; 4])
+        .with_validation_split(25);
+        assert_eq!(session.training_size(), 3);
+    }
+
+    struct StopAfterEpochs {
+        epochs: usize,
+    }
+
+    impl Callback for StopAfterEpochs {
+        fn on_epoch_end(&mut self, session: &mut DenseSession, epoch: usize, _logs: &Logs) {
+            if epoch + 1 == self.epochs {
+                session.stop_training();
+            }
+        }
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let directory =
+            std::env::temp_dir().join(format!("bricks_checkpoints_{}", std::process::id()));
+        let directory = directory.to_str().unwrap();
+        let network = build_network();
+        // the weights and policies are saved along with the checkpoint
+        let options = |session: DenseSession| {
+            session
+                .with_seed(42)
+                .with_class_weights(Matrix::from(vec![1.0, 2.0]))
+                .with_sample_weights(vec![0.5, 1.0, 1.5, 2.0])
+                .with_gradient_clipping(GradientClipping::GlobalNorm(0.5))
+                .with_non_finite_policy(NonFinitePolicy::Rollback)
+        };
+
+        let mut session = options(DenseSession::new(
+            network.clone(),
+            0.5,
+            xor_data(),
+            vec![],
+            6,
+            None,
+            false,
+            Some(2),
+        ))
+        .with_scheduler(Box::new(ReduceOnPlateau::new(0.5, 1)));
+        let history = session.train();
+        let expected = session.release_network();
+
+        let mut session = options(DenseSession::new(
+            network,
+            0.5,
+            xor_data(),
+            vec![],
+            6,
+            None,
+            false,
+            Some(2),
+        ))
+        .with_scheduler(Box::new(ReduceOnPlateau::new(0.5, 1)))
+        .with_callback(Box::new(ModelCheckpoint::new(directory).keep_last(2)))
+        .with_callback(Box::new(StopAfterEpochs { epochs: 3 }));
+        session.train();
+        assert_eq!(session.completed_epochs(), 3);
+
+        let mut checkpoints = std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        checkpoints.sort();
+        assert_eq!(checkpoints, vec!["checkpoint-000002", "checkpoint-000003"]);
+
+        let checkpoint = latest_checkpoint(directory).unwrap();
+        let mut session = DenseSession::resume(checkpoint.to_str().unwrap(), xor_data(), vec![])
+            .with_scheduler(Box::new(ReduceOnPlateau::new(0.5, 1)));
+        session.train();
+        std::fs::remove_dir_all(directory).unwrap();
+
+        assert_eq!(session.completed_epochs(), 6);
+        let resumed = session.history();
+        assert_eq!(resumed.len(), 6);
+        for epoch in 0..6 {
+            assert_eq!(resumed.epochs[epoch].epoch, epoch);
+            assert_eq!(resumed.epochs[epoch].logs, history.epochs[epoch].logs);
+        }
+        let network = session.release_network();
+        for l in 0..network.weights().len() {
+            assert_eq!(
+                network.weights()[l].to_string(),
+                expected.weights()[l].to_string()
+            );
+            assert_eq!(
+                network.biases()[l].to_string(),
+                expected.biases()[l].to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_history_export() {
+        let mut history = TrainingHistory::new();
+        history.epochs.push(EpochRecord {
+            epoch: 0,
+            wall_time: 0.5,
+            logs: Logs::from([
+                ("loss".to_string(), 1.0),
+                ("learning_rate".to_string(), 0.1),
+            ]),
+        });
+        history.epochs.push(EpochRecord {
+            epoch: 1,
+            wall_time: 0.25,
+            logs: Logs::from([
+                ("loss".to_string(), f64::NAN),
+                ("val_loss".to_string(), 2.0),
+            ]),
+        });
+        history.test.insert("loss".to_string(), 3.0);
+
+        assert_eq!(
+            history.to_csv(),
+            "epoch,wall_time,learning_rate,loss,val_loss\n0,0.5,0.1,1,\n1,0.25,,NaN,2\n"
+        );
+        assert_eq!(
+            history.to_json(),
+            "{\"epochs\":[{\"epoch\":0,\"wall_time\":0.5,\"learning_rate\":0.1,\"loss\":1.0},\
+             {\"epoch\":1,\"wall_time\":0.25,\"loss\":null,\"val_loss\":2.0}],\
+             \"test\":{\"loss\":3.0}}"
+        );
+        assert_eq!(history.metric("val_loss")[1], 2.0);
+        assert!(history.metric("val_loss")[0].is_nan());
+    }
+
+    #[test]
+    fn test_train_returns_history() {
+        let mut session = DenseSession::new(
+            build_network(),
+            0.1,
+            xor_data(),
+            xor_data(),
+            4,
+            None,
+            false,
+            None,
+        );
+        let history = session.train();
+        assert_eq!(history.len(), 4);
+        assert!(history.test.is_empty());
+        assert!(history.epochs.iter().all(|record| record.wall_time >= 0.0));
+
+        let history = session.fit();
+        assert_eq!(history.len(), 4);
+        assert!(history.test.contains_key("loss"));
+    }
+
+    #[test]
+    fn test_cross_validate() {
+        let mut data = xor_data();
+        data.extend(xor_data());
+        let mut nb_sessions = 0;
+
+        let results = cross_validate(
+            |training_data, testing_data| {
+                nb_sessions += 1;
+                assert_eq!((training_data.len(), testing_data.len()), (6, 2));
+                DenseSession::new(
+                    build_network(),
+                    0.1,
+                    training_data,
+                    testing_data,
+                    2,
+                    None,
+                    false,
+                    None,
+                )
+                .with_metrics(vec![Box::new(Accuracy::new())])
+            },
+            &data,
+            4,
+            true,
+        );
+
+        assert_eq!(nb_sessions, 4);
+        assert_eq!(results.folds.len(), 4);
+        let accuracies = results.values("accuracy");
+        let mean = accuracies.iter().sum::<f64>() / 4.0;
+        let variance = accuracies.iter().map(|a| (a - mean).powi(2)).sum::<f64>() / 4.0;
+        assert_eq!(results.mean("accuracy"), mean);
+        assert!((results.std("accuracy") - variance.sqrt()).abs() < 1E-12);
+        assert_eq!(
+            results.summary()["loss"],
+            (results.mean("loss"), results.std("loss"))
+        );
+    }
+
+    fn train_with_workers(
+        network: &DenseNetwork,
+        workers: Option<usize>,
+    ) -> (DenseNetwork, TrainingHistory) {
+        let data = (0..10)
+            .map(|i| {
+                let x = i as f64 / 10.0;
+                (
+                    Matrix::from(vec![x, 1.0 - x * x]),
+                    Matrix::from(vec![(i % 3 == 0) as usize as f64]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let sample_weights = (0..10).map(|i| 1.0 + i as f64 / 10.0).collect();
+
+        let mut session =
+            DenseSession::new(network.clone(), 0.5, data, vec![], 5, None, false, Some(4))
+                .with_seed(9)
+                .with_sample_weights(sample_weights)
+                .with_metrics(vec![Box::new(Accuracy::new())]);
+        if let Some(workers) = workers {
+            session = session.with_workers(workers);
+        }
+        let history = session.train();
+        (session.release_network(), history)
+    }
+
+    #[test]
+    fn test_data_parallel_matches_single_thread() {
+        let network = build_network();
+        let (expected, expected_history) = train_with_workers(&network, None);
+
+        for workers in [1, 3, 8] {
+            let (trained, history) = train_with_workers(&network, Some(workers));
+            for l in 0..trained.weights().len() {
+                assert_eq!(
+                    trained.weights()[l].to_string(),
+                    expected.weights()[l].to_string()
+                );
+                assert_eq!(
+                    trained.biases()[l].to_string(),
+                    expected.biases()[l].to_string()
+                );
+            }
+            for (record, expected) in history.epochs.iter().zip(&expected_history.epochs) {
+                assert_eq!(record.logs, expected.logs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_file_dataset_session() {
+        let path = "../examples/xor/training_data.dat";
+        let network = build_network();
+
+        let mut in_memory = DenseSession::new(
+            network.clone(),
+            0.5,
+            load_data(path),
+            vec![],
+            4,
+            None,
+            false,
+            Some(3),
+        )
+        .with_seed(4)
+        .with_validation_split(25);
+        let expected = in_memory.train();
+
+        let mut from_file = DenseSession::from_datasets(
+            network,
+            0.5,
+            FileDataset::open(path),
+            FileDataset::open(path),
+            4,
+            None,
+            false,
+            Some(3),
+        )
+        .with_seed(4)
+        .with_validation_split(25)
+        .with_prefetch(2);
+        let history = from_file.train();
+
+        assert_eq!(from_file.training_size(), 3);
+        for (record, expected) in history.epochs.iter().zip(&expected.epochs) {
+            assert_eq!(record.logs, expected.logs);
+        }
+        assert_eq!(
+            from_file.network().weights()[0].to_string(),
+            in_memory.network().weights()[0].to_string()
+        );
+        assert!(from_file.test() > 0.0);
+    }
+
+    #[test]
+    fn test_augmented_session() {
+        let network = build_network();
+        let noise = |seed: Option<u64>| {
+            let augmentation =
+                Augmentation::new(DenseShape::one_d(2)).with_transform(GaussianNoise::new(0.1));
+            match seed {
+                Some(seed) => augmentation.with_seed(seed),
+                None => augmentation,
+            }
+        };
+        let train = |augmentation: Option<Augmentation>, prefetch: usize| {
+            let mut session = DenseSession::new(
+                network.clone(),
+                0.5,
+                load_data("../examples/xor/training_data.dat"),
+                vec![],
+                5,
+                None,
+                false,
+                Some(2),
+            )
+            .with_seed(1)
+            .with_prefetch(prefetch);
+            if let Some(augmentation) = augmentation {
+                session = session.with_augmentation(augmentation);
+            }
+            session.train();
+            session.network().weights()[0].to_string()
+        };
+
+        let augmented = train(Some(noise(Some(2))), 0);
+        assert_eq!(augmented, train(Some(noise(Some(2))), 2));
+        assert_ne!(augmented, train(Some(noise(Some(3))), 0));
+        assert_ne!(augmented, train(None, 0));
+        // without a seed of its own the augmentation follows the session
+        assert_eq!(train(Some(noise(None)), 0), train(Some(noise(Some(1))), 0));
+    }
+}
@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod tuning_tests {
+    use bricks::activations::DenseActivation;
+    use bricks::losses::Loss;
+    use bricks::maths::Matrix;
+    use bricks::networks::DenseNetwork;
+    use bricks::sessions::{DenseSession, MonitorMode};
+    use bricks::shapes::DenseShape;
+    use bricks::tuning::{Params, SearchSpace, Tuner, Value};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // positive inputs and weights start every output above 0.5, far from
+    // the targets, so only learning can bring the loss down
+    fn build_session(params: &Params, epochs: usize) -> DenseSession {
+        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+        let shape = vec![
+            DenseShape::one_d(2),
+            DenseShape::one_d(3),
+            DenseShape::one_d(1),
+        ];
+        let network = DenseNetwork::new(activations, Loss::MeanSquaredError, shape);
+        let data = vec![
+            (Matrix::from(vec![1.0, 0.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![0.0, 1.0]), Matrix::from(vec![0.0])),
+            (Matrix::from(vec![1.0, 1.0]), Matrix::from(vec![0.0])),
+        ];
+        DenseSession::new(
+            network,
+            params.float("learning_rate"),
+            data.clone(),
+            vec![],
+            epochs,
+            None,
+            false,
+            Some(params.int("minibatch")),
+        )
+        .with_validation_data(data)
+    }
+
+    #[test]
+    fn test_grid() {
+        let space = SearchSpace::new()
+            .floats("learning_rate", vec![0.1, 1.0])
+            .ints("minibatch", vec![1, 2, 3])
+            .texts("activation", vec!["Sigmoid"]);
+        let grid = space.grid();
+
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[0].float("learning_rate"), 0.1);
+        assert_eq!(grid[1].int("minibatch"), 2);
+        assert_eq!(grid[3].float("learning_rate"), 1.0);
+        assert_eq!(grid[5].text("activation"), "Sigmoid");
+        assert_eq!(
+            grid[0].to_string(),
+            "activation=Sigmoid learning_rate=0.1 minibatch=1"
+        );
+    }
+
+    #[test]
+    fn test_sample() {
+        let space = SearchSpace::new()
+            .log_uniform("learning_rate", 1E-4, 1E-1)
+            .uniform("momentum", 0.5, 0.9);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..100 {
+            let params = space.sample(&mut rng);
+            assert!((1E-4..=1E-1).contains(&params.float("learning_rate")));
+            assert!((0.5..=0.9).contains(&params.float("momentum")));
+        }
+    }
+
+    #[test]
+    fn test_grid_search() {
+        let space = SearchSpace::new()
+            .floats("learning_rate", vec![0.0, 5.0])
+            .ints("minibatch", vec![3]);
+        let tuner = Tuner::new(space, "val_loss", MonitorMode::Min);
+        let results = tuner.grid_search(build_session, 200);
+
+        assert_eq!(results.trials.len(), 2);
+        assert_eq!(results.best_params().float("learning_rate"), 5.0);
+        assert!(results.trials[0].score <= results.trials[1].score);
+        assert_eq!(results.to_table().lines().count(), 3);
+        assert!(results
+            .trials
+            .iter()
+            .all(|trial| trial.history.test.is_empty()));
+    }
+
+    #[test]
+    fn test_diverging_trial() {
+        // an infinite learning rate diverges on the first update, before
+        // any epoch is logged
+        let space = SearchSpace::new()
+            .floats("learning_rate", vec![f64::INFINITY, 0.0, 5.0])
+            .ints("minibatch", vec![3]);
+        let tuner = Tuner::new(space, "val_loss", MonitorMode::Min);
+        let results = tuner.grid_search(build_session, 50);
+
+        assert_eq!(results.trials.len(), 3);
+        assert_eq!(results.best_params().float("learning_rate"), 5.0);
+        let failed = &results.trials[2];
+        assert!(failed.failed && failed.score.is_nan());
+        assert!(failed.history.is_empty());
+        assert!(results.trials[..2].iter().all(|trial| !trial.failed));
+
+        // a failed trial is not trained any longer
+        let results = tuner.successive_halving(build_session, 3, 1, 3, 2);
+        assert!(results
+            .trials
+            .iter()
+            .all(|trial| trial.epochs == 1 || !trial.failed));
+    }
+
+    #[test]
+    fn test_random_search_is_seeded() {
+        let space = SearchSpace::new()
+            .log_uniform("learning_rate", 1E-2, 1.0)
+            .ints("minibatch", vec![1, 3]);
+        let draw = |seed| {
+            let tuner = Tuner::new(space.clone(), "val_loss", MonitorMode::Min).with_seed(seed);
+            let results = tuner.random_search(build_session, 4, 1);
+            let mut params = results
+                .trials
+                .iter()
+                .map(|trial| trial.params.to_string())
+                .collect::<Vec<_>>();
+            params.sort();
+            params
+        };
+
+        assert_eq!(draw(11), draw(11));
+        assert_ne!(draw(11), draw(12));
+    }
+
+    #[test]
+    fn test_successive_halving() {
+        let space = SearchSpace::new()
+            .log_uniform("learning_rate", 1E-2, 5.0)
+            .ints("minibatch", vec![3]);
+        let tuner = Tuner::new(space, "val_loss", MonitorMode::Min).with_seed(5);
+        let results = tuner.successive_halving(build_session, 9, 1, 9, 3);
+
+        let epochs = results.trials.iter().map(|trial| trial.epochs);
+        assert_eq!(
+            epochs.collect::<Vec<_>>(),
+            [vec![9], vec![3; 3], vec![1; 9]].concat()
+        );
+        assert_eq!(results.best().epochs, 9);
+        assert_eq!(results.best().history.len(), 9);
+    }
+
+    #[test]
+    fn test_hyperband() {
+        let space = SearchSpace::new()
+            .log_uniform("learning_rate", 1E-2, 5.0)
+            .ints("minibatch", vec![1, 3]);
+        let tuner = Tuner::new(space, "val_loss", MonitorMode::Min).with_seed(5);
+        let results = tuner.hyperband(build_session, 9, 3);
+
+        // brackets of 9 + 3 + 1, 5 + 1 and 3 trials
+        assert_eq!(results.trials.len(), 22);
+        let full = results
+            .trials
+            .iter()
+            .filter(|trial| trial.epochs == 9)
+            .count();
+        assert_eq!(full, 5);
+        assert!(matches!(
+            results.best().params.get("minibatch"),
+            Value::Int(_)
+        ));
+    }
+}
@@ -1,9 +1,20 @@
 use bricks::activations::DenseActivation;
-use bricks::data::{load_data, split_data};
+use bricks::augmentation::{Augmentation, RandomRotation, RandomScale, RandomShift};
+use bricks::data::{load_data, load_mnist, ordered_split};
 use bricks::losses::Loss;
-use bricks::networks::{DenseNetwork, Network};
-use bricks::sessions::{DenseSession, Session};
+use bricks::metrics::{Accuracy, TopKAccuracy};
+use bricks::networks::DenseNetwork;
+use bricks::preprocessing::{save_model, MinMaxScaler, Pipeline};
+use bricks::sessions::{latest_checkpoint, DenseSession, ModelCheckpoint, Session};
 use bricks::shapes::DenseShape;
+use std::path::Path;
+
+// the MNIST training set, once decompressed, replaces small_data.dat when
+// found next to it
+const MNIST_IMAGES: &str = "train-images-idx3-ubyte";
+const MNIST_LABELS: &str = "train-labels-idx1-ubyte";
+// the scaler fitted on the first training, reused when resuming
+const PIPELINE: &str = "digit_reader_pipeline.save";
 
 pub fn train_network() {
     let activations = vec![
@@ -21,30 +32,56 @@ pub fn train_network() {
     ];
 
 
-    let mut network = DenseNetwork::new(
-        activations,
-        Loss::CrossEntropy,
-        shape,
-        None,
-    );
-    let data = load_data("small_data.dat");
-    let (training_data, testing_data) = split_data(data, 30);
+    let data = if Path::new(MNIST_IMAGES).exists() {
+        load_mnist(MNIST_IMAGES, MNIST_LABELS, true)
+    } else {
+        load_data("small_data.dat")
+    };
+    let (training_data, testing_data) = ordered_split(data, 30);
+    let pipeline = if Path::new(PIPELINE).exists() {
+        Pipeline::load(PIPELINE)
+    } else {
+        let mut pipeline = Pipeline::new().with_step(MinMaxScaler::new());
+        pipeline.fit_inputs(&training_data);
+        pipeline.save(PIPELINE);
+        pipeline
+    };
+    let training_data = pipeline.transform_inputs(&training_data);
+    let testing_data = pipeline.transform_inputs(&testing_data);
 
 
     println!("Data loaded!");
-    let mut session = DenseSession::new(
-        network,
-        1E-1,
-        training_data,
-        testing_data,
-        50,
-        Some(0.005),
-        true,
-        Some(100)
-    );
+    let session = match latest_checkpoint("checkpoints") {
+        Some(checkpoint) => {
+            println!("Resuming from {}", checkpoint.display());
+            DenseSession::resume(checkpoint.to_str().unwrap(), training_data, testing_data)
+        }
+        None => {
+            let network = DenseNetwork::new(activations, Loss::CrossEntropy, shape);
+            DenseSession::new(
+                network,
+                1E-1,
+                training_data,
+                testing_data,
+                50,
+                Some(0.005),
+                true,
+                Some(100)
+            )
+        }
+    };
+    let mut session = session
+        .with_callback(Box::new(ModelCheckpoint::new("checkpoints").every(5)))
+        .with_metrics(vec![Box::new(Accuracy::new()), Box::new(TopKAccuracy::new(3))])
+        .with_augmentation(
+            Augmentation::new(DenseShape::new(28, 28, 1))
+                .with_transform(RandomShift::new(2, 2))
+                .with_transform(RandomRotation::new(10.0))
+                .with_transform(RandomScale::new(0.9, 1.1)),
+        );
 
     println!("Launching session fitting!");
-    session.fit();
-    network = session.release_network();
-    network.save_network("digit_reader.save");
+    session.fit().save_csv("digit_reader_history.csv");
+    let network = session.release_network();
+    save_model("digit_reader_model", &network, &pipeline);
 }
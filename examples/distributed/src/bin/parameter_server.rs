@@ -0,0 +1,36 @@
+use bricks::data::load_data;
+use bricks::distributed::{ParameterServer, UpdateMode};
+use bricks::networks::Network;
+use bricks::sessions::{DenseSession, Session};
+use distributed::{digit_counter_network, DEFAULT_ADDRESS};
+use std::env;
+use std::net::TcpListener;
+
+// parameter_server [nb_workers] [sync | stale=<pushes>] [address]
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let nb_workers = args
+        .get(1)
+        .map_or(2, |n| n.parse().expect("Invalid number of workers"));
+    let mode = match args.get(2).map(String::as_str) {
+        None | Some("sync") => UpdateMode::Synchronous,
+        Some(mode) => match mode.strip_prefix("stale=").map(str::parse) {
+            Some(Ok(staleness)) => UpdateMode::BoundedStaleness(staleness),
+            _ => panic!("Unknown mode {}, expected sync or stale=<pushes>", mode),
+        },
+    };
+    let address = args.get(3).map_or(DEFAULT_ADDRESS, String::as_str);
+
+    let listener = TcpListener::bind(address).expect("Could not listen on the given address");
+    println!(
+        "Waiting for {} workers on {} ({:?})",
+        nb_workers, address, mode
+    );
+    let server = ParameterServer::new(digit_counter_network(), 0.5, mode);
+    let network = server.serve(&listener, nb_workers);
+
+    let testing_data = load_data("../digit_counter/training_data.dat");
+    let mut session = DenseSession::new(network, 0.5, vec![], testing_data, 0, None, false, None);
+    println!("Error value: {}", session.test());
+    session.release_network().save_network("distributed.save");
+}
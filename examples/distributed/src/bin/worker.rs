@@ -0,0 +1,26 @@
+use bricks::data::load_data;
+use bricks::distributed::Worker;
+use distributed::{digit_counter_network, DEFAULT_ADDRESS};
+use std::env;
+
+// worker <rank> <nb_workers> [address], each worker training on every
+// nb_workers-th sample starting from its rank
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let rank: usize = args.get(1).map_or(0, |n| n.parse().expect("Invalid rank"));
+    let nb_workers: usize = args
+        .get(2)
+        .map_or(2, |n| n.parse().expect("Invalid number of workers"));
+    let address = args.get(3).map_or(DEFAULT_ADDRESS, String::as_str);
+
+    let data = load_data("../digit_counter/training_data.dat");
+    let shard = data.into_iter().skip(rank).step_by(nb_workers).collect();
+
+    let mut worker =
+        Worker::connect(address, digit_counter_network(), shard, 2000, 4).with_seed(rank as u64);
+    let history = worker.train();
+    let loss = history
+        .last()
+        .map_or(f64::NAN, |record| record.logs["loss"]);
+    println!("Worker {} done, last loss: {}", rank, loss);
+}
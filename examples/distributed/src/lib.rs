@@ -0,0 +1,18 @@
+use bricks::activations::DenseActivation;
+use bricks::losses::Loss;
+use bricks::networks::DenseNetwork;
+use bricks::shapes::DenseShape;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+// the server and the workers must build networks of the same shape, the
+// workers then receiving the parameters of the server
+pub fn digit_counter_network() -> DenseNetwork {
+    let activations = vec![DenseActivation::Sigmoid, DenseActivation::Softmax];
+    let shape = vec![
+        DenseShape::one_d(4),
+        DenseShape::one_d(64),
+        DenseShape::one_d(16),
+    ];
+    DenseNetwork::new(activations, Loss::CategoricalCrossEntropy(0.0), shape)
+}
@@ -2,31 +2,46 @@ use bricks::activations::DenseActivation;
 use bricks::data::load_data;
 use bricks::losses::Loss;
 use bricks::networks::{DenseNetwork, Network};
-use bricks::sessions::{DenseSession, Session};
+use bricks::sessions::{latest_checkpoint, DenseSession, ModelCheckpoint, Session};
 use bricks::shapes::DenseShape;
+use std::env;
+use std::path::Path;
 
+// xor [resume]: tests the saved network, training one first when there is
+// none. With resume, the training goes on from the latest checkpoint.
 fn main() {
-
-    let mut network : DenseNetwork;
-    let save_exist = std::path::Path::new("xor.save").exists();
-    if !save_exist {
-        println!("Creating network");
-        let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
-        let shape = vec![DenseShape::one_d(2), DenseShape::one_d(16), DenseShape::one_d(1)];
-        network = DenseNetwork::new(activations, Loss::MeanSquaredError, shape, None);
-    }
-    else {
-        println!("Loading network from save");
-        network = DenseNetwork::load_network("xor.save");
-    }
+    let resume = env::args().nth(1).as_deref() == Some("resume");
 
     let training_data = load_data("training_data.dat");
 
     let testing_data = training_data.clone();
 
-    let mut session = DenseSession::new(network, 1E0, training_data, testing_data, 5000, Some(0.005), true, None);
+    if Path::new("xor.save").exists() && !resume {
+        println!("Loading network from save");
+        let network = DenseNetwork::load_network("xor.save");
+        let mut session = DenseSession::new(network, 1E0, training_data, testing_data, 5000, Some(0.005), true, None);
+        println!("Error value: {}", session.test());
+        return;
+    }
+
+    let checkpoint = if resume { latest_checkpoint("checkpoints") } else { None };
+    let session = match checkpoint {
+        Some(checkpoint) => {
+            println!("Resuming from {}", checkpoint.display());
+            DenseSession::resume(checkpoint.to_str().unwrap(), training_data, testing_data)
+        }
+        None => {
+            println!("Creating network");
+            let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+            let shape = vec![DenseShape::one_d(2), DenseShape::one_d(16), DenseShape::one_d(1)];
+            let network = DenseNetwork::new(activations, Loss::MeanSquaredError, shape);
+            DenseSession::new(network, 1E0, training_data, testing_data, 5000, Some(0.005), true, None)
+        }
+    };
+    let mut session = session.with_callback(Box::new(ModelCheckpoint::new("checkpoints").every(500)));
 
-    println!("Error value: {}", if !save_exist {session.fit()} else {session.test()});
-    network = session.release_network();
+    session.train();
+    println!("Error value: {}", session.test());
+    let network = session.release_network();
     network.save_network("xor.save");
 }